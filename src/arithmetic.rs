@@ -0,0 +1,455 @@
+use crate::bytecode::*;
+use crate::constants::JAVA_LANG_ARITHMETIC_EXCEPTION;
+use crate::exception::Throwable;
+use crate::values::Value;
+
+// the semantics of the numeric instructions of JVMS chapter 6: int and long arithmetic wraps around,
+// float and double follow IEEE 754 round to nearest, and conversions to int or long saturate
+
+fn division_by_zero() -> Throwable {
+    Throwable::new(JAVA_LANG_ARITHMETIC_EXCEPTION, "/ by zero")
+}
+
+/// `value1 op value2` of iadd, isub, imul, idiv, irem, ishl, ishr, iushr, iand, ior and ixor
+pub fn int_binary(opcode: u8, value1: i32, value2: i32) -> Result<i32, Throwable> {
+    Ok(match opcode {
+        IADD => value1.wrapping_add(value2),
+        ISUB => value1.wrapping_sub(value2),
+        IMUL => value1.wrapping_mul(value2),
+        // Integer.MIN_VALUE / -1 overflows to Integer.MIN_VALUE, and the remainder is 0
+        IDIV if value2 == 0 => return Err(division_by_zero()),
+        IDIV => value1.wrapping_div(value2),
+        IREM if value2 == 0 => return Err(division_by_zero()),
+        IREM => value1.wrapping_rem(value2),
+        // only the low 5 bits are the shift distance
+        ISHL => value1.wrapping_shl(value2 as u32 & 0x1f),
+        ISHR => value1.wrapping_shr(value2 as u32 & 0x1f),
+        IUSHR => ((value1 as u32) >> (value2 as u32 & 0x1f)) as i32,
+        IAND => value1 & value2,
+        IOR => value1 | value2,
+        IXOR => value1 ^ value2,
+        _ => unreachable!("int arithmetic opcode 0x{:02x}", opcode)
+    })
+}
+
+/// `value1 op value2` of ladd, lsub, lmul, ldiv, lrem, land, lor and lxor
+pub fn long_binary(opcode: u8, value1: i64, value2: i64) -> Result<i64, Throwable> {
+    Ok(match opcode {
+        LADD => value1.wrapping_add(value2),
+        LSUB => value1.wrapping_sub(value2),
+        LMUL => value1.wrapping_mul(value2),
+        LDIV if value2 == 0 => return Err(division_by_zero()),
+        LDIV => value1.wrapping_div(value2),
+        LREM if value2 == 0 => return Err(division_by_zero()),
+        LREM => value1.wrapping_rem(value2),
+        LAND => value1 & value2,
+        LOR => value1 | value2,
+        LXOR => value1 ^ value2,
+        _ => unreachable!("long arithmetic opcode 0x{:02x}", opcode)
+    })
+}
+
+/// lshl, lshr and lushr, where the distance is an int of which only the low 6 bits are used
+pub fn long_shift(opcode: u8, value: i64, distance: i32) -> i64 {
+    let distance = distance as u32 & 0x3f;
+    match opcode {
+        LSHL => value.wrapping_shl(distance),
+        LSHR => value.wrapping_shr(distance),
+        LUSHR => ((value as u64) >> distance) as i64,
+        _ => unreachable!("long shift opcode 0x{:02x}", opcode)
+    }
+}
+
+/// fadd, fsub, fmul, fdiv and frem, the remainder truncates the quotient as C's fmod rather than IEEE 754 remainder
+pub fn float_binary(opcode: u8, value1: f32, value2: f32) -> f32 {
+    match opcode {
+        FADD => value1 + value2,
+        FSUB => value1 - value2,
+        FMUL => value1 * value2,
+        FDIV => value1 / value2,
+        FREM => value1 % value2,
+        _ => unreachable!("float arithmetic opcode 0x{:02x}", opcode)
+    }
+}
+
+/// dadd, dsub, dmul, ddiv and drem
+pub fn double_binary(opcode: u8, value1: f64, value2: f64) -> f64 {
+    match opcode {
+        DADD => value1 + value2,
+        DSUB => value1 - value2,
+        DMUL => value1 * value2,
+        DDIV => value1 / value2,
+        DREM => value1 % value2,
+        _ => unreachable!("double arithmetic opcode 0x{:02x}", opcode)
+    }
+}
+
+/// ineg, lneg, fneg and dneg, negating Integer.MIN_VALUE overflows to itself and negating 0.0 is -0.0
+pub fn negate(opcode: u8, value: Value) -> Value {
+    match (opcode, value) {
+        (INEG, Value::Int(value)) => Value::Int(value.wrapping_neg()),
+        (LNEG, Value::Long(value)) => Value::Long(value.wrapping_neg()),
+        (FNEG, Value::Float(value)) => Value::Float(-value),
+        (DNEG, Value::Double(value)) => Value::Double(-value),
+        (opcode, _) => unreachable!("negation opcode 0x{:02x} on a value of another type", opcode)
+    }
+}
+
+/// the conversions i2l through i2s, narrowing to int or long saturates and NaN becomes 0
+pub fn convert(opcode: u8, value: Value) -> Value {
+    match (opcode, value) {
+        (I2L, Value::Int(value)) => Value::Long(value as i64),
+        (I2F, Value::Int(value)) => Value::Float(value as f32),
+        (I2D, Value::Int(value)) => Value::Double(value as f64),
+        (L2I, Value::Long(value)) => Value::Int(value as i32),
+        (L2F, Value::Long(value)) => Value::Float(value as f32),
+        (L2D, Value::Long(value)) => Value::Double(value as f64),
+        // Rust's float to integer casts saturate and map NaN to 0 exactly as JVMS 2.8.3
+        (F2I, Value::Float(value)) => Value::Int(value as i32),
+        (F2L, Value::Float(value)) => Value::Long(value as i64),
+        (F2D, Value::Float(value)) => Value::Double(value as f64),
+        (D2I, Value::Double(value)) => Value::Int(value as i32),
+        (D2L, Value::Double(value)) => Value::Long(value as i64),
+        (D2F, Value::Double(value)) => Value::Float(value as f32),
+        // sign extended back to int, except char which is zero extended
+        (I2B, Value::Int(value)) => Value::Int(value as i8 as i32),
+        (I2C, Value::Int(value)) => Value::Int(value as u16 as i32),
+        (I2S, Value::Int(value)) => Value::Int(value as i16 as i32),
+        (opcode, _) => unreachable!("conversion opcode 0x{:02x} on a value of another type", opcode)
+    }
+}
+
+pub fn lcmp(value1: i64, value2: i64) -> i32 {
+    value1.cmp(&value2) as i32
+}
+
+/// fcmpl and fcmpg differ only in the result of comparing NaN, -1 and 1 respectively
+pub fn float_compare(opcode: u8, value1: f32, value2: f32) -> i32 {
+    match value1.partial_cmp(&value2) {
+        Some(ordering) => ordering as i32,
+        None if opcode == FCMPG => 1,
+        None => -1
+    }
+}
+
+/// dcmpl and dcmpg
+pub fn double_compare(opcode: u8, value1: f64, value2: f64) -> i32 {
+    match value1.partial_cmp(&value2) {
+        Some(ordering) => ordering as i32,
+        None if opcode == DCMPG => 1,
+        None => -1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAN_F: f32 = f32::NAN;
+    const NAN_D: f64 = f64::NAN;
+
+    // the same value, NaNs are equal and 0.0 is not -0.0
+    fn same_value(actual: &Value, expected: &Value) -> bool {
+        match (actual, expected) {
+            (Value::Int(a), Value::Int(e)) => a == e,
+            (Value::Long(a), Value::Long(e)) => a == e,
+            (Value::Float(a), Value::Float(e)) => a.to_bits() == e.to_bits() || (a.is_nan() && e.is_nan()),
+            (Value::Double(a), Value::Double(e)) => a.to_bits() == e.to_bits() || (a.is_nan() && e.is_nan()),
+            _ => false
+        }
+    }
+
+    #[test]
+    fn int_arithmetic() {
+        // none is an ArithmeticException
+        let cases: &[(u8, i32, i32, Option<i32>)] = &[
+            (IADD, 1, 2, Some(3)),
+            (IADD, i32::MAX, 1, Some(i32::MIN)),
+            (IADD, i32::MIN, -1, Some(i32::MAX)),
+            (ISUB, 1, 2, Some(-1)),
+            (ISUB, i32::MIN, 1, Some(i32::MAX)),
+            (IMUL, 6, -7, Some(-42)),
+            (IMUL, 0x10000, 0x10000, Some(0)),
+            (IMUL, i32::MAX, 2, Some(-2)),
+            (IMUL, i32::MIN, -1, Some(i32::MIN)),
+            (IDIV, 7, 2, Some(3)),
+            (IDIV, -7, 2, Some(-3)),
+            (IDIV, 7, -2, Some(-3)),
+            (IDIV, i32::MIN, -1, Some(i32::MIN)),
+            (IDIV, 1, 0, None),
+            (IDIV, 0, 0, None),
+            (IREM, 7, 2, Some(1)),
+            (IREM, -7, 2, Some(-1)),
+            (IREM, 7, -2, Some(1)),
+            (IREM, i32::MIN, -1, Some(0)),
+            (IREM, 1, 0, None),
+            (ISHL, 1, 31, Some(i32::MIN)),
+            (ISHL, 1, 32, Some(1)),
+            (ISHL, 1, 33, Some(2)),
+            (ISHL, 1, -1, Some(i32::MIN)),
+            (ISHR, -8, 1, Some(-4)),
+            (ISHR, i32::MIN, 31, Some(-1)),
+            (ISHR, -1, 32, Some(-1)),
+            (ISHR, 16, 36, Some(1)),
+            (IUSHR, -8, 1, Some(0x7fff_fffc)),
+            (IUSHR, -1, 28, Some(0xf)),
+            (IUSHR, -1, 32, Some(-1)),
+            (IUSHR, i32::MIN, 63, Some(1)),
+            (IAND, 0b1100, 0b1010, Some(0b1000)),
+            (IOR, 0b1100, 0b1010, Some(0b1110)),
+            (IXOR, 0b1100, 0b1010, Some(0b0110)),
+            (IXOR, -1, 0x0f0f_0f0f, Some(0xf0f0_f0f0_u32 as i32))
+        ];
+        for &(opcode, value1, value2, expected) in cases {
+            let actual = int_binary(opcode, value1, value2);
+            match expected {
+                Some(expected) => assert_eq!(actual.ok(), Some(expected), "0x{:02x} {} {}", opcode, value1, value2),
+                None => assert_eq!(actual.err().map(|throwable| throwable.to_string()),
+                    Some("java.lang.ArithmeticException: / by zero".to_string()), "0x{:02x} {} {}", opcode, value1, value2)
+            }
+        }
+    }
+
+    #[test]
+    fn negation() {
+        let cases: Vec<(u8, Value, Value)> = vec![
+            (INEG, Value::Int(1), Value::Int(-1)),
+            (INEG, Value::Int(0), Value::Int(0)),
+            (INEG, Value::Int(i32::MAX), Value::Int(-i32::MAX)),
+            (INEG, Value::Int(i32::MIN), Value::Int(i32::MIN)),
+            (LNEG, Value::Long(-5), Value::Long(5)),
+            (LNEG, Value::Long(i64::MIN), Value::Long(i64::MIN)),
+            (FNEG, Value::Float(0.0), Value::Float(-0.0)),
+            (FNEG, Value::Float(-0.0), Value::Float(0.0)),
+            (FNEG, Value::Float(f32::INFINITY), Value::Float(f32::NEG_INFINITY)),
+            (FNEG, Value::Float(NAN_F), Value::Float(NAN_F)),
+            (DNEG, Value::Double(0.0), Value::Double(-0.0)),
+            (DNEG, Value::Double(1.5), Value::Double(-1.5)),
+            (DNEG, Value::Double(NAN_D), Value::Double(NAN_D))
+        ];
+        for (opcode, value, expected) in cases {
+            let actual = negate(opcode, value);
+            assert!(same_value(&actual, &expected), "0x{:02x} {:?} expected {:?}", opcode, bits(&actual), bits(&expected));
+        }
+    }
+
+    #[test]
+    fn long_arithmetic() {
+        let cases: &[(u8, i64, i64, Option<i64>)] = &[
+            (LADD, 1, 2, Some(3)),
+            (LADD, i64::MAX, 1, Some(i64::MIN)),
+            (LSUB, i64::MIN, 1, Some(i64::MAX)),
+            (LMUL, 1 << 32, 1 << 32, Some(0)),
+            (LMUL, i64::MIN, -1, Some(i64::MIN)),
+            (LDIV, -7, 2, Some(-3)),
+            (LDIV, i64::MIN, -1, Some(i64::MIN)),
+            (LDIV, 1, 0, None),
+            (LREM, -7, 2, Some(-1)),
+            (LREM, 7, -2, Some(1)),
+            (LREM, i64::MIN, -1, Some(0)),
+            (LREM, 1, 0, None),
+            (LAND, 0b1100, 0b1010, Some(0b1000)),
+            (LOR, 0b1100, 0b1010, Some(0b1110)),
+            (LXOR, -1, 0, Some(-1))
+        ];
+        for &(opcode, value1, value2, expected) in cases {
+            let actual = long_binary(opcode, value1, value2);
+            match expected {
+                Some(expected) => assert_eq!(actual.ok(), Some(expected), "0x{:02x} {} {}", opcode, value1, value2),
+                None => assert_eq!(actual.err().map(|throwable| throwable.to_string()),
+                    Some("java.lang.ArithmeticException: / by zero".to_string()), "0x{:02x} {} {}", opcode, value1, value2)
+            }
+        }
+    }
+
+    #[test]
+    fn long_shifts() {
+        let cases: &[(u8, i64, i32, i64)] = &[
+            (LSHL, 1, 63, i64::MIN),
+            (LSHL, 1, 64, 1),
+            (LSHL, 1, 65, 2),
+            (LSHL, 1, 32, 1 << 32),
+            (LSHL, 1, -1, i64::MIN),
+            (LSHR, -8, 1, -4),
+            (LSHR, i64::MIN, 63, -1),
+            (LSHR, -1, 64, -1),
+            (LUSHR, -1, 60, 0xf),
+            (LUSHR, -1, 64, -1),
+            (LUSHR, i64::MIN, 127, 1)
+        ];
+        for &(opcode, value, distance, expected) in cases {
+            assert_eq!(long_shift(opcode, value, distance), expected, "0x{:02x} {} {}", opcode, value, distance);
+        }
+    }
+
+    #[test]
+    fn float_arithmetic() {
+        let cases: &[(u8, f32, f32, f32)] = &[
+            (FADD, 0.1, 0.2, 0.3),
+            (FADD, f32::MAX, f32::MAX, f32::INFINITY),
+            (FADD, f32::INFINITY, f32::NEG_INFINITY, NAN_F),
+            (FADD, 0.0, -0.0, 0.0),
+            (FADD, -0.0, -0.0, -0.0),
+            (FSUB, 0.0, 0.0, 0.0),
+            (FSUB, -0.0, 0.0, -0.0),
+            (FMUL, 1e30, 1e30, f32::INFINITY),
+            (FMUL, -1.0, 0.0, -0.0),
+            (FMUL, f32::INFINITY, 0.0, NAN_F),
+            (FDIV, 1.0, 0.0, f32::INFINITY),
+            (FDIV, -1.0, 0.0, f32::NEG_INFINITY),
+            (FDIV, 1.0, -0.0, f32::NEG_INFINITY),
+            (FDIV, 0.0, 0.0, NAN_F),
+            (FDIV, 1.0, 3.0, 0.333_333_34),
+            (FREM, 5.5, 2.0, 1.5),
+            (FREM, -5.5, 2.0, -1.5),
+            (FREM, 5.5, -2.0, 1.5),
+            (FREM, 1.0, 0.0, NAN_F),
+            (FREM, f32::INFINITY, 2.0, NAN_F),
+            (FREM, 2.0, f32::INFINITY, 2.0),
+            (FREM, -0.0, 1.0, -0.0),
+            // fmod truncates the quotient where IEEE 754 remainder rounds it, 3 rather than 2
+            (FREM, 7.0, 2.5, 2.0),
+            (FADD, NAN_F, 1.0, NAN_F)
+        ];
+        for &(opcode, value1, value2, expected) in cases {
+            let actual = float_binary(opcode, value1, value2);
+            assert!(same_value(&Value::Float(actual), &Value::Float(expected)),
+                "0x{:02x} {} {} = {} expected {}", opcode, value1, value2, actual, expected);
+        }
+    }
+
+    #[test]
+    fn double_arithmetic() {
+        let cases: &[(u8, f64, f64, f64)] = &[
+            (DADD, 0.1, 0.2, 0.300_000_000_000_000_04),
+            (DADD, f64::MAX, f64::MAX, f64::INFINITY),
+            (DADD, -0.0, -0.0, -0.0),
+            (DSUB, -0.0, 0.0, -0.0),
+            (DMUL, -1.0, 0.0, -0.0),
+            (DMUL, f64::INFINITY, 0.0, NAN_D),
+            (DDIV, 1.0, 0.0, f64::INFINITY),
+            (DDIV, 1.0, -0.0, f64::NEG_INFINITY),
+            (DDIV, 0.0, 0.0, NAN_D),
+            (DDIV, f64::MIN_POSITIVE, 2.0, 1.112_536_929_253_600_7e-308),
+            (DREM, 5.5, 2.0, 1.5),
+            (DREM, -5.5, 2.0, -1.5),
+            (DREM, 5.5, -2.0, 1.5),
+            (DREM, 7.0, 2.5, 2.0),
+            (DREM, 1.0, 0.0, NAN_D),
+            (DREM, f64::NEG_INFINITY, 1.0, NAN_D),
+            (DREM, 1.0, f64::INFINITY, 1.0),
+            (DREM, 1e300, 3.0, 0.0)
+        ];
+        for &(opcode, value1, value2, expected) in cases {
+            let actual = double_binary(opcode, value1, value2);
+            assert!(same_value(&Value::Double(actual), &Value::Double(expected)),
+                "0x{:02x} {} {} = {} expected {}", opcode, value1, value2, actual, expected);
+        }
+    }
+
+    #[test]
+    fn conversions() {
+        let cases: Vec<(u8, Value, Value)> = vec![
+            (I2L, Value::Int(-1), Value::Long(-1)),
+            (I2L, Value::Int(i32::MIN), Value::Long(i32::MIN as i64)),
+            (I2F, Value::Int(16_777_217), Value::Float(16_777_216.0)),
+            (I2F, Value::Int(i32::MAX), Value::Float(2_147_483_648.0)),
+            (I2D, Value::Int(i32::MIN), Value::Double(-2_147_483_648.0)),
+            (L2I, Value::Long(0x1_2345_6789), Value::Int(0x2345_6789)),
+            (L2I, Value::Long(0xffff_ffff), Value::Int(-1)),
+            (L2I, Value::Long(i64::MIN), Value::Int(0)),
+            (L2F, Value::Long(i64::MAX), Value::Float(9.223_372e18)),
+            (L2D, Value::Long((1 << 53) + 1), Value::Double(9_007_199_254_740_992.0)),
+            (F2I, Value::Float(1.9), Value::Int(1)),
+            (F2I, Value::Float(-1.9), Value::Int(-1)),
+            (F2I, Value::Float(NAN_F), Value::Int(0)),
+            (F2I, Value::Float(1e10), Value::Int(i32::MAX)),
+            (F2I, Value::Float(-1e10), Value::Int(i32::MIN)),
+            (F2I, Value::Float(f32::INFINITY), Value::Int(i32::MAX)),
+            (F2I, Value::Float(f32::NEG_INFINITY), Value::Int(i32::MIN)),
+            (F2L, Value::Float(NAN_F), Value::Long(0)),
+            (F2L, Value::Float(1e20), Value::Long(i64::MAX)),
+            (F2L, Value::Float(-1e20), Value::Long(i64::MIN)),
+            (F2L, Value::Float(-2.5), Value::Long(-2)),
+            (F2D, Value::Float(0.1), Value::Double(0.100_000_001_490_116_12)),
+            (F2D, Value::Float(-0.0), Value::Double(-0.0)),
+            (F2D, Value::Float(NAN_F), Value::Double(NAN_D)),
+            (D2I, Value::Double(NAN_D), Value::Int(0)),
+            (D2I, Value::Double(2_147_483_647.9), Value::Int(i32::MAX)),
+            (D2I, Value::Double(2_147_483_648.0), Value::Int(i32::MAX)),
+            (D2I, Value::Double(-2_147_483_649.0), Value::Int(i32::MIN)),
+            (D2I, Value::Double(-0.9), Value::Int(0)),
+            (D2L, Value::Double(NAN_D), Value::Long(0)),
+            (D2L, Value::Double(1e19), Value::Long(i64::MAX)),
+            (D2L, Value::Double(-1e19), Value::Long(i64::MIN)),
+            (D2L, Value::Double(f64::NEG_INFINITY), Value::Long(i64::MIN)),
+            (D2L, Value::Double(4_503_599_627_370_495.5), Value::Long(4_503_599_627_370_495)),
+            (D2F, Value::Double(1e40), Value::Float(f32::INFINITY)),
+            (D2F, Value::Double(-1e40), Value::Float(f32::NEG_INFINITY)),
+            (D2F, Value::Double(1e-50), Value::Float(0.0)),
+            (D2F, Value::Double(-1e-50), Value::Float(-0.0)),
+            (D2F, Value::Double(0.1), Value::Float(0.1)),
+            (D2F, Value::Double(NAN_D), Value::Float(NAN_F)),
+            (I2B, Value::Int(0x7f), Value::Int(127)),
+            (I2B, Value::Int(0x80), Value::Int(-128)),
+            (I2B, Value::Int(0x1ff), Value::Int(-1)),
+            (I2B, Value::Int(-129), Value::Int(127)),
+            (I2C, Value::Int(-1), Value::Int(0xffff)),
+            (I2C, Value::Int(0x1_0041), Value::Int(0x41)),
+            (I2C, Value::Int(0x8000), Value::Int(0x8000)),
+            (I2S, Value::Int(0x8000), Value::Int(-32768)),
+            (I2S, Value::Int(0xffff), Value::Int(-1)),
+            (I2S, Value::Int(0x1_7fff), Value::Int(32767))
+        ];
+        for (opcode, value, expected) in cases {
+            let actual = convert(opcode, value);
+            assert!(same_value(&actual, &expected), "0x{:02x} {:?} expected {:?}", opcode, bits(&actual), bits(&expected));
+        }
+    }
+
+    fn bits(value: &Value) -> (&'static str, u64) {
+        match value {
+            Value::Int(value) => ("int", *value as u32 as u64),
+            Value::Long(value) => ("long", *value as u64),
+            Value::Float(value) => ("float", value.to_bits() as u64),
+            Value::Double(value) => ("double", value.to_bits()),
+            _ => ("other", 0)
+        }
+    }
+
+    #[test]
+    fn comparisons() {
+        let cases: &[(i64, i64, i32)] = &[(1, 2, -1), (2, 1, 1), (3, 3, 0), (i64::MIN, i64::MAX, -1), (-1, 0, -1)];
+        for &(value1, value2, expected) in cases {
+            assert_eq!(lcmp(value1, value2), expected, "lcmp {} {}", value1, value2);
+        }
+        let cases: &[(u8, f32, f32, i32)] = &[
+            (FCMPL, 1.0, 2.0, -1),
+            (FCMPG, 1.0, 2.0, -1),
+            (FCMPL, 2.0, 1.0, 1),
+            (FCMPL, 0.0, -0.0, 0),
+            (FCMPG, f32::NEG_INFINITY, f32::INFINITY, -1),
+            (FCMPL, NAN_F, 1.0, -1),
+            (FCMPG, NAN_F, 1.0, 1),
+            (FCMPL, 1.0, NAN_F, -1),
+            (FCMPG, 1.0, NAN_F, 1),
+            (FCMPL, NAN_F, NAN_F, -1),
+            (FCMPG, NAN_F, NAN_F, 1)
+        ];
+        for &(opcode, value1, value2, expected) in cases {
+            assert_eq!(float_compare(opcode, value1, value2), expected, "0x{:02x} {} {}", opcode, value1, value2);
+        }
+        let cases: &[(u8, f64, f64, i32)] = &[
+            (DCMPL, 1.0, 2.0, -1),
+            (DCMPG, 2.0, 1.0, 1),
+            (DCMPL, -0.0, 0.0, 0),
+            (DCMPL, NAN_D, 0.0, -1),
+            (DCMPG, NAN_D, 0.0, 1),
+            (DCMPL, f64::INFINITY, NAN_D, -1),
+            (DCMPG, f64::INFINITY, NAN_D, 1)
+        ];
+        for &(opcode, value1, value2, expected) in cases {
+            assert_eq!(double_compare(opcode, value1, value2), expected, "0x{:02x} {} {}", opcode, value1, value2);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::rc::Rc;
+use crate::bytecode::u2;
+use crate::constants::*;
+use crate::exception::Throwable;
+use crate::interpreter::{method_name, resolve_method};
+use crate::lambda::{self, JAVA_LANG_INVOKE_LAMBDA_METAFACTORY};
+use crate::string_concat::{self, Concat, JAVA_LANG_INVOKE_STRING_CONCAT_FACTORY};
+use crate::thread::JavaThread;
+use crate::types::{Class, Constant, Method};
+use crate::values::Value;
+use crate::vm::Vm;
+
+/// the target of a linked invokedynamic instruction. The VM implements the bootstrap methods it supports rather than
+/// running them, there are no java.lang.invoke.CallSite objects.
+pub enum CallSite {
+    // LambdaMetafactory: an instance of the class spun for the lambda captures the arguments, the same one if there are none
+    Lambda(Rc<Class>),
+    // StringConcatFactory: a new string of the arguments as the recipe of the call site lays them out
+    Concat(Concat)
+}
+
+impl Vm {
+    /// the call site of the invokedynamic instruction at `pc` of `method`, linked when it is first executed.
+    /// If linking fails, every later execution throws the same error (JVMS 5.4.3.6).
+    pub fn call_site(&mut self, method: &Rc<Method>, pc: usize) -> Result<Rc<CallSite>, Throwable> {
+        if let Some(linked) = method.call_sites.borrow().get(&pc) {
+            return linked.as_ref().map(Rc::clone).map_err(|error| self.resolution_error(error));
+        }
+        let linked = self.link_call_site(&method.class(), u2(method.code(), pc + 1)).map(Rc::new);
+        let cached = linked.as_ref().map(Rc::clone).map_err(|error| self.resolution_error(error));
+        method.call_sites.borrow_mut().insert(pc, cached);
+        linked
+    }
+
+    fn link_call_site(&mut self, class: &Rc<Class>, index: u16) -> Result<CallSite, Throwable> {
+        let (bootstrap_method_attr_index, name, descriptor) = match &class.constant_pool[index as usize] {
+            Constant::InvokeDynamic { bootstrap_method_attr_index, name, descriptor } =>
+                (*bootstrap_method_attr_index as usize, name.clone(), descriptor.clone()),
+            _ => panic!("invokedynamic expected at constant pool index {}", index)
+        };
+        let bootstrap = class.bootstrap_methods.get(bootstrap_method_attr_index).ok_or_else(|| Throwable::new(JAVA_LANG_CLASS_FORMAT_ERROR,
+            format!("Short length on BootstrapMethods in class file {}", class.name)))?;
+        let reference_index = match class.constant_pool[bootstrap.method_handle as usize] {
+            Constant::MethodHandle { reference_kind: JVM_REF_INVOKE_STATIC, reference_index } => reference_index,
+            _ => return Err(Throwable::new(JAVA_LANG_BOOTSTRAP_METHOD_ERROR, format!(
+                "bootstrap method at index {} of {} is not a static method", bootstrap_method_attr_index, class.name.replace('/', "."))))
+        };
+        let (_, bootstrap_method) = resolve_method(self, class, reference_index)?;
+        match (bootstrap_method.class().name.as_str(), bootstrap_method.name()) {
+            (JAVA_LANG_INVOKE_LAMBDA_METAFACTORY, "metafactory" | "altMetafactory") =>
+                Ok(CallSite::Lambda(lambda::spin(self, class, &name, &descriptor, &bootstrap.arguments)?)),
+            (JAVA_LANG_INVOKE_STRING_CONCAT_FACTORY, "makeConcatWithConstants" | "makeConcat") =>
+                Ok(CallSite::Concat(string_concat::link(class, &descriptor, &bootstrap.arguments, bootstrap_method.name() == "makeConcatWithConstants")?)),
+            _ => Err(Throwable::new(JAVA_LANG_BOOTSTRAP_METHOD_ERROR, format!(
+                "bootstrap method {} is not supported", method_name(&bootstrap_method))))
+        }
+    }
+
+    /// invoke the target of `call_site` with the arguments on the operand stack of `thread`
+    pub fn invoke_call_site(&mut self, thread: &mut JavaThread, call_site: &CallSite) -> Result<Option<Value>, Throwable> {
+        match call_site {
+            CallSite::Lambda(class) => lambda::new_lambda(self, thread, class).map(Some),
+            CallSite::Concat(concat) => concat.invoke(self, thread).map(Some)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_builder::{test_vm, ClassBuilder, Code};
+    use crate::class_loader::APP_LOADER;
+    use crate::vm::VmOptions;
+
+    const INVOKEDYNAMIC: u8 = 0xba;
+    const RETURN: u8 = 0xb1;
+
+    #[test]
+    fn failed_linking_is_rethrown() {
+        let mut vm = test_vm(VmOptions::default());
+        let mut builder = ClassBuilder::new("C", Some(JAVA_LANG_OBJECT));
+        // the class has no BootstrapMethods attribute
+        let index = builder.invoke_dynamic(0, "run", "()V");
+        let code = [&[INVOKEDYNAMIC][..], &index.to_be_bytes(), &[0, 0, RETURN]].concat();
+        let class = builder.method(ACC_STATIC, "m", "()V", Some(Code::new(0, 0, code))).define(&mut vm, APP_LOADER);
+        let method = class.methods[0].clone();
+        for _ in 0..2 {
+            let error = vm.call_site(&method, 0).err().expect("linking fails");
+            assert_eq!(error.class_name, JAVA_LANG_CLASS_FORMAT_ERROR);
+            assert_eq!(error.message, "Short length on BootstrapMethods in class file C");
+            // the error is cached rather than linking again
+            assert!(method.call_sites.borrow()[&0].is_err());
+        }
+    }
+}
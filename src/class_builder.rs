@@ -0,0 +1,240 @@
+//! class files written by the tests, with the constants, fields, methods and code they need
+
+use std::rc::Rc;
+use crate::class_loader::{ClassLoaderId, ClassLoaders, BOOTSTRAP_LOADER, CLASS_FILE_MAGIC};
+use crate::class_path::ClassPath;
+use crate::constants::*;
+use crate::types::Class;
+use crate::vm::{Vm, VmOptions};
+
+/// the Code attribute of a method
+pub struct Code {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub code: Vec<u8>,
+    // start_pc, end_pc, handler_pc and catch_type
+    pub exception_table: Vec<[u16; 4]>,
+    // the number of entries and the entries of the StackMapTable attribute
+    pub stack_map_table: Option<(u16, Vec<u8>)>
+}
+
+impl Code {
+    pub fn new(max_stack: u16, max_locals: u16, code: Vec<u8>) -> Self {
+        Code { max_stack, max_locals, code, exception_table: vec![], stack_map_table: None }
+    }
+}
+
+pub struct ClassBuilder {
+    name: String,
+    constants: Vec<Vec<u8>>,
+    major_version: u16,
+    access_flags: u16,
+    this_class: u16,
+    super_class: u16,
+    interfaces: Vec<u16>,
+    fields: Vec<Vec<u8>>,
+    methods: Vec<Vec<u8>>,
+    attributes: Vec<Vec<u8>>
+}
+
+impl ClassBuilder {
+    /// a public class of version 52 extending `super_class`, none only for java/lang/Object
+    pub fn new(name: &str, super_class: Option<&str>) -> Self {
+        let mut builder = ClassBuilder {
+            name: name.to_string(),
+            constants: vec![],
+            major_version: 52,
+            access_flags: ACC_PUBLIC | ACC_SUPER,
+            this_class: 0,
+            super_class: 0,
+            interfaces: vec![],
+            fields: vec![],
+            methods: vec![],
+            attributes: vec![]
+        };
+        builder.this_class = builder.class(name);
+        builder.super_class = super_class.map_or(0, |super_class| builder.class(super_class));
+        builder
+    }
+
+    pub fn major_version(mut self, major_version: u16) -> Self {
+        self.major_version = major_version;
+        self
+    }
+
+    pub fn access_flags(mut self, access_flags: u16) -> Self {
+        self.access_flags = access_flags;
+        self
+    }
+
+    pub fn interface(mut self, name: &str) -> Self {
+        let interface = self.class(name);
+        self.interfaces.push(interface);
+        self
+    }
+
+    fn constant(&mut self, entry: Vec<u8>) -> u16 {
+        let index = self.constants.iter().position(|constant| *constant == entry).unwrap_or_else(|| {
+            self.constants.push(entry);
+            self.constants.len() - 1
+        });
+        index as u16 + 1
+    }
+
+    pub fn utf8(&mut self, value: &str) -> u16 {
+        self.constant([&[JVM_TAG_UTF8][..], &(value.len() as u16).to_be_bytes(), value.as_bytes()].concat())
+    }
+
+    /// a Utf8 constant of modified UTF-8 bytes, e.g. with NUL as 0xc0 0x80
+    #[allow(dead_code)]
+    pub fn utf8_bytes(&mut self, bytes: &[u8]) -> u16 {
+        self.constant([&[JVM_TAG_UTF8][..], &(bytes.len() as u16).to_be_bytes(), bytes].concat())
+    }
+
+    pub fn class(&mut self, name: &str) -> u16 {
+        let name = self.utf8(name);
+        self.constant([&[JVM_TAG_CLASS][..], &name.to_be_bytes()].concat())
+    }
+
+    #[allow(dead_code)]
+    pub fn string(&mut self, value: &str) -> u16 {
+        let value = self.utf8(value);
+        self.constant([&[JVM_TAG_STRING][..], &value.to_be_bytes()].concat())
+    }
+
+    pub fn integer(&mut self, value: i32) -> u16 {
+        self.constant([&[JVM_TAG_INTEGER][..], &value.to_be_bytes()].concat())
+    }
+
+    fn member_ref(&mut self, tag: u8, class: &str, name: &str, descriptor: &str) -> u16 {
+        let class = self.class(class);
+        let (name, descriptor) = (self.utf8(name), self.utf8(descriptor));
+        let name_and_type = self.constant([&[JVM_TAG_NAME_AND_TYPE][..], &name.to_be_bytes(), &descriptor.to_be_bytes()].concat());
+        self.constant([&[tag][..], &class.to_be_bytes(), &name_and_type.to_be_bytes()].concat())
+    }
+
+    pub fn field_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        self.member_ref(JVM_TAG_FIELDREF, class, name, descriptor)
+    }
+
+    pub fn method_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        self.member_ref(JVM_TAG_METHODREF, class, name, descriptor)
+    }
+
+    pub fn invoke_dynamic(&mut self, bootstrap_method_attr_index: u16, name: &str, descriptor: &str) -> u16 {
+        let (name, descriptor) = (self.utf8(name), self.utf8(descriptor));
+        let name_and_type = self.constant([&[JVM_TAG_NAME_AND_TYPE][..], &name.to_be_bytes(), &descriptor.to_be_bytes()].concat());
+        self.constant([&[JVM_TAG_INVOKE_DYNAMIC][..], &bootstrap_method_attr_index.to_be_bytes(), &name_and_type.to_be_bytes()].concat())
+    }
+
+    #[allow(dead_code)]
+    pub fn interface_method_ref(&mut self, class: &str, name: &str, descriptor: &str) -> u16 {
+        self.member_ref(JVM_TAG_INTERFACE_METHODREF, class, name, descriptor)
+    }
+
+    pub fn field(self, access_flags: u16, name: &str, descriptor: &str) -> Self {
+        self.field_with_constant(access_flags, name, descriptor, 0)
+    }
+
+    /// a field with a ConstantValue attribute of the constant at `constant_value`, none if 0
+    pub fn field_with_constant(mut self, access_flags: u16, name: &str, descriptor: &str, constant_value: u16) -> Self {
+        let (name, descriptor) = (self.utf8(name), self.utf8(descriptor));
+        let mut field: Vec<u8> = [access_flags, name, descriptor].iter().flat_map(|u2| u2.to_be_bytes()).collect();
+        if constant_value == 0 {
+            field.extend(0u16.to_be_bytes());
+        } else {
+            let attribute_name = self.utf8("ConstantValue");
+            field.extend(1u16.to_be_bytes());
+            field.extend(attribute_name.to_be_bytes());
+            field.extend(2u32.to_be_bytes());
+            field.extend(constant_value.to_be_bytes());
+        }
+        self.fields.push(field);
+        self
+    }
+
+    /// a method with `code`, none for an abstract or native method
+    pub fn method(mut self, access_flags: u16, name: &str, descriptor: &str, code: Option<Code>) -> Self {
+        let (name, descriptor) = (self.utf8(name), self.utf8(descriptor));
+        let mut method: Vec<u8> = [access_flags, name, descriptor].iter().flat_map(|u2| u2.to_be_bytes()).collect();
+        match code {
+            None => method.extend(0u16.to_be_bytes()),
+            Some(code) => {
+                let mut attributes = vec![];
+                if let Some((entries, frames)) = code.stack_map_table {
+                    attributes.push(self.attribute("StackMapTable", [&entries.to_be_bytes()[..], &frames].concat()));
+                }
+                let mut body = vec![];
+                body.extend(code.max_stack.to_be_bytes());
+                body.extend(code.max_locals.to_be_bytes());
+                body.extend((code.code.len() as u32).to_be_bytes());
+                body.extend(&code.code);
+                body.extend((code.exception_table.len() as u16).to_be_bytes());
+                body.extend(code.exception_table.iter().flatten().flat_map(|u2| u2.to_be_bytes()));
+                body.extend((attributes.len() as u16).to_be_bytes());
+                body.extend(attributes.concat());
+                let code = self.attribute("Code", body);
+                method.extend(1u16.to_be_bytes());
+                method.extend(code);
+            }
+        }
+        self.methods.push(method);
+        self
+    }
+
+    /// the `<init>()V` of a class extending `super_class`, which calls the constructor of the superclass
+    pub fn constructor(mut self, super_class: &str) -> Self {
+        let init = self.method_ref(super_class, "<init>", "()V");
+        let [high, low] = init.to_be_bytes();
+        self.method(ACC_PUBLIC, "<init>", "()V", Some(Code::new(1, 1, vec![0x2a, 0xb7, high, low, 0xb1])))
+    }
+
+    /// a class attribute, e.g. NestHost
+    pub fn class_attribute(mut self, name: &str, info: Vec<u8>) -> Self {
+        let attribute = self.attribute(name, info);
+        self.attributes.push(attribute);
+        self
+    }
+
+    fn attribute(&mut self, name: &str, info: Vec<u8>) -> Vec<u8> {
+        let name = self.utf8(name);
+        [&name.to_be_bytes()[..], &(info.len() as u32).to_be_bytes(), &info].concat()
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        let mut bytes = CLASS_FILE_MAGIC.to_be_bytes().to_vec();
+        bytes.extend(0u16.to_be_bytes());
+        bytes.extend(self.major_version.to_be_bytes());
+        bytes.extend((self.constants.len() as u16 + 1).to_be_bytes());
+        bytes.extend(self.constants.concat());
+        for u2 in [self.access_flags, self.this_class, self.super_class, self.interfaces.len() as u16].iter().chain(&self.interfaces) {
+            bytes.extend(u2.to_be_bytes());
+        }
+        for members in [&self.fields, &self.methods, &self.attributes] {
+            bytes.extend((members.len() as u16).to_be_bytes());
+            bytes.extend(members.concat());
+        }
+        bytes
+    }
+
+    /// define the class with `loader`, it is neither linked nor initialized
+    pub fn define(&self, vm: &mut Vm, loader: ClassLoaderId) -> Rc<Class> {
+        vm.class_loaders.define_class(&mut vm.method_area, loader, &self.name, self.bytes()).expect("defined class")
+    }
+}
+
+/// a VM without class paths, whose bootstrap loader defines java/lang/Object with a constructor and the interfaces
+/// of the array classes
+pub fn test_vm(options: VmOptions) -> Vm {
+    let loaders = ClassLoaders::new(ClassPath::new(vec![]), ClassPath::new(vec![]), ClassPath::new(vec![]));
+    let mut vm = Vm::new(loaders, options);
+    ClassBuilder::new(JAVA_LANG_OBJECT, None)
+        .method(ACC_PUBLIC, "<init>", "()V", Some(Code::new(0, 1, vec![0xb1])))
+        .define(&mut vm, BOOTSTRAP_LOADER);
+    for interface in ["java/lang/Cloneable", "java/io/Serializable"] {
+        ClassBuilder::new(interface, Some(JAVA_LANG_OBJECT))
+            .access_flags(ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT)
+            .define(&mut vm, BOOTSTRAP_LOADER);
+    }
+    vm
+}
@@ -1,20 +1,43 @@
 use std::rc::Rc;
-use std::convert::TryInto;
-use std::any::Any;
-use std::fs;
 use crate::constants::*;
 use enum_as_inner::EnumAsInner;
 
 // bytes definition for jvm_spec naming
+#[allow(non_camel_case_types)]
 pub type u1 = u8;
+#[allow(non_camel_case_types)]
 pub type u2 = u16;
+#[allow(non_camel_case_types)]
 pub type u4 = u32;
 
-pub fn read(file: &str) -> ClassFile {
-    let mut buffer = Buffer::from(file);
-    let classfile = ClassFile::from(&mut buffer);
-    println!("{}", classfile.major_version);
-    classfile
+/// a class file which is truncated, has bytes beyond its end, or is malformed otherwise (JVMS 4.8)
+#[derive(PartialEq, Eq, Debug)]
+pub enum FormatError {
+    Truncated,
+    ExtraBytes,
+    Malformed(String)
+}
+
+impl FormatError {
+    /// the message of the ClassFormatError for the class file of `name`
+    pub fn message(&self, name: &str) -> String {
+        match self {
+            FormatError::Truncated => "Truncated class file".to_string(),
+            FormatError::ExtraBytes => format!("Extra bytes at the end of class file {}", name),
+            FormatError::Malformed(message) => format!("{} in class file {}", message, name)
+        }
+    }
+}
+
+/// parse a class file and check that its constants refer to constants of the right kinds, so that they can be resolved
+pub fn parse(bytes: Vec<u1>) -> Result<ClassFile, FormatError> {
+    let mut buffer = Buffer::from(bytes);
+    let classfile = ClassFile::from(&mut buffer)?;
+    if buffer.remaining() > 0 {
+        return Err(FormatError::ExtraBytes);
+    }
+    classfile.check()?;
+    Ok(classfile)
 }
 
 pub struct Buffer {
@@ -23,50 +46,54 @@ pub struct Buffer {
 }
 
 impl Buffer {
-    fn from(file: &str) -> Self {
+    fn from(bytes: Vec<u1>) -> Self {
         Buffer {
-            bytes: fs::read(file).unwrap(),
+            bytes,
             pos: 0
         }
     }
 }
 
 impl Buffer {
-    fn u1(&mut self) -> u1 {
-        let value = self.bytes[self.pos];
-        self.pos += 1;
-        value
+    /// the next `length` bytes, the class file is truncated if there are fewer
+    fn take(&mut self, length: usize) -> Result<&[u1], FormatError> {
+        let end = self.pos.checked_add(length).filter(|&end| end <= self.bytes.len()).ok_or(FormatError::Truncated)?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
     }
 
-    fn u2(&mut self) -> u2 {
-        let value = u2::from_be_bytes([
-            self.bytes[self.pos+0], self.bytes[self.pos+1]
-        ]);
-        self.pos += 2;
-        value
+    fn u1(&mut self) -> Result<u1, FormatError> {
+        Ok(self.take(1)?[0])
     }
 
-    fn u4(&mut self) -> u4 {
-        let value = u4::from_be_bytes([
-            self.bytes[self.pos+0], self.bytes[self.pos+1],
-            self.bytes[self.pos+2], self.bytes[self.pos+3]
-        ]);
-        self.pos += 4;
-        value
+    fn u2(&mut self) -> Result<u2, FormatError> {
+        let bytes = self.take(2)?;
+        Ok(u2::from_be_bytes([bytes[0], bytes[1]]))
     }
 
-    fn bytes(&mut self, length: usize) -> Vec<u1> {
-        let mut vec = Vec::with_capacity(length);
-        for i in 0..length {
-            vec.push(self.bytes[self.pos + i])
-        }
-        self.pos += length;
-        vec
+    fn u4(&mut self) -> Result<u4, FormatError> {
+        let bytes = self.take(4)?;
+        Ok(u4::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<Vec<u1>, FormatError> {
+        Ok(self.take(length)?.to_vec())
+    }
+
+    /// `count` u2 values, e.g. the indexes of the interfaces of a class
+    fn u2s(&mut self, count: u2) -> Result<Vec<u2>, FormatError> {
+        (0..count).map(|_| self.u2()).collect()
     }
 }
 
+#[allow(dead_code)]
 trait Resolve {
-    fn resolve(&mut self, constant_pool: &Vec<ConstantPoolInfo>);
+    fn resolve(&mut self, constant_pool: &[ConstantPoolInfo]);
 }
 
 /*
@@ -89,6 +116,7 @@ ClassFile {
 	attribute_info 	attributes[attributes_count];
 }
 */
+#[allow(dead_code)]
 pub struct ClassFile {
     pub(crate) magic: u4,
     pub(crate) minor_version: u2,
@@ -110,24 +138,24 @@ pub struct ClassFile {
 
 impl ClassFile {
 
-    fn from(buffer: &mut Buffer) -> ClassFile {
-        let magic = buffer.u4();
-        let minor_version = buffer.u2();
-        let major_version = buffer.u2();
-        let constant_pool_count = buffer.u2();
-        let constant_pool = ConstantPool::from(buffer, constant_pool_count);
-        let access_flags = buffer.u2();
-        let this_class = buffer.u2();
-        let super_class = buffer.u2();
-        let interfaces_count = buffer.u2();
-        let interfaces = (0..interfaces_count).map(|_| buffer.u2()).collect();
-        let fields_count = buffer.u2();
-        let fields = FieldInfo::with_capacity(buffer, &constant_pool, fields_count);
-        let methods_count = buffer.u2();
-        let methods = MethodInfo::with_capacity(buffer, &constant_pool, methods_count);
-        let attributes_count = buffer.u2();
-        let attributes = AttributeInfo::with_capacity(buffer, &constant_pool, attributes_count);
-        ClassFile {
+    fn from(buffer: &mut Buffer) -> Result<ClassFile, FormatError> {
+        let magic = buffer.u4()?;
+        let minor_version = buffer.u2()?;
+        let major_version = buffer.u2()?;
+        let constant_pool_count = buffer.u2()?;
+        let constant_pool = ConstantPool::from(buffer, constant_pool_count)?;
+        let access_flags = buffer.u2()?;
+        let this_class = buffer.u2()?;
+        let super_class = buffer.u2()?;
+        let interfaces_count = buffer.u2()?;
+        let interfaces = buffer.u2s(interfaces_count)?;
+        let fields_count = buffer.u2()?;
+        let fields = FieldInfo::with_capacity(buffer, &constant_pool, fields_count)?;
+        let methods_count = buffer.u2()?;
+        let methods = MethodInfo::with_capacity(buffer, &constant_pool, methods_count)?;
+        let attributes_count = buffer.u2()?;
+        let attributes = AttributeInfo::with_capacity(buffer, &constant_pool, attributes_count)?;
+        Ok(ClassFile {
             magic,
             minor_version,
             major_version,
@@ -144,18 +172,204 @@ impl ClassFile {
             methods,
             attributes_count,
             attributes
+        })
+    }
+}
+
+impl ClassFile {
+    pub(crate) fn class_name(&self) -> String {
+        self.constant_pool.resolve_class(self.this_class)
+    }
+
+    /// empty for java/lang/Object which has no super class
+    pub(crate) fn super_class_name(&self) -> String {
+        if self.super_class == 0 {
+            "".to_string()
+        } else {
+            self.constant_pool.resolve_class(self.super_class)
         }
     }
+
+    pub(crate) fn interface_names(&self) -> Vec<String> {
+        self.interfaces.iter().map(|interface| self.constant_pool.resolve_class(*interface)).collect()
+    }
+
+    /// the host of the nest this class is a member of, empty if the class has no NestHost attribute
+    pub(crate) fn nest_host_name(&self) -> String {
+        self.attributes.iter().find_map(|attribute| match attribute {
+            AttributeInfo::NestHost(nest_host) => Some(self.constant_pool.resolve_class(nest_host.host_class_index)),
+            _ => None
+        }).unwrap_or_default()
+    }
+
+    /// the members of the nest this class hosts, from its NestMembers attribute
+    pub(crate) fn nest_member_names(&self) -> Vec<String> {
+        self.attributes.iter().find_map(|attribute| match attribute {
+            AttributeInfo::NestMembers(nest_members) =>
+                Some(nest_members.classes.iter().map(|&class| self.constant_pool.resolve_class(class)).collect()),
+            _ => None
+        }).unwrap_or_default()
+    }
+
+    /// the MethodHandle and static argument indexes of each entry of the BootstrapMethods attribute
+    pub(crate) fn bootstrap_methods(&self) -> Vec<(u2, Vec<u2>)> {
+        self.attributes.iter().find_map(|attribute| match attribute {
+            AttributeInfo::BootstrapMethods(bootstrap_methods) => Some(bootstrap_methods.bootstrap_methods.iter()
+                .map(|entry| (entry.bootstrap_method_ref, entry.bootstrap_arguments.clone()))
+                .collect()),
+            _ => None
+        }).unwrap_or_default()
+    }
+
+    /// the name of the source file given by the SourceFile attribute, empty without one
+    pub(crate) fn source_file(&self) -> String {
+        self.attributes.iter().find_map(|attribute| match attribute {
+            AttributeInfo::SourceFile(source_file) => Some(self.constant_pool.resolve_utf8(source_file.source_file_index)),
+            _ => None
+        }).unwrap_or_default()
+    }
+
+    /// JVMS 4.8: the indexes into the constant pool refer to constants of the right kinds, the `resolve_` functions
+    /// need not fail afterwards
+    fn check(&self) -> Result<(), FormatError> {
+        let cp = &self.constant_pool;
+        cp.check_references()?;
+        cp.check_class(self.this_class)
+            .map_err(|_| FormatError::Malformed(format!("Invalid this class index {} in constant pool", self.this_class)))?;
+        if self.super_class != 0 {
+            cp.check_class(self.super_class)
+                .map_err(|_| FormatError::Malformed(format!("Invalid superclass index {} in constant pool", self.super_class)))?;
+        }
+        for &interface in &self.interfaces {
+            cp.check_class(interface)
+                .map_err(|_| FormatError::Malformed(format!("Interface name has bad constant pool index {}", interface)))?;
+        }
+        for field in &self.fields {
+            cp.check_utf8(field.name_index)?;
+            cp.check_utf8(field.descriptor_index)?;
+            if let Some(constant_value) = field.find_constant_value_attribute() {
+                cp.check(constant_value.constant_value_index, |constant| matches!(constant,
+                    ConstantPoolInfo::Integer(_) | ConstantPoolInfo::Long(_) | ConstantPoolInfo::Float(_) |
+                    ConstantPoolInfo::Double(_) | ConstantPoolInfo::String(_)))
+                    .map_err(|_| FormatError::Malformed(format!("Bad initial value index {} in ConstantValue attribute",
+                        constant_value.constant_value_index)))?;
+            }
+        }
+        for method in &self.methods {
+            cp.check_utf8(method.name_index)?;
+            cp.check_utf8(method.descriptor_index)?;
+            if let Some(code) = method.find_code_attribute() {
+                for entry in code.exception_table.iter().filter(|entry| entry.catch_type != 0) {
+                    cp.check_class(entry.catch_type)?;
+                }
+                if let Some(local_variable_table) = code.find_local_variable_table_attribute() {
+                    for entry in &local_variable_table.local_variable_table {
+                        cp.check_utf8(entry.name_index)?;
+                        cp.check_utf8(entry.descriptor_index)?;
+                    }
+                }
+            }
+        }
+        for attribute in &self.attributes {
+            match attribute {
+                AttributeInfo::SourceFile(source_file) => cp.check_utf8(source_file.source_file_index)?,
+                AttributeInfo::NestHost(nest_host) => cp.check_class(nest_host.host_class_index)?,
+                AttributeInfo::NestMembers(nest_members) => {
+                    for &class in &nest_members.classes {
+                        cp.check_class(class)?;
+                    }
+                }
+                AttributeInfo::BootstrapMethods(bootstrap_methods) => {
+                    for entry in &bootstrap_methods.bootstrap_methods {
+                        cp.check(entry.bootstrap_method_ref, |constant| matches!(constant, ConstantPoolInfo::MethodHandle(_)))?;
+                        for &argument in &entry.bootstrap_arguments {
+                            // a loadable constant (JVMS 4.4)
+                            cp.check(argument, |constant| matches!(constant,
+                                ConstantPoolInfo::Integer(_) | ConstantPoolInfo::Long(_) | ConstantPoolInfo::Float(_) |
+                                ConstantPoolInfo::Double(_) | ConstantPoolInfo::Class(_) | ConstantPoolInfo::String(_) |
+                                ConstantPoolInfo::MethodHandle(_) | ConstantPoolInfo::MethodType(_) | ConstantPoolInfo::Dynamic(_)))?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
 }
 
 pub struct ConstantPool(pub Vec<ConstantPoolInfo>);
 
 impl ConstantPool {
-    fn from(buffer: &mut Buffer, constant_pool_count: u2) -> Self {
+    fn from(buffer: &mut Buffer, constant_pool_count: u2) -> Result<Self, FormatError> {
+        if constant_pool_count == 0 {
+            return Err(FormatError::Malformed("Illegal constant pool size 0".to_string()));
+        }
         let mut constant_pool = Vec::<ConstantPoolInfo>::with_capacity(constant_pool_count as usize);
         constant_pool.push(ConstantPoolInfo::Unknown);
-        (1..constant_pool_count).for_each(|_| constant_pool.push(ConstantPoolInfo::from(buffer)));
-        ConstantPool(constant_pool)
+        while constant_pool.len() < constant_pool_count as usize {
+            let constant = ConstantPoolInfo::from(buffer)?;
+            let two_entries = matches!(constant, ConstantPoolInfo::Long(_) | ConstantPoolInfo::Double(_));
+            constant_pool.push(constant);
+            if two_entries {
+                constant_pool.push(ConstantPoolInfo::Unknown); // 8-byte constants take up two entries
+            }
+        }
+        Ok(ConstantPool(constant_pool))
+    }
+
+    /// the constant at `index` if it is of the kind `is_kind` accepts
+    fn check(&self, index: u2, is_kind: fn(&ConstantPoolInfo) -> bool) -> Result<(), FormatError> {
+        match self.0.get(index as usize) {
+            Some(constant) if is_kind(constant) => Ok(()),
+            _ => Err(FormatError::Malformed(format!("Invalid constant pool index {}", index)))
+        }
+    }
+
+    fn check_utf8(&self, index: u2) -> Result<(), FormatError> {
+        self.check(index, |constant| matches!(constant, ConstantPoolInfo::Utf8(_)))
+    }
+
+    fn check_class(&self, index: u2) -> Result<(), FormatError> {
+        self.check(index, |constant| matches!(constant, ConstantPoolInfo::Class(_)))
+    }
+
+    fn check_name_and_type(&self, index: u2) -> Result<(), FormatError> {
+        self.check(index, |constant| matches!(constant, ConstantPoolInfo::NameAndType(_)))
+    }
+
+    /// JVMS 4.4: every constant refers to constants of the kinds its tag requires
+    fn check_references(&self) -> Result<(), FormatError> {
+        for constant in &self.0 {
+            match constant {
+                ConstantPoolInfo::Class(class_info) => self.check_utf8(class_info.name_index)?,
+                ConstantPoolInfo::String(string_info) => self.check_utf8(string_info.string_index)?,
+                ConstantPoolInfo::FieldRef(ConstantFieldRefInfo { class_index, name_and_type_index, .. }) |
+                ConstantPoolInfo::MethodRef(ConstantMethodRefInfo { class_index, name_and_type_index, .. }) |
+                ConstantPoolInfo::InterfaceMethodRef(ConstantInterfaceMethodrefInfo { class_index, name_and_type_index, .. }) => {
+                    self.check_class(*class_index)?;
+                    self.check_name_and_type(*name_and_type_index)?;
+                }
+                ConstantPoolInfo::NameAndType(name_and_type_info) => {
+                    self.check_utf8(name_and_type_info.name_index)?;
+                    self.check_utf8(name_and_type_info.descriptor_index)?;
+                }
+                ConstantPoolInfo::MethodHandle(method_handle) => {
+                    if !(JVM_REF_GET_FIELD..=JVM_REF_INVOKE_INTERFACE).contains(&method_handle.reference_kind) {
+                        return Err(FormatError::Malformed(format!("Bad method handle kind {}", method_handle.reference_kind)));
+                    }
+                    self.check(method_handle.reference_index, |constant| matches!(constant,
+                        ConstantPoolInfo::FieldRef(_) | ConstantPoolInfo::MethodRef(_) | ConstantPoolInfo::InterfaceMethodRef(_)))?;
+                }
+                ConstantPoolInfo::MethodType(method_type) => self.check_utf8(method_type.descriptor_index)?,
+                ConstantPoolInfo::InvokeDynamic(invoke_dynamic) => self.check_name_and_type(invoke_dynamic.name_and_type_index)?,
+                ConstantPoolInfo::Dynamic(dynamic) => self.check_name_and_type(dynamic.name_and_type_index)?,
+                ConstantPoolInfo::Module(module) => self.check_utf8(module.name_index)?,
+                ConstantPoolInfo::Package(package) => self.check_utf8(package.name_index)?,
+                _ => {}
+            }
+        }
+        Ok(())
     }
 
     pub(crate) fn resolve_name_and_type(&self, name_and_type_index: u16) -> (String, String) {
@@ -168,7 +382,15 @@ impl ConstantPool {
 
     pub(crate) fn resolve_utf8(&self, utf8_index: u16) -> String {
         match &self.0[utf8_index as usize] {
-            ConstantPoolInfo::Utf8(utf8_info) => String::from_utf8(utf8_info.bytes.clone()).unwrap(),
+            ConstantPoolInfo::Utf8(utf8_info) => utf8_info.value(),
+            _ => panic!("utf8 constant is not indexed as a utf8")
+        }
+    }
+
+    /// the UTF-16 code units of a Utf8 constant, an unpaired surrogate is kept
+    pub(crate) fn resolve_utf16(&self, utf8_index: u16) -> Vec<u2> {
+        match &self.0[utf8_index as usize] {
+            ConstantPoolInfo::Utf8(utf8_info) => modified_utf8_to_utf16(&utf8_info.bytes),
             _ => panic!("utf8 constant is not indexed as a utf8")
         }
     }
@@ -203,93 +425,96 @@ pub enum ConstantPoolInfo {
     Utf8(ConstantUtf8Info),
     MethodHandle(ConstantMethodHandleInfo),
     MethodType(ConstantMethodTypeInfo),
-    InvokeDynamic(ConstantInvokeDynamicInfo)
+    InvokeDynamic(ConstantInvokeDynamicInfo),
+    Dynamic(ConstantDynamicInfo),
+    Module(ConstantModuleInfo),
+    Package(ConstantPackageInfo)
 }
 
 impl ConstantPoolInfo {
-    fn from(buffer: &mut Buffer) -> Self {
-        let tag = buffer.u1();
-        match tag {
+    fn from(buffer: &mut Buffer) -> Result<Self, FormatError> {
+        let tag = buffer.u1()?;
+        Ok(match tag {
             JVM_TAG_CLASS =>
                 ConstantPoolInfo::Class(
-                    ConstantClassInfo { tag, name_index: buffer.u2() }
+                    ConstantClassInfo { tag, name_index: buffer.u2()? }
                 ),
             JVM_TAG_FIELDREF =>
                 ConstantPoolInfo::FieldRef(
                     ConstantFieldRefInfo {
                         tag,
-                        class_index: buffer.u2(),
-                        name_and_type_index: buffer.u2()
+                        class_index: buffer.u2()?,
+                        name_and_type_index: buffer.u2()?
                     }
                 ),
             JVM_TAG_METHODREF =>
                 ConstantPoolInfo::MethodRef(
                     ConstantMethodRefInfo {
                         tag,
-                        class_index: buffer.u2(),
-                        name_and_type_index: buffer.u2()
+                        class_index: buffer.u2()?,
+                        name_and_type_index: buffer.u2()?
                     }
                 ),
             JVM_TAG_INTERFACE_METHODREF =>
                 ConstantPoolInfo::InterfaceMethodRef(
                     ConstantInterfaceMethodrefInfo {
                         tag,
-                        class_index: buffer.u2(),
-                        name_and_type_index: buffer.u2()
+                        class_index: buffer.u2()?,
+                        name_and_type_index: buffer.u2()?
                     }
                 ),
             JVM_TAG_STRING =>
                 ConstantPoolInfo::String(
                     ConstantStringInfo {
                         tag,
-                        string_index: buffer.u2()
+                        string_index: buffer.u2()?
                     }
                 ),
             JVM_TAG_INTEGER =>
                 ConstantPoolInfo::Integer(
                     ConstantIntegerInfo {
                         tag,
-                        bytes: buffer.u4()
+                        bytes: buffer.u4()?
                     }
                 ),
             JVM_TAG_FLOAT =>
                 ConstantPoolInfo::Float(
                     ConstantFloatInfo {
                         tag,
-                        bytes: buffer.u4()
+                        bytes: buffer.u4()?
                     }
                 ),
             JVM_TAG_LONG =>
                 ConstantPoolInfo::Long(
                     ConstantLongInfo {
                         tag,
-                        high_bytes: buffer.u4(),
-                        low_bytes: buffer.u4()
+                        high_bytes: buffer.u4()?,
+                        low_bytes: buffer.u4()?
                     }
                 ),
             JVM_TAG_DOUBLE =>
                 ConstantPoolInfo::Double(
                     ConstantDoubleInfo {
                         tag,
-                        high_bytes: buffer.u4(),
-                        low_bytes: buffer.u4()
+                        high_bytes: buffer.u4()?,
+                        low_bytes: buffer.u4()?
                     }
                 ),
             JVM_TAG_NAME_AND_TYPE =>
                 ConstantPoolInfo::NameAndType(
                     ConstantNameAndTypeInfo {
                         tag,
-                        name_index: buffer.u2(),
-                        descriptor_index: buffer.u2()
+                        name_index: buffer.u2()?,
+                        descriptor_index: buffer.u2()?
                     }
                 ),
             JVM_TAG_UTF8 => {
-                let length = buffer.u2();
+                let length = buffer.u2()?;
                 ConstantPoolInfo::Utf8(
                     ConstantUtf8Info {
                         tag,
                         length,
-                        bytes: buffer.bytes(length as usize)
+                        bytes: buffer.bytes(length as usize)?
                     }
                 )
             }
@@ -297,27 +522,49 @@ impl ConstantPoolInfo {
                 ConstantPoolInfo::MethodHandle(
                     ConstantMethodHandleInfo {
                         tag,
-                        reference_kind: buffer.u1(),
-                        reference_index: buffer.u2()
+                        reference_kind: buffer.u1()?,
+                        reference_index: buffer.u2()?
                     }
                 ),
             JVM_TAG_METHOD_TYPE =>
                 ConstantPoolInfo::MethodType(
                     ConstantMethodTypeInfo {
                         tag,
-                        descriptor_index: buffer.u2()
+                        descriptor_index: buffer.u2()?
                     }
                 ),
             JVM_TAG_INVOKE_DYNAMIC =>
                 ConstantPoolInfo::InvokeDynamic(
                     ConstantInvokeDynamicInfo {
                         tag,
-                        bootstrap_method_attr_index: buffer.u2(),
-                        name_and_type_index: buffer.u2()
+                        bootstrap_method_attr_index: buffer.u2()?,
+                        name_and_type_index: buffer.u2()?
                     }
                 ),
-            _ => panic!("Not a supported constant tag {}", tag)
-        }
+            JVM_TAG_DYNAMIC =>
+                ConstantPoolInfo::Dynamic(
+                    ConstantDynamicInfo {
+                        tag,
+                        bootstrap_method_attr_index: buffer.u2()?,
+                        name_and_type_index: buffer.u2()?
+                    }
+                ),
+            JVM_TAG_MODULE =>
+                ConstantPoolInfo::Module(
+                    ConstantModuleInfo {
+                        tag,
+                        name_index: buffer.u2()?
+                    }
+                ),
+            JVM_TAG_PACKAGE =>
+                ConstantPoolInfo::Package(
+                    ConstantPackageInfo {
+                        tag,
+                        name_index: buffer.u2()?
+                    }
+                ),
+            _ => return Err(FormatError::Malformed(format!("Unknown constant tag {}", tag)))
+        })
     }
 
 }
@@ -328,6 +575,7 @@ CONSTANT_Class_info {
     u2 name_index;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantClassInfo {
     tag: u1,
     pub(crate) name_index: u2
@@ -340,6 +588,7 @@ CONSTANT_Fieldref_info {
     u2 name_and_type_index;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantFieldRefInfo {
     tag: u1,
     pub(crate) class_index: u2,
@@ -353,6 +602,7 @@ CONSTANT_Methodref_info {
     u2 name_and_type_index;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantMethodRefInfo {
     tag: u1,
     pub(crate) class_index: u2,
@@ -366,6 +616,7 @@ CONSTANT_InterfaceMethodref_info {
     u2 name_and_type_index;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantInterfaceMethodrefInfo  {
     tag              : u1,
     pub(crate) class_index       : u2,
@@ -378,6 +629,7 @@ CONSTANT_String_info {
     u2 string_index;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantStringInfo {
     tag: u1,
     pub(crate) string_index: u2
@@ -389,6 +641,7 @@ CONSTANT_Integer_info {
     u4 bytes;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantIntegerInfo {
     tag: u1,
     pub(crate) bytes: u4
@@ -400,6 +653,7 @@ CONSTANT_Float_info {
     u4 bytes;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantFloatInfo {
     tag: u1,
     pub(crate) bytes: u4
@@ -412,6 +666,7 @@ CONSTANT_Long_info {
     u4 low_bytes;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantLongInfo {
     tag: u1,
     pub(crate) high_bytes: u4,
@@ -425,6 +680,7 @@ CONSTANT_Double_info {
     u4 low_bytes;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantDoubleInfo{
     tag: u1,
     pub(crate) high_bytes: u4,
@@ -438,6 +694,7 @@ CONSTANT_NameAndType_info {
     u2 descriptor_index;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantNameAndTypeInfo {
     tag: u1,
     pub(crate) name_index: u2,
@@ -451,6 +708,7 @@ CONSTANT_Utf8_info {
     u1 bytes[length];
 }
 */
+#[allow(dead_code)]
 pub struct ConstantUtf8Info {
     tag: u1,
     pub(crate) length: u2,
@@ -458,12 +716,43 @@ pub struct ConstantUtf8Info {
 }
 
 impl ConstantUtf8Info {
-    // TODO refine clone
     pub fn value(&self) -> String {
-        unsafe { String::from_utf8_unchecked(self.bytes.clone()) }
+        decode_modified_utf8(&self.bytes)
     }
 }
 
+/// decode modified UTF-8: `\0` is encoded in 2 bytes and supplementary characters as surrogate pairs of 3 bytes each.
+/// Unpaired surrogates can not be represented in a Rust string and are replaced.
+pub fn decode_modified_utf8(bytes: &[u1]) -> String {
+    if bytes.iter().all(|&byte| byte < 0x80) {
+        return bytes.iter().map(|&byte| byte as char).collect();
+    }
+    String::from_utf16_lossy(&modified_utf8_to_utf16(bytes))
+}
+
+/// the UTF-16 code units of a modified UTF-8 string, malformed bytes are decoded as they were Latin-1
+pub fn modified_utf8_to_utf16(bytes: &[u1]) -> Vec<u2> {
+    let mut chars = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i] as u2;
+        if byte & 0x80 == 0 {
+            chars.push(byte);
+            i += 1;
+        } else if byte & 0xe0 == 0xc0 && i + 1 < bytes.len() && bytes[i + 1] & 0xc0 == 0x80 {
+            chars.push((byte & 0x1f) << 6 | (bytes[i + 1] & 0x3f) as u2);
+            i += 2;
+        } else if byte & 0xf0 == 0xe0 && i + 2 < bytes.len() && bytes[i + 1] & 0xc0 == 0x80 && bytes[i + 2] & 0xc0 == 0x80 {
+            chars.push((byte & 0x0f) << 12 | ((bytes[i + 1] & 0x3f) as u2) << 6 | (bytes[i + 2] & 0x3f) as u2);
+            i += 3;
+        } else {
+            chars.push(byte);
+            i += 1;
+        }
+    }
+    chars
+}
+
 /*
 CONSTANT_MethodHandle_info {
     u1 tag;
@@ -471,6 +760,7 @@ CONSTANT_MethodHandle_info {
     u2 reference_index;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantMethodHandleInfo {
     tag: u1,
     pub(crate) reference_kind: u1,
@@ -483,6 +773,7 @@ CONSTANT_MethodType_info {
     u2 descriptor_index;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantMethodTypeInfo {
     tag: u1,
     pub(crate) descriptor_index: u2
@@ -495,12 +786,51 @@ CONSTANT_InvokeDynamic_info {
     u2 name_and_type_index;
 }
 */
+#[allow(dead_code)]
 pub struct ConstantInvokeDynamicInfo {
     tag: u1,
     pub(crate) bootstrap_method_attr_index: u2,
     pub(crate) name_and_type_index: u2
 }
 
+/*
+CONSTANT_Dynamic_info {
+    u1 tag;
+    u2 bootstrap_method_attr_index;
+    u2 name_and_type_index;
+}
+*/
+#[allow(dead_code)]
+pub struct ConstantDynamicInfo {
+    tag: u1,
+    pub(crate) bootstrap_method_attr_index: u2,
+    pub(crate) name_and_type_index: u2
+}
+
+/*
+CONSTANT_Module_info {
+    u1 tag;
+    u2 name_index;
+}
+*/
+#[allow(dead_code)]
+pub struct ConstantModuleInfo {
+    tag: u1,
+    pub(crate) name_index: u2
+}
+
+/*
+CONSTANT_Package_info {
+    u1 tag;
+    u2 name_index;
+}
+*/
+#[allow(dead_code)]
+pub struct ConstantPackageInfo {
+    tag: u1,
+    pub(crate) name_index: u2
+}
+
 /*
 field_info {
     u2             access_flags;
@@ -510,6 +840,7 @@ field_info {
     attribute_info attributes[attributes_count];
 }
 */
+#[allow(dead_code)]
 pub struct FieldInfo {
     pub(crate) access_flags: u2,
     pub(crate) name_index: u2,
@@ -519,26 +850,38 @@ pub struct FieldInfo {
 }
 
 impl FieldInfo {
-    fn from(buffer: &mut Buffer, constant_pool: &ConstantPool) -> Self {
-        let access_flags = buffer.u2();
-        let name_index = buffer.u2();
-        let descriptor_index = buffer.u2();
-        let attribute_count = buffer.u2();
-        let attributes = AttributeInfo::with_capacity(buffer, constant_pool, attribute_count);
-        FieldInfo {
+    fn from(buffer: &mut Buffer, constant_pool: &ConstantPool) -> Result<Self, FormatError> {
+        let access_flags = buffer.u2()?;
+        let name_index = buffer.u2()?;
+        let descriptor_index = buffer.u2()?;
+        let attribute_count = buffer.u2()?;
+        let attributes = AttributeInfo::with_capacity(buffer, constant_pool, attribute_count)?;
+        Ok(FieldInfo {
             access_flags,
             name_index,
             descriptor_index,
             attribute_count,
             attributes
-        }
+        })
+    }
+
+    fn with_capacity(buffer: &mut Buffer, constant_pool: &ConstantPool, fields_count: u2) -> Result<Vec<Self>, FormatError> {
+        (0..fields_count).map(|_| FieldInfo::from(buffer, constant_pool)).collect()
     }
 
-    fn with_capacity(buffer: &mut Buffer, constant_pool: &ConstantPool, fields_count: u2) -> Vec<Self> {
-        (0..fields_count).map(|_| FieldInfo::from(buffer, &constant_pool)).collect()
+    /// the initial value of a static field, e.g. `static final int MAX = 10`
+    pub fn find_constant_value_attribute(&self) -> Option<&ConstantValueAttribute> {
+        self.attributes.iter().find_map(|attribute| {
+            if let AttributeInfo::ConstantValue(constant_value_attribute) = attribute {
+                Some(constant_value_attribute)
+            } else {
+                None
+            }
+        })
     }
 }
 
+#[allow(dead_code)]
 pub struct MethodInfo {
     pub(crate) access_flags: u2,
     pub(crate) name_index: u2,
@@ -557,37 +900,34 @@ method_info {
 }
 */
 impl MethodInfo {
-    fn from(buffer: &mut Buffer, constant_pool: &ConstantPool) -> Self {
-        let access_flags = buffer.u2();
-        let name_index = buffer.u2();
-        let descriptor_index = buffer.u2();
-        let attribute_count = buffer.u2();
-        let attributes = AttributeInfo::with_capacity(buffer, constant_pool, attribute_count);
-        MethodInfo {
+    fn from(buffer: &mut Buffer, constant_pool: &ConstantPool) -> Result<Self, FormatError> {
+        let access_flags = buffer.u2()?;
+        let name_index = buffer.u2()?;
+        let descriptor_index = buffer.u2()?;
+        let attribute_count = buffer.u2()?;
+        let attributes = AttributeInfo::with_capacity(buffer, constant_pool, attribute_count)?;
+        Ok(MethodInfo {
             access_flags,
             name_index,
             descriptor_index,
             attribute_count,
             attributes
-        }
+        })
     }
 
-    fn with_capacity(buffer: &mut Buffer, constant_pool: &ConstantPool, method_count: u2) -> Vec<Self> {
-        (0..method_count).map(|_| MethodInfo::from(buffer, &constant_pool)).collect()
+    fn with_capacity(buffer: &mut Buffer, constant_pool: &ConstantPool, method_count: u2) -> Result<Vec<Self>, FormatError> {
+        (0..method_count).map(|_| MethodInfo::from(buffer, constant_pool)).collect()
     }
 
-    pub fn find_code_attribute(&self) -> &CodeAttribute {
-        let found = self.attributes.iter().find_map(|attribute| {
+    /// abstract and native methods have no code
+    pub fn find_code_attribute(&self) -> Option<&CodeAttribute> {
+        self.attributes.iter().find_map(|attribute| {
             if let AttributeInfo::Code(code_attribute) = attribute {
                 Some(code_attribute)
             } else {
                 None
             }
-        });
-        if found.is_none() {
-            panic!("Code attribute not found")
-        }
-        found.unwrap()
+        })
     }
 }
 
@@ -599,38 +939,58 @@ attribute_info {
 }
 */
 #[derive(EnumAsInner)]
+#[allow(dead_code)]
 pub enum AttributeInfo {
     Unknown,
+    ConstantValue(ConstantValueAttribute),
     Code(CodeAttribute),
+    StackMapTable(StackMapTableAttribute),
     LineNumberTable(LineNumberTableAttribute),
     LocalVariableTable(LocalVariableTableAttribute),
     SourceFile(SourceFileAttribute),
+    NestHost(NestHostAttribute),
+    NestMembers(NestMembersAttribute),
+    BootstrapMethods(BootstrapMethodsAttribute),
     RuntimeVisibleAnnotation(RuntimeVisibleAnnotationAttribute)
 }
 
 impl AttributeInfo {
-    fn from(buffer: &mut Buffer, constant_pool: &ConstantPool) -> Self {
-        let attribute_name_index = buffer.u2();
-        let attribute_length = buffer.u4();
-        let attribute_name = constant_pool.resolve_utf8(attribute_name_index);
-        match attribute_name.as_str() {
+    fn from(buffer: &mut Buffer, constant_pool: &ConstantPool) -> Result<Self, FormatError> {
+        let attribute_name_index = buffer.u2()?;
+        let attribute_length = buffer.u4()?;
+        let attribute_name = match constant_pool.0.get(attribute_name_index as usize) {
+            Some(ConstantPoolInfo::Utf8(utf8_info)) => utf8_info.value(),
+            _ => return Err(FormatError::Malformed(format!("Invalid attribute name index {}", attribute_name_index)))
+        };
+        // an attribute is parsed from its own bytes, it may neither read beyond them nor leave any
+        let buffer = &mut Buffer::from(buffer.bytes(attribute_length as usize)?);
+        let attribute = match attribute_name.as_str() {
+            "ConstantValue" => {
+                AttributeInfo::ConstantValue(
+                    ConstantValueAttribute {
+                        attribute_name_index,
+                        attribute_length,
+                        constant_value_index: buffer.u2()?
+                    }
+                )
+            }
             "Code" => {
-                let max_stack= buffer.u2();
-                let max_locals = buffer.u2();
-                let code_length = buffer.u4();
-                let code = buffer.bytes(code_length as usize);
-                let exception_table_length = buffer.u2();
+                let max_stack= buffer.u2()?;
+                let max_locals = buffer.u2()?;
+                let code_length = buffer.u4()?;
+                let code = buffer.bytes(code_length as usize)?;
+                let exception_table_length = buffer.u2()?;
                 let mut exception_table = Vec::<ExceptionTableEntry>::with_capacity(exception_table_length as usize);
                 for _ in 0..exception_table_length {
                     exception_table.push(ExceptionTableEntry {
-                        start_pc: buffer.u2(),
-                        end_pc: buffer.u2(),
-                        handle_pc: buffer.u2(),
-                        catch_type: buffer.u2()
+                        start_pc: buffer.u2()?,
+                        end_pc: buffer.u2()?,
+                        handle_pc: buffer.u2()?,
+                        catch_type: buffer.u2()?
                     })
                 }
-                let attributes_count = buffer.u2();
-                let attributes = AttributeInfo::with_capacity(buffer, constant_pool, attributes_count);
+                let attributes_count = buffer.u2()?;
+                let attributes = AttributeInfo::with_capacity(buffer, constant_pool, attributes_count)?;
                 let code = CodeAttribute {
                     attribute_name_index,
                     attribute_length,
@@ -643,16 +1003,38 @@ impl AttributeInfo {
                     attributes_count,
                     attributes
                 };
-                println!("code.attributes: {}", code.attributes.len());
                 AttributeInfo::Code(
                     code
                 )
             }
+            "StackMapTable" => {
+                let number_of_entries = buffer.u2()?;
+                let mut entries = Vec::with_capacity(number_of_entries as usize);
+                for _ in 0..number_of_entries {
+                    let frame = StackMapFrame::from(buffer).map_err(|_| {
+                        FormatError::Malformed("StackMapTable format error: access beyond the end of attribute".to_string())
+                    })?;
+                    let reserved = matches!(frame, StackMapFrame::Reserved(_));
+                    entries.push(frame);
+                    if reserved {
+                        break; // the length of a reserved frame is unknown, the verifier rejects it
+                    }
+                }
+                buffer.bytes(buffer.remaining())?; // the frames after a reserved one
+                AttributeInfo::StackMapTable(
+                    StackMapTableAttribute {
+                        attribute_name_index,
+                        attribute_length,
+                        number_of_entries,
+                        entries
+                    }
+                )
+            }
             "LineNumberTable" => {
-                let line_number_table_length = buffer.u2();
+                let line_number_table_length = buffer.u2()?;
                 let mut line_number_table = Vec::<LineNumberTableEntry>::with_capacity(line_number_table_length as usize);
                 for _ in 0..line_number_table_length {
-                    line_number_table.push(LineNumberTableEntry { start_pc: buffer.u2(), line_number: buffer.u2() })
+                    line_number_table.push(LineNumberTableEntry { start_pc: buffer.u2()?, line_number: buffer.u2()? })
                 }
                 AttributeInfo::LineNumberTable(
                     LineNumberTableAttribute {
@@ -664,15 +1046,15 @@ impl AttributeInfo {
                 )
             }
             "LocalVariableTable" => {
-                let local_variable_table_length = buffer.u2();
+                let local_variable_table_length = buffer.u2()?;
                 let mut local_variable_table = Vec::<LocalVariableTableEntry>::with_capacity(local_variable_table_length as usize);
                 for _ in 0..local_variable_table_length {
                     local_variable_table.push(LocalVariableTableEntry {
-                        start_pc: buffer.u2(),
-                        length: buffer.u2(),
-                        name_index: buffer.u2(),
-                        descriptor_index: buffer.u2(),
-                        index: buffer.u2()
+                        start_pc: buffer.u2()?,
+                        length: buffer.u2()?,
+                        name_index: buffer.u2()?,
+                        descriptor_index: buffer.u2()?,
+                        index: buffer.u2()?
                     })
                 }
                 AttributeInfo::LocalVariableTable(
@@ -689,23 +1071,80 @@ impl AttributeInfo {
                     SourceFileAttribute {
                         attribute_name_index,
                         attribute_length,
-                        source_file_index: buffer.u2()
+                        source_file_index: buffer.u2()?
+                    }
+                )
+            }
+            "NestHost" => {
+                AttributeInfo::NestHost(
+                    NestHostAttribute {
+                        attribute_name_index,
+                        attribute_length,
+                        host_class_index: buffer.u2()?
+                    }
+                )
+            }
+            "NestMembers" => {
+                let number_of_classes = buffer.u2()?;
+                AttributeInfo::NestMembers(
+                    NestMembersAttribute {
+                        attribute_name_index,
+                        attribute_length,
+                        number_of_classes,
+                        classes: buffer.u2s(number_of_classes)?
+                    }
+                )
+            }
+            "BootstrapMethods" => {
+                let num_bootstrap_methods = buffer.u2()?;
+                let bootstrap_methods = (0..num_bootstrap_methods).map(|_| {
+                    let bootstrap_method_ref = buffer.u2()?;
+                    let num_bootstrap_arguments = buffer.u2()?;
+                    Ok(BootstrapMethodEntry {
+                        bootstrap_method_ref,
+                        num_bootstrap_arguments,
+                        bootstrap_arguments: buffer.u2s(num_bootstrap_arguments)?
+                    })
+                }).collect::<Result<_, FormatError>>()?;
+                AttributeInfo::BootstrapMethods(
+                    BootstrapMethodsAttribute {
+                        attribute_name_index,
+                        attribute_length,
+                        num_bootstrap_methods,
+                        bootstrap_methods
                     }
                 )
             }
             _ => {
-                println!("Skip not parsed attribute: {}", attribute_name);
-                buffer.bytes(attribute_length as usize); // skip
+                buffer.bytes(buffer.remaining())?; // skip not parsed attribute
                 AttributeInfo::Unknown
             }
+        };
+        if buffer.remaining() > 0 {
+            return Err(FormatError::Malformed(format!("Invalid {} attribute length {}", attribute_name, attribute_length)));
         }
+        Ok(attribute)
     }
 
-    fn with_capacity(buffer: &mut Buffer, constant_pool: &ConstantPool, attributes_count: u2) -> Vec<Self> {
+    fn with_capacity(buffer: &mut Buffer, constant_pool: &ConstantPool, attributes_count: u2) -> Result<Vec<Self>, FormatError> {
         (0..attributes_count).map(|_| AttributeInfo::from(buffer, constant_pool)).collect()
     }
 }
 
+/*
+ConstantValue_attribute {
+    u2 attribute_name_index;
+    u4 attribute_length;
+    u2 constantvalue_index;
+}
+*/
+#[allow(dead_code)]
+pub struct ConstantValueAttribute {
+    pub(crate) attribute_name_index: u2,
+    pub(crate) attribute_length: u4,
+    pub(crate) constant_value_index: u2
+}
+
 /*
 Code_attribute {
     u2 attribute_name_index;
@@ -724,6 +1163,7 @@ Code_attribute {
     attribute_info attributes[attributes_count];
 }
 */
+#[allow(dead_code)]
 pub struct  CodeAttribute {
     pub(crate) attribute_name_index: u2,
     pub(crate) attribute_length: u4,
@@ -738,32 +1178,36 @@ pub struct  CodeAttribute {
 }
 
 impl CodeAttribute {
-    pub fn find_local_variable_table_attribute(&self) -> &LocalVariableTableAttribute {
-        let found = self.attributes.iter().find_map(|attribute| {
+    /// debug attributes are absent if the class is compiled without `-g`
+    pub fn find_local_variable_table_attribute(&self) -> Option<&LocalVariableTableAttribute> {
+        self.attributes.iter().find_map(|attribute| {
             if let AttributeInfo::LocalVariableTable(local_variable_table_attribute) = attribute {
                 Some(local_variable_table_attribute)
             } else {
                 None
             }
-        });
-        if found.is_none() {
-            panic!("LocalVariableTable attribute not found")
-        }
-        found.unwrap()
+        })
+    }
+
+    /// absent in class files before version 50, and in methods without branches
+    pub fn find_stack_map_table_attribute(&self) -> Option<&StackMapTableAttribute> {
+        self.attributes.iter().find_map(|attribute| {
+            if let AttributeInfo::StackMapTable(stack_map_table) = attribute {
+                Some(stack_map_table)
+            } else {
+                None
+            }
+        })
     }
 
-    pub fn find_line_number_table_attribute(&self) -> &LineNumberTableAttribute {
-        let found = self.attributes.iter().find_map(|attribute| {
+    pub fn find_line_number_table_attribute(&self) -> Option<&LineNumberTableAttribute> {
+        self.attributes.iter().find_map(|attribute| {
             if let AttributeInfo::LineNumberTable(line_number_table) = attribute {
                 Some(line_number_table)
             } else {
                 None
             }
-        });
-        if found.is_none() {
-            panic!("LineNumberTable attribute not found")
-        }
-        found.unwrap()
+        })
     }
 }
 
@@ -774,6 +1218,133 @@ pub struct ExceptionTableEntry {
     pub(crate) catch_type: u2
 }
 
+/*
+StackMapTable_attribute {
+    u2              attribute_name_index;
+    u4              attribute_length;
+    u2              number_of_entries;
+    stack_map_frame entries[number_of_entries];
+}
+*/
+#[allow(dead_code)]
+pub struct StackMapTableAttribute {
+    pub(crate) attribute_name_index: u2,
+    pub(crate) attribute_length: u4,
+    pub(crate) number_of_entries: u2,
+    pub(crate) entries: Vec<StackMapFrame>
+}
+
+/*
+union stack_map_frame {
+    same_frame;                         0-63
+    same_locals_1_stack_item_frame;     64-127
+    same_locals_1_stack_item_frame_extended; 247
+    chop_frame;                         248-250
+    same_frame_extended;                251
+    append_frame;                       252-254
+    full_frame;                         255
+}
+*/
+#[derive(Clone, Debug)]
+pub enum StackMapFrame {
+    Same { offset_delta: u2 },
+    SameLocals1StackItem { offset_delta: u2, stack: VerificationTypeInfo },
+    // the last k locals are absent
+    Chop { offset_delta: u2, k: u1 },
+    Append { offset_delta: u2, locals: Vec<VerificationTypeInfo> },
+    Full { offset_delta: u2, locals: Vec<VerificationTypeInfo>, stack: Vec<VerificationTypeInfo> },
+    // frame types 128-246 are reserved
+    Reserved(u1)
+}
+
+impl StackMapFrame {
+    fn from(buffer: &mut Buffer) -> Result<Self, FormatError> {
+        let frame_type = buffer.u1()?;
+        Ok(match frame_type {
+            0..=63 => StackMapFrame::Same { offset_delta: frame_type as u2 },
+            64..=127 => StackMapFrame::SameLocals1StackItem {
+                offset_delta: frame_type as u2 - 64,
+                stack: VerificationTypeInfo::from(buffer)?
+            },
+            247 => StackMapFrame::SameLocals1StackItem {
+                offset_delta: buffer.u2()?,
+                stack: VerificationTypeInfo::from(buffer)?
+            },
+            248..=250 => StackMapFrame::Chop { offset_delta: buffer.u2()?, k: 251 - frame_type },
+            251 => StackMapFrame::Same { offset_delta: buffer.u2()? },
+            252..=254 => {
+                let offset_delta = buffer.u2()?;
+                let locals = (0..frame_type - 251).map(|_| VerificationTypeInfo::from(buffer)).collect::<Result<_, _>>()?;
+                StackMapFrame::Append { offset_delta, locals }
+            }
+            255 => {
+                let offset_delta = buffer.u2()?;
+                let number_of_locals = buffer.u2()?;
+                let locals = (0..number_of_locals).map(|_| VerificationTypeInfo::from(buffer)).collect::<Result<_, _>>()?;
+                let number_of_stack_items = buffer.u2()?;
+                let stack = (0..number_of_stack_items).map(|_| VerificationTypeInfo::from(buffer)).collect::<Result<_, _>>()?;
+                StackMapFrame::Full { offset_delta, locals, stack }
+            }
+            _ => StackMapFrame::Reserved(frame_type)
+        })
+    }
+
+    pub fn offset_delta(&self) -> u2 {
+        match self {
+            StackMapFrame::Same { offset_delta } |
+            StackMapFrame::SameLocals1StackItem { offset_delta, .. } |
+            StackMapFrame::Chop { offset_delta, .. } |
+            StackMapFrame::Append { offset_delta, .. } |
+            StackMapFrame::Full { offset_delta, .. } => *offset_delta,
+            StackMapFrame::Reserved(_) => 0
+        }
+    }
+}
+
+/*
+union verification_type_info {
+    Top_variable_info;                  0
+    Integer_variable_info;              1
+    Float_variable_info;                2
+    Double_variable_info;               3
+    Long_variable_info;                 4
+    Null_variable_info;                 5
+    UninitializedThis_variable_info;    6
+    Object_variable_info;               7 u2 cpool_index
+    Uninitialized_variable_info;        8 u2 offset
+}
+*/
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum VerificationTypeInfo {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    Object(u2),
+    Uninitialized(u2),
+    Invalid(u1)
+}
+
+impl VerificationTypeInfo {
+    fn from(buffer: &mut Buffer) -> Result<Self, FormatError> {
+        Ok(match buffer.u1()? {
+            0 => VerificationTypeInfo::Top,
+            1 => VerificationTypeInfo::Integer,
+            2 => VerificationTypeInfo::Float,
+            3 => VerificationTypeInfo::Double,
+            4 => VerificationTypeInfo::Long,
+            5 => VerificationTypeInfo::Null,
+            6 => VerificationTypeInfo::UninitializedThis,
+            7 => VerificationTypeInfo::Object(buffer.u2()?),
+            8 => VerificationTypeInfo::Uninitialized(buffer.u2()?),
+            tag => VerificationTypeInfo::Invalid(tag)
+        })
+    }
+}
+
 /*
 LineNumberTable_attribute {
     u2 attribute_name_index;
@@ -784,6 +1355,7 @@ LineNumberTable_attribute {
     } line_number_table[line_number_table_length];
 }
 */
+#[allow(dead_code)]
 pub struct LineNumberTableAttribute {
     pub(crate) attribute_name_index: u2,
     pub(crate) attribute_length: u4,
@@ -809,6 +1381,7 @@ LocalVariableTable_attribute {
     } local_variable_table[local_variable_table_length];
 }
 */
+#[allow(dead_code)]
 pub struct LocalVariableTableAttribute {
     pub(crate) attribute_name_index: u2,
     pub(crate) attribute_length: u4,
@@ -831,12 +1404,69 @@ SourceFile_attribute {
     u2 sourcefile_index;
 }
 */
+#[allow(dead_code)]
 pub struct SourceFileAttribute {
     pub(crate) attribute_name_index: u2,
     pub(crate) attribute_length: u4,
     pub(crate) source_file_index: u2
 }
 
+/*
+NestHost_attribute {
+    u2 attribute_name_index;
+    u4 attribute_length;
+    u2 host_class_index;
+}
+*/
+#[allow(dead_code)]
+pub struct NestHostAttribute {
+    pub(crate) attribute_name_index: u2,
+    pub(crate) attribute_length: u4,
+    pub(crate) host_class_index: u2
+}
+
+/*
+NestMembers_attribute {
+    u2 attribute_name_index;
+    u4 attribute_length;
+    u2 number_of_classes;
+    u2 classes[number_of_classes];
+}
+*/
+#[allow(dead_code)]
+pub struct NestMembersAttribute {
+    pub(crate) attribute_name_index: u2,
+    pub(crate) attribute_length: u4,
+    pub(crate) number_of_classes: u2,
+    pub(crate) classes: Vec<u2>
+}
+
+/*
+BootstrapMethods_attribute {
+    u2 attribute_name_index;
+    u4 attribute_length;
+    u2 num_bootstrap_methods;
+    {   u2 bootstrap_method_ref;
+        u2 num_bootstrap_arguments;
+        u2 bootstrap_arguments[num_bootstrap_arguments];
+    } bootstrap_methods[num_bootstrap_methods];
+}
+*/
+#[allow(dead_code)]
+pub struct BootstrapMethodsAttribute {
+    pub(crate) attribute_name_index: u2,
+    pub(crate) attribute_length: u4,
+    pub(crate) num_bootstrap_methods: u2,
+    pub(crate) bootstrap_methods: Vec<BootstrapMethodEntry>
+}
+
+#[allow(dead_code)]
+pub struct BootstrapMethodEntry {
+    pub(crate) bootstrap_method_ref: u2,
+    pub(crate) num_bootstrap_arguments: u2,
+    pub(crate) bootstrap_arguments: Vec<u2>
+}
+
 /*
 RuntimeVisibleAnnotations_attribute {
     u2         attribute_name_index;
@@ -845,6 +1475,7 @@ RuntimeVisibleAnnotations_attribute {
     annotation annotations[num_annotations];
 }
 */
+#[allow(dead_code)]
 pub struct RuntimeVisibleAnnotationAttribute {
     pub(crate) attribute_name_index: u2,
     pub(crate) attribute_length: u4,
@@ -861,12 +1492,14 @@ annotation {
     } element_value_pairs[num_element_value_pairs];
 }
 */
+#[allow(dead_code)]
 pub struct Annotation {
     pub(crate) type_index: u2,
     pub(crate) num_element_value_pairs: u2,
     pub(crate) element_value_pairs: Vec<AnnotationElementValuePair>,
 }
 
+#[allow(dead_code)]
 pub struct AnnotationElementValuePair {
     pub(crate) element_name_index: u2,
     pub(crate) value: ElementValue
@@ -892,6 +1525,7 @@ element_value {
     } value;
 }
 */
+#[allow(dead_code)]
 pub struct ElementValue {
     tag: u1,
     pub(crate) const_value_index: u2,
@@ -901,11 +1535,13 @@ pub struct ElementValue {
     pub(crate) array_value: ElementValueArray
 }
 
+#[allow(dead_code)]
 pub struct EnumConstValue {
     pub(crate) type_name_index: u2,
     pub(crate) const_name_index: u2
 }
 
+#[allow(dead_code)]
 pub struct ElementValueArray {
     pub(crate) num_values: u2,
     pub(crate) values: Vec<Rc<ElementValue>>
@@ -0,0 +1,496 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+use crate::class_file::{self, FormatError};
+use crate::class_path::ClassPath;
+use crate::constants::*;
+use crate::exception::Throwable;
+use crate::method_area::{ClassId, MethodArea};
+use crate::types::{Class, Type};
+
+pub const CLASS_FILE_MAGIC: u32 = 0xCAFEBABE;
+pub const MAX_SUPPORTED_MAJOR_VERSION: u16 = 61; // java 17
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ClassLoaderId(pub(crate) usize);
+
+pub const BOOTSTRAP_LOADER: ClassLoaderId = ClassLoaderId(0);
+pub const PLATFORM_LOADER: ClassLoaderId = ClassLoaderId(1);
+pub const APP_LOADER: ClassLoaderId = ClassLoaderId(2);
+
+// the super class and super interfaces of a class being derived
+type Supers = (Option<Rc<Class>>, Vec<Rc<Class>>);
+
+pub struct ClassLoader {
+    pub(crate) name: &'static str,
+    pub(crate) parent: Option<ClassLoaderId>,
+    class_path: ClassPath
+}
+
+/// JVMS 5.3.4: `name` must denote the same class in all the `loaders`
+struct LoadingConstraint {
+    name: String,
+    loaders: Vec<ClassLoaderId>,
+    class: Option<ClassId>
+}
+
+/// the built-in class loaders: bootstrap <- platform <- application, each delegates to its parent first.
+/// The loaded classes are kept in the method area.
+pub struct ClassLoaders {
+    loaders: Vec<ClassLoader>,
+    constraints: Vec<LoadingConstraint>,
+    // classes being derived, a class can not be its own superclass or superinterface
+    loading: HashSet<(String, ClassLoaderId)>
+}
+
+impl ClassLoader {
+    /// the named module of a class defined by this loader, e.g. `java.base`
+    pub fn module_of(&self, name: &str) -> Option<String> {
+        self.class_path.module_of(name)
+    }
+}
+
+impl ClassLoaders {
+    pub fn new(boot_class_path: ClassPath, platform_class_path: ClassPath, app_class_path: ClassPath) -> Self {
+        let loader = |name, parent, class_path| ClassLoader {
+            name,
+            parent,
+            class_path
+        };
+        ClassLoaders {
+            loaders: vec![
+                loader("bootstrap", None, boot_class_path),
+                loader("platform", Some(BOOTSTRAP_LOADER), platform_class_path),
+                loader("app", Some(PLATFORM_LOADER), app_class_path)
+            ],
+            constraints: vec![],
+            loading: HashSet::new()
+        }
+    }
+
+    pub fn loader(&self, id: ClassLoaderId) -> &ClassLoader {
+        &self.loaders[id.0]
+    }
+
+    /// load a class by its binary name in internal form, delegating to the parent loader first.
+    /// ClassNotFoundException is thrown if neither the parent nor this loader can find it.
+    pub fn load_class(&mut self, method_area: &mut MethodArea, loader: ClassLoaderId, name: &str) -> Result<Rc<Class>, Throwable> {
+        if let Some(class) = method_area.find_loaded_class(loader, name) {
+            return Ok(class);
+        }
+        if name.starts_with(JVM_SIGNATURE_ARRAY) {
+            return self.load_array_class(method_area, loader, name);
+        }
+        if let Some(parent) = self.loaders[loader.0].parent {
+            match self.load_class(method_area, parent, name) {
+                Ok(class) => {
+                    self.record(method_area, loader, &class)?;
+                    return Ok(class);
+                }
+                Err(throwable) if throwable.class_name == JAVA_LANG_CLASS_NOT_FOUND_EXCEPTION => {}
+                Err(throwable) => return Err(throwable)
+            }
+        }
+        let bytes = self.loaders[loader.0].class_path.find_class(name)
+            .ok_or_else(|| Throwable::new(JAVA_LANG_CLASS_NOT_FOUND_EXCEPTION, name.replace('/', ".")))?;
+        self.define_class(method_area, loader, name, bytes)
+    }
+
+    /// array classes are created by the VM rather than loaded (JVMS 5.3.3). The defining loader is the one of the
+    /// component class, or the bootstrap loader for primitive components, and `loader` is an initiating loader.
+    fn load_array_class(&mut self, method_area: &mut MethodArea, loader: ClassLoaderId, name: &str) -> Result<Rc<Class>, Throwable> {
+        let not_found = || Throwable::new(JAVA_LANG_CLASS_NOT_FOUND_EXCEPTION, name.replace('/', "."));
+        if name.bytes().take_while(|&b| b == b'[').count() > MAX_ARRAY_DIMENSIONS {
+            return Err(not_found());
+        }
+        let component_name = &name[1..];
+        let component_type = if component_name.starts_with(JVM_SIGNATURE_ARRAY) {
+            Type::Class(self.load_class(method_area, loader, component_name)?)
+        } else if let Some(class_name) = component_name.strip_prefix(JVM_SIGNATURE_CLASS).and_then(|rest| rest.strip_suffix(JVM_SIGNATURE_ENDCLASS)) {
+            if class_name.is_empty() || class_name.contains(JVM_SIGNATURE_ENDCLASS) || class_name.starts_with(JVM_SIGNATURE_ARRAY) {
+                return Err(not_found());
+            }
+            Type::Class(self.load_class(method_area, loader, class_name)?)
+        } else {
+            Type::primitive(component_name).ok_or_else(not_found)?
+        };
+        let defining_loader = match &component_type {
+            Type::Class(component) => component.defining_loader,
+            _ => BOOTSTRAP_LOADER
+        };
+        let class = match method_area.find_loaded_class(defining_loader, name) {
+            Some(class) => class,
+            None => {
+                let object = self.load_class(method_area, BOOTSTRAP_LOADER, JAVA_LANG_OBJECT)?;
+                let interfaces = vec![
+                    self.load_class(method_area, BOOTSTRAP_LOADER, JAVA_LANG_CLONEABLE)?,
+                    self.load_class(method_area, BOOTSTRAP_LOADER, JAVA_IO_SERIALIZABLE)?
+                ];
+                let class = method_area.add_array_class(name, defining_loader, component_type, object, interfaces);
+                self.record(method_area, defining_loader, &class)?;
+                class
+            }
+        };
+        self.record(method_area, loader, &class)?;
+        Ok(class)
+    }
+
+    /// resolve a class referenced symbolically from a class defined by `loader` (JVMS 5.4.3.1),
+    /// a class that can not be found is a NoClassDefFoundError
+    pub fn resolve_class(&mut self, method_area: &mut MethodArea, loader: ClassLoaderId, name: &str) -> Result<Rc<Class>, Throwable> {
+        self.load_class(method_area, loader, name).map_err(|throwable| {
+            if throwable.class_name == JAVA_LANG_CLASS_NOT_FOUND_EXCEPTION {
+                Throwable::new(JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR, name)
+            } else {
+                throwable
+            }
+        })
+    }
+
+    /// derive a class from its class file bytes and record `loader` as its defining loader (JVMS 5.3.5)
+    pub fn define_class(&mut self, method_area: &mut MethodArea, loader: ClassLoaderId, name: &str, bytes: Vec<u8>) -> Result<Rc<Class>, Throwable> {
+        if method_area.find_loaded_class(loader, name).is_some() {
+            return Err(Throwable::new(JAVA_LANG_LINKAGE_ERROR, format!(
+                "loader '{}' attempted duplicate class definition for {}", self.loaders[loader.0].name, name.replace('/', "."))));
+        }
+        // a file which is no class file at all, or one of a later version, is told apart from a malformed one before it is parsed
+        let (magic, minor_version, major_version) = match bytes.get(..8) {
+            Some(&[m0, m1, m2, m3, minor0, minor1, major0, major1]) =>
+                (u32::from_be_bytes([m0, m1, m2, m3]), u16::from_be_bytes([minor0, minor1]), u16::from_be_bytes([major0, major1])),
+            _ => return Err(Throwable::new(JAVA_LANG_CLASS_FORMAT_ERROR, FormatError::Truncated.message(name)))
+        };
+        if magic != CLASS_FILE_MAGIC {
+            return Err(Throwable::new(JAVA_LANG_CLASS_FORMAT_ERROR, format!(
+                "Incompatible magic value {} in class file {}", magic, name)));
+        }
+        if major_version > MAX_SUPPORTED_MAJOR_VERSION {
+            return Err(Throwable::new(JAVA_LANG_UNSUPPORTED_CLASS_VERSION_ERROR, format!(
+                "{} has been compiled by a more recent version of the Java Runtime (class file version {}.{}), \
+                this version of the Java Runtime only recognizes class file versions up to {}.0",
+                name.replace('/', "."), major_version, minor_version, MAX_SUPPORTED_MAJOR_VERSION)));
+        }
+        let classfile = class_file::parse(bytes)
+            .map_err(|error| Throwable::new(JAVA_LANG_CLASS_FORMAT_ERROR, error.message(name)))?;
+        let class_name = classfile.class_name();
+        if class_name != name {
+            return Err(Throwable::new(JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR, format!("{} (wrong name: {})", name, class_name)));
+        }
+
+        self.loading.insert((name.to_string(), loader));
+        let supers = self.load_supers(method_area, loader, name, &classfile);
+        self.loading.remove(&(name.to_string(), loader));
+        let (super_class, interfaces) = supers?;
+
+        let class = method_area.add_class(&classfile, loader, super_class, interfaces);
+        self.record(method_area, loader, &class)?;
+        Ok(class)
+    }
+
+    fn load_supers(&mut self, method_area: &mut MethodArea, loader: ClassLoaderId, name: &str, classfile: &class_file::ClassFile) -> Result<Supers, Throwable> {
+        let is_interface = classfile.access_flags & ACC_INTERFACE != 0;
+        let super_class_name = classfile.super_class_name();
+        let super_class = if super_class_name.is_empty() {
+            if name != JAVA_LANG_OBJECT {
+                return Err(Throwable::new(JAVA_LANG_CLASS_FORMAT_ERROR, format!("Invalid superclass index 0 in class file {}", name)));
+            }
+            None
+        } else {
+            let super_class = self.load_super(method_area, loader, name, &super_class_name)?;
+            if super_class.access_flags & ACC_INTERFACE != 0 {
+                return Err(Throwable::new(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, format!(
+                    "class {} has interface {} as super class", name.replace('/', "."), super_class_name.replace('/', "."))));
+            }
+            if super_class.access_flags & ACC_FINAL != 0 {
+                return Err(Throwable::new(JAVA_LANG_VERIFY_ERROR, format!(
+                    "Cannot inherit from final class {}", super_class_name.replace('/', "."))));
+            }
+            if is_interface && super_class_name != JAVA_LANG_OBJECT {
+                return Err(Throwable::new(JAVA_LANG_CLASS_FORMAT_ERROR, format!(
+                    "Interfaces must have java.lang.Object as superclass in class file {}", name)));
+            }
+            Some(super_class)
+        };
+        let mut interfaces = vec![];
+        for interface_name in classfile.interface_names() {
+            let interface = self.load_super(method_area, loader, name, &interface_name)?;
+            if interface.access_flags & ACC_INTERFACE == 0 {
+                return Err(Throwable::new(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, format!(
+                    "class {} can not implement {}, because it is not an interface", name.replace('/', "."), interface_name.replace('/', "."))));
+            }
+            interfaces.push(interface);
+        }
+        Ok((super_class, interfaces))
+    }
+
+    fn load_super(&mut self, method_area: &mut MethodArea, loader: ClassLoaderId, name: &str, super_name: &str) -> Result<Rc<Class>, Throwable> {
+        if self.loading.contains(&(super_name.to_string(), loader)) {
+            return Err(Throwable::new(JAVA_LANG_CLASS_CIRCULARITY_ERROR, name.replace('/', ".")));
+        }
+        self.resolve_class(method_area, loader, super_name)
+    }
+
+    /// record `loader` as an initiating loader of `class`, which must satisfy the loading constraints
+    fn record(&mut self, method_area: &mut MethodArea, loader: ClassLoaderId, class: &Rc<Class>) -> Result<(), Throwable> {
+        if let Some(constraint) = self.constraints.iter_mut()
+            .find(|constraint| constraint.name == class.name && constraint.loaders.contains(&loader)) {
+            match constraint.class {
+                Some(constrained) if constrained != class.id => {
+                    return Err(Throwable::new(JAVA_LANG_LINKAGE_ERROR, format!(
+                        "loader constraint violation: loader '{}' wants to load class {}. \
+                        A different class with the same name was previously loaded by '{}'.",
+                        self.loaders[loader.0].name, class.name.replace('/', "."), self.loaders[method_area.class(constrained).defining_loader.0].name)));
+                }
+                _ => constraint.class = Some(class.id)
+            }
+        }
+        method_area.record(loader, class);
+        Ok(())
+    }
+
+    /// impose the constraint that `name` loaded by `loader1` and `loader2` must be the same class
+    pub fn add_constraint(&mut self, method_area: &MethodArea, name: &str, loader1: ClassLoaderId, loader2: ClassLoaderId) -> Result<(), Throwable> {
+        if loader1 == loader2 {
+            return Ok(());
+        }
+        let (mut loaders, mut classes) = (vec![loader1, loader2], vec![]);
+        classes.extend(method_area.find_loaded_class(loader1, name).map(|class| class.id));
+        classes.extend(method_area.find_loaded_class(loader2, name).map(|class| class.id));
+        // merge the existing constraints of either loader
+        let mut i = 0;
+        while i < self.constraints.len() {
+            let constraint = &self.constraints[i];
+            if constraint.name == name && (constraint.loaders.contains(&loader1) || constraint.loaders.contains(&loader2)) {
+                let constraint = self.constraints.swap_remove(i);
+                loaders.extend(constraint.loaders);
+                classes.extend(constraint.class);
+            } else {
+                i += 1;
+            }
+        }
+        loaders.sort_by_key(|loader| loader.0);
+        loaders.dedup();
+        if let Some(&conflict) = classes.iter().find(|&&class| class != classes[0]) {
+            return Err(Throwable::new(JAVA_LANG_LINKAGE_ERROR, format!(
+                "loader constraint violation: the class loaders '{}' and '{}' have different Class objects for the type {} used in the signature",
+                self.loaders[method_area.class(classes[0]).defining_loader.0].name, self.loaders[method_area.class(conflict).defining_loader.0].name, name.replace('/', "."))));
+        }
+        self.constraints.push(LoadingConstraint {
+            name: name.to_string(),
+            loaders,
+            class: classes.into_iter().next()
+        });
+        Ok(())
+    }
+
+    /// the constraints for a field or method referenced across loaders: every class in its descriptor must be the same
+    pub fn add_descriptor_constraints(&mut self, method_area: &MethodArea, descriptor: &str, loader1: ClassLoaderId, loader2: ClassLoaderId) -> Result<(), Throwable> {
+        for name in class_names_of_descriptor(descriptor) {
+            self.add_constraint(method_area, name, loader1, loader2)?;
+        }
+        Ok(())
+    }
+}
+
+/// the class names in a field or method descriptor, e.g. `java/lang/String` of `([Ljava/lang/String;)V`
+pub fn class_names_of_descriptor(descriptor: &str) -> Vec<&str> {
+    let mut names = vec![];
+    let mut rest = descriptor;
+    while let Some(start) = rest.find(JVM_SIGNATURE_CLASS) {
+        let end = match rest[start..].find(JVM_SIGNATURE_ENDCLASS) {
+            Some(end) => start + end,
+            None => break
+        };
+        names.push(&rest[start + 1..end]);
+        rest = &rest[end + 1..];
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_builder::{test_vm, ClassBuilder, Code};
+    use crate::interpreter;
+    use crate::types::Field;
+    use crate::vm::{Vm, VmOptions};
+
+    // the message of a constraint violated by the classes named T of the application and bootstrap loaders
+    const VIOLATION: &str = "loader constraint violation: the class loaders 'app' and 'bootstrap' have different Class objects \
+        for the type T used in the signature";
+
+    /// a VM whose bootstrap loader defines `T` and `D`, which declares the static field `t` and the methods `m` of type T.
+    /// The application loader defines a class `T` of its own if `app_defines_t`.
+    fn two_loaders(app_defines_t: bool) -> Vm {
+        let mut vm = test_vm(VmOptions::default());
+        ClassBuilder::new("T", Some(JAVA_LANG_OBJECT)).define(&mut vm, BOOTSTRAP_LOADER);
+        if app_defines_t {
+            ClassBuilder::new("T", Some(JAVA_LANG_OBJECT)).define(&mut vm, APP_LOADER);
+        }
+        ClassBuilder::new("D", Some(JAVA_LANG_OBJECT))
+            .field(ACC_PUBLIC | ACC_STATIC, "t", "LT;")
+            .method(ACC_PUBLIC | ACC_STATIC, "m", "(LT;)V", Some(Code::new(0, 1, vec![0xb1])))
+            .method(ACC_PUBLIC, "m", "(LT;)I", Some(Code::new(1, 2, vec![0x03, 0xac])))
+            .define(&mut vm, BOOTSTRAP_LOADER);
+        vm
+    }
+
+    /// resolve the constant `reference` adds to `C` of the application loader, with `resolve`
+    fn resolve<T>(vm: &mut Vm, reference: impl FnOnce(&mut ClassBuilder) -> u16,
+                  resolve: impl FnOnce(&mut Vm, &Rc<Class>, u16) -> Result<T, Throwable>) -> Result<(), Throwable> {
+        let mut builder = ClassBuilder::new("C", Some(JAVA_LANG_OBJECT));
+        let index = reference(&mut builder);
+        let class = builder.define(vm, APP_LOADER);
+        resolve(vm, &class, index).map(|_| ())
+    }
+
+    fn m(builder: &mut ClassBuilder) -> u16 {
+        builder.method_ref("D", "m", "(LT;)V")
+    }
+
+    fn t(builder: &mut ClassBuilder) -> u16 {
+        builder.field_ref("D", "t", "LT;")
+    }
+
+    fn resolve_static_field(vm: &mut Vm, class: &Rc<Class>, index: u16) -> Result<Rc<Field>, Throwable> {
+        interpreter::resolve_field(vm, class, index, true)
+    }
+
+    fn message(result: Result<(), Throwable>) -> String {
+        let throwable = result.expect_err("LinkageError");
+        assert_eq!(throwable.class_name, JAVA_LANG_LINKAGE_ERROR, "{}", throwable);
+        throwable.message
+    }
+
+    #[test]
+    fn method_resolution_constrains_the_descriptor() {
+        let mut vm = two_loaders(true);
+        assert_eq!(message(resolve(&mut vm, m, interpreter::resolve_method)), VIOLATION);
+        // the same class for both loaders
+        let mut vm = two_loaders(false);
+        assert!(resolve(&mut vm, m, interpreter::resolve_method).is_ok());
+    }
+
+    #[test]
+    fn field_resolution_constrains_the_type() {
+        let mut vm = two_loaders(true);
+        assert_eq!(message(resolve(&mut vm, t, resolve_static_field)), VIOLATION);
+        let mut vm = two_loaders(false);
+        assert!(resolve(&mut vm, t, resolve_static_field).is_ok());
+    }
+
+    #[test]
+    fn constraint_holds_for_a_class_loaded_afterwards() {
+        let mut vm = two_loaders(false);
+        resolve(&mut vm, t, resolve_static_field).expect("resolved field");
+        // T of the application loader must be T of the bootstrap loader from now on
+        let bytes = ClassBuilder::new("T", Some(JAVA_LANG_OBJECT)).bytes();
+        let result = vm.class_loaders.define_class(&mut vm.method_area, APP_LOADER, "T", bytes).map(|_| ());
+        assert_eq!(message(result), "loader constraint violation: loader 'app' wants to load class T. \
+            A different class with the same name was previously loaded by 'bootstrap'.");
+    }
+
+    #[test]
+    fn overriding_constrains_the_descriptor() {
+        let overriding = |vm: &mut Vm| {
+            let class = ClassBuilder::new("E", Some("D"))
+                .method(ACC_PUBLIC, "m", "(LT;)I", Some(Code::new(1, 2, vec![0x04, 0xac])))
+                .define(vm, APP_LOADER);
+            vm.link_class(&class)
+        };
+        let mut vm = two_loaders(true);
+        assert_eq!(message(overriding(&mut vm)), VIOLATION);
+        let mut vm = two_loaders(false);
+        assert!(overriding(&mut vm).is_ok());
+    }
+
+    #[test]
+    fn implementing_an_interface_constrains_the_descriptor() {
+        let implementing = |vm: &mut Vm| {
+            ClassBuilder::new("I", Some(JAVA_LANG_OBJECT))
+                .access_flags(ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT)
+                .method(ACC_PUBLIC | ACC_ABSTRACT, "n", "()LT;", None)
+                .define(vm, BOOTSTRAP_LOADER);
+            let class = ClassBuilder::new("F", Some(JAVA_LANG_OBJECT))
+                .interface("I")
+                .method(ACC_PUBLIC, "n", "()LT;", Some(Code::new(1, 1, vec![0x01, 0xb0])))
+                .define(vm, APP_LOADER);
+            vm.link_class(&class)
+        };
+        let mut vm = two_loaders(true);
+        assert_eq!(message(implementing(&mut vm)), VIOLATION);
+        let mut vm = two_loaders(false);
+        assert!(implementing(&mut vm).is_ok());
+    }
+
+    /// the message of the ClassFormatError defining `T` of `bytes` throws
+    fn format_error(bytes: Vec<u8>) -> String {
+        let mut vm = test_vm(VmOptions::default());
+        let throwable = vm.class_loaders.define_class(&mut vm.method_area, APP_LOADER, "T", bytes).map(|_| ()).expect_err("ClassFormatError");
+        assert_eq!(throwable.class_name, JAVA_LANG_CLASS_FORMAT_ERROR, "{}", throwable);
+        throwable.message
+    }
+
+    /// a class with a constant, a field with a ConstantValue attribute, a method with code and a SourceFile attribute
+    fn class_file() -> ClassBuilder {
+        let mut builder = ClassBuilder::new("T", Some(JAVA_LANG_OBJECT));
+        let (seven, source_file) = (builder.integer(7), builder.utf8("T.java"));
+        builder.field_with_constant(ACC_PUBLIC | ACC_STATIC | ACC_FINAL, "SEVEN", "I", seven)
+            .method(ACC_PUBLIC | ACC_STATIC, "m", "()I", Some(Code::new(1, 0, vec![0x10, 0x07, 0xac])))
+            .class_attribute("SourceFile", source_file.to_be_bytes().to_vec())
+    }
+
+    #[test]
+    fn class_file_is_parsed() {
+        let mut vm = test_vm(VmOptions::default());
+        let class = vm.class_loaders.define_class(&mut vm.method_area, APP_LOADER, "T", class_file().bytes()).expect("defined class");
+        assert_eq!(class.source_file, "T.java");
+    }
+
+    #[test]
+    fn truncated_class_file() {
+        let bytes = class_file().bytes();
+        for length in 0..bytes.len() {
+            assert_eq!(format_error(bytes[..length].to_vec()), "Truncated class file", "{} of {} bytes", length, bytes.len());
+        }
+    }
+
+    #[test]
+    fn extra_bytes_after_the_class_file() {
+        let mut bytes = class_file().bytes();
+        bytes.push(0);
+        assert_eq!(format_error(bytes), "Extra bytes at the end of class file T");
+    }
+
+    #[test]
+    fn not_a_class_file() {
+        // the local file header of a zip file
+        assert_eq!(format_error(b"PK\x03\x04\x14\x00\x08\x00".to_vec()), "Incompatible magic value 1347093252 in class file T");
+    }
+
+    #[test]
+    fn unknown_constant_tag() {
+        let mut bytes = class_file().bytes();
+        // the tag of the first constant follows the magic, the versions and the constant pool count
+        bytes[10] = 99;
+        assert_eq!(format_error(bytes), "Unknown constant tag 99 in class file T");
+    }
+
+    #[test]
+    fn constant_of_the_wrong_kind() {
+        // the host of a nest is a Class constant, not a Utf8 one
+        let mut builder = ClassBuilder::new("T", Some(JAVA_LANG_OBJECT));
+        let host = builder.utf8("H");
+        let bytes = builder.class_attribute("NestHost", host.to_be_bytes().to_vec()).bytes();
+        assert_eq!(format_error(bytes), format!("Invalid constant pool index {} in class file T", host));
+        // beyond the constant pool
+        let bytes = ClassBuilder::new("T", Some(JAVA_LANG_OBJECT)).class_attribute("SourceFile", vec![0xff, 0xff]).bytes();
+        assert_eq!(format_error(bytes), "Invalid constant pool index 65535 in class file T");
+    }
+
+    #[test]
+    fn attribute_of_the_wrong_length() {
+        let mut builder = ClassBuilder::new("T", Some(JAVA_LANG_OBJECT));
+        let source_file = builder.utf8("T.java");
+        let bytes = builder.class_attribute("SourceFile", [&source_file.to_be_bytes()[..], &[0]].concat()).bytes();
+        assert_eq!(format_error(bytes), "Invalid SourceFile attribute length 3 in class file T");
+    }
+}
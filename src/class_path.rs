@@ -0,0 +1,372 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use crate::jimage::JImage;
+use crate::manifest::{Manifest, MANIFEST_NAME};
+use crate::zip::ZipFile;
+
+#[cfg(windows)]
+pub const CLASS_PATH_SEPARATOR: char = ';';
+#[cfg(not(windows))]
+pub const CLASS_PATH_SEPARATOR: char = ':';
+
+pub const CLASS_PATH_WILDCARD: &str = "*";
+pub const CLASS_FILE_SUFFIX: &str = ".class";
+pub const JIMAGE_FILE: &str = "lib/modules";
+pub const JMODS_DIR: &str = "jmods";
+pub const JMOD_CLASSES_DIR: &str = "classes/";
+pub const JMOD_SUFFIX: &str = ".jmod";
+
+// modules of the runtime image defined to the boot and platform loaders, the others go to the application loader,
+// see jdk.internal.module.ModuleLoaderMap
+const BOOT_MODULES: [&str; 22] = [
+    "java.base", "java.datatransfer", "java.desktop", "java.instrument", "java.logging", "java.management",
+    "java.management.rmi", "java.naming", "java.prefs", "java.rmi", "java.security.sasl", "java.xml",
+    "jdk.internal.vm.ci", "jdk.jfr", "jdk.management", "jdk.management.agent", "jdk.management.jfr",
+    "jdk.naming.rmi", "jdk.net", "jdk.nio.mapmode", "jdk.sctp", "jdk.unsupported"
+];
+const PLATFORM_MODULES: [&str; 22] = [
+    "java.compiler", "java.net.http", "java.scripting", "java.security.jgss", "java.smartcardio", "java.sql",
+    "java.sql.rowset", "java.transaction.xa", "java.xml.crypto", "jdk.accessibility", "jdk.charsets",
+    "jdk.crypto.cryptoki", "jdk.crypto.ec", "jdk.dynalink", "jdk.httpserver", "jdk.jsobject", "jdk.localedata",
+    "jdk.naming.dns", "jdk.security.auth", "jdk.security.jgss", "jdk.xml.dom", "jdk.zipfs"
+];
+
+/// the built-in class loader which a module of the runtime image is defined to
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ModuleLoader {
+    Boot,
+    Platform,
+    App
+}
+
+impl ModuleLoader {
+    pub fn of(module: &str) -> Self {
+        if BOOT_MODULES.contains(&module) {
+            ModuleLoader::Boot
+        } else if PLATFORM_MODULES.contains(&module) {
+            ModuleLoader::Platform
+        } else {
+            ModuleLoader::App
+        }
+    }
+}
+
+/// one element of the class search path, classes are located by their resource name,
+/// e.g. `com/acme/Foo.class`
+pub enum ClassPathEntry {
+    Directory(PathBuf),
+    Jar(ZipFile, Option<Manifest>),
+    JImage(Rc<JImage>, ModuleLoader), // only the modules defined to the loader
+    Jmod(ZipFile)
+}
+
+impl ClassPathEntry {
+    fn new(path: &Path) -> Option<Self> {
+        if path.is_dir() {
+            Some(ClassPathEntry::Directory(path.to_path_buf()))
+        } else if path.is_file() {
+            match ZipFile::open(path) {
+                Ok(zip) => {
+                    let manifest = match zip.read(MANIFEST_NAME) {
+                        Some(Ok(bytes)) => Some(Manifest::parse(&bytes)),
+                        _ => None
+                    };
+                    Some(ClassPathEntry::Jar(zip, manifest))
+                }
+                Err(error) => {
+                    eprintln!("Warning: ignore class path entry {}", error);
+                    None
+                }
+            }
+        } else {
+            None // non-existent entries are ignored like java does
+        }
+    }
+
+    fn manifest(&self) -> Option<&Manifest> {
+        match self {
+            ClassPathEntry::Jar(_, manifest) => manifest.as_ref(),
+            _ => None
+        }
+    }
+
+    fn read(&self, resource_name: &str) -> Option<Vec<u8>> {
+        match self {
+            ClassPathEntry::Directory(dir) => {
+                let file = dir.join(resource_name);
+                if file.is_file() {
+                    fs::read(file).ok()
+                } else {
+                    None
+                }
+            }
+            ClassPathEntry::Jar(zip, _) => warn_on_error(zip.read(resource_name)?),
+            ClassPathEntry::JImage(jimage, loader) => {
+                let module = jimage.module_of_resource(resource_name)?;
+                if ModuleLoader::of(&module) != *loader {
+                    return None;
+                }
+                warn_on_error(jimage.read(&["/", &module, "/", resource_name].concat())?)
+            }
+            ClassPathEntry::Jmod(zip) => warn_on_error(zip.read(&[JMOD_CLASSES_DIR, resource_name].concat())?)
+        }
+    }
+
+    /// the module of a resource of the runtime image, none for the class path
+    fn module_of(&self, resource_name: &str) -> Option<String> {
+        match self {
+            ClassPathEntry::JImage(jimage, _) => jimage.module_of_resource(resource_name),
+            ClassPathEntry::Jmod(zip) => zip.path.file_stem().map(|stem| stem.to_string_lossy().into_owned()),
+            _ => None
+        }
+    }
+
+    /// the jars or directories listed in `Class-Path` of the manifest, relative to the jar's directory
+    fn manifest_class_path(&self) -> Vec<PathBuf> {
+        match self {
+            ClassPathEntry::Jar(zip, Some(manifest)) => {
+                let dir = zip.path.parent().unwrap_or_else(|| Path::new("."));
+                manifest.class_path().iter()
+                    .map(|url| dir.join(url.strip_prefix("file:").unwrap_or(url)))
+                    .collect()
+            }
+            _ => vec![]
+        }
+    }
+}
+
+/// an ordered list of class path entries.
+/// The entry which a resource is found in (or not found at all) is memoized,
+/// so a repeated lookup never walks the entries again.
+pub struct ClassPath {
+    entries: Vec<ClassPathEntry>,
+    cache: RefCell<HashMap<String, Option<usize>>>
+}
+
+impl ClassPath {
+    pub fn new(entries: Vec<ClassPathEntry>) -> Self {
+        ClassPath {
+            entries,
+            cache: RefCell::new(HashMap::new())
+        }
+    }
+
+    /// parse a class search path like `-cp a:b/*:c`, an empty element means the current directory
+    /// and `dir/*` expands to all the jar files in `dir`.
+    /// The `Class-Path` of a jar's manifest is searched right after the jar itself.
+    pub fn parse(class_path: &str) -> Self {
+        let mut entries = vec![];
+        let mut visited = HashSet::new();
+        for element in class_path.split(CLASS_PATH_SEPARATOR) {
+            let element = if element.is_empty() { "." } else { element };
+            for path in expand_wildcard(element) {
+                ClassPath::add_entry(&mut entries, &mut visited, &path);
+            }
+        }
+        ClassPath::new(entries)
+    }
+
+    fn add_entry(entries: &mut Vec<ClassPathEntry>, visited: &mut HashSet<PathBuf>, path: &Path) {
+        let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if !visited.insert(canonical) {
+            return;
+        }
+        if let Some(entry) = ClassPathEntry::new(path) {
+            let dependencies = entry.manifest_class_path();
+            entries.push(entry);
+            for dependency in dependencies {
+                ClassPath::add_entry(entries, visited, &dependency);
+            }
+        }
+    }
+
+    /// the class paths of the runtime modules for the boot, platform and application loaders respectively,
+    /// from the `lib/modules` jimage of a JDK 9+, or the `jmods/*.jmod` if there is no jimage
+    pub fn runtime(java_home: &Path) -> io::Result<(Self, Self, Self)> {
+        let jimage = java_home.join(JIMAGE_FILE);
+        if jimage.is_file() {
+            let jimage = Rc::new(JImage::open(&jimage)?);
+            return Ok((
+                ClassPath::new(vec![ClassPathEntry::JImage(jimage.clone(), ModuleLoader::Boot)]),
+                ClassPath::new(vec![ClassPathEntry::JImage(jimage.clone(), ModuleLoader::Platform)]),
+                ClassPath::new(vec![ClassPathEntry::JImage(jimage, ModuleLoader::App)])
+            ));
+        }
+        let mut jmods: Vec<PathBuf> = fs::read_dir(java_home.join(JMODS_DIR))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.to_string_lossy().ends_with(JMOD_SUFFIX))
+            .collect();
+        jmods.sort();
+        let (mut boot, mut platform, mut app) = (vec![], vec![], vec![]);
+        for jmod in jmods {
+            let module = jmod.file_stem().map_or("".to_string(), |stem| stem.to_string_lossy().into_owned());
+            let entry = ClassPathEntry::Jmod(ZipFile::open(&jmod)?);
+            match ModuleLoader::of(&module) {
+                ModuleLoader::Boot => boot.push(entry),
+                ModuleLoader::Platform => platform.push(entry),
+                ModuleLoader::App => app.push(entry)
+            }
+        }
+        Ok((ClassPath::new(boot), ClassPath::new(platform), ClassPath::new(app)))
+    }
+
+    /// search `other` after the entries of this class path
+    pub fn append(mut self, other: ClassPath) -> Self {
+        self.entries.extend(other.entries);
+        self.cache.borrow_mut().clear();
+        self
+    }
+
+    /// the class path when no `-cp` is specified: `CLASSPATH` environment variable or the current directory
+    pub fn from_env() -> Self {
+        match env::var("CLASSPATH") {
+            Ok(class_path) if !class_path.is_empty() => ClassPath::parse(&class_path),
+            _ => ClassPath::parse(".")
+        }
+    }
+
+    /// `Main-Class` in the manifest of the first entry, which is the jar of `-jar`
+    pub fn main_class(&self) -> Option<String> {
+        self.entries.first()
+            .and_then(|entry| entry.manifest())
+            .and_then(|manifest| manifest.main_class())
+            .map(|main_class| main_class.to_string())
+    }
+
+    /// find a class by its binary name in internal form, e.g. `com/acme/Foo`
+    pub fn find_class(&self, binary_name: &str) -> Option<Vec<u8>> {
+        self.find_resource(&[binary_name, CLASS_FILE_SUFFIX].concat())
+    }
+
+    /// the module of a class found by `find_class`, none if it is not in a module of the runtime image
+    pub fn module_of(&self, binary_name: &str) -> Option<String> {
+        let resource_name = [binary_name, CLASS_FILE_SUFFIX].concat();
+        let found = self.cache.borrow().get(&resource_name).copied().flatten()?;
+        self.entries[found].module_of(&resource_name)
+    }
+
+    pub fn find_resource(&self, resource_name: &str) -> Option<Vec<u8>> {
+        let cached = self.cache.borrow().get(resource_name).copied();
+        match cached {
+            Some(None) => None,
+            Some(Some(i)) => self.entries[i].read(resource_name),
+            None => {
+                let found = self.entries.iter().enumerate()
+                    .find_map(|(i, entry)| entry.read(resource_name).map(|bytes| (i, bytes)));
+                self.cache.borrow_mut().insert(resource_name.to_string(), found.as_ref().map(|(i, _)| *i));
+                found.map(|(_, bytes)| bytes)
+            }
+        }
+    }
+}
+
+fn warn_on_error(result: io::Result<Vec<u8>>) -> Option<Vec<u8>> {
+    match result {
+        Ok(bytes) => Some(bytes),
+        Err(error) => {
+            eprintln!("Warning: {}", error);
+            None
+        }
+    }
+}
+
+/// `dir/*` matches all the files with `.jar` or `.JAR` extension in `dir`, not recursively
+fn expand_wildcard(element: &str) -> Vec<PathBuf> {
+    let path = Path::new(element);
+    if path.file_name().is_some_and(|name| name == CLASS_PATH_WILDCARD) {
+        let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+        let mut jars: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "jar" || ext == "JAR"))
+                .collect(),
+            Err(_) => vec![]
+        };
+        jars.sort();
+        jars
+    } else {
+        vec![path.to_path_buf()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a new empty directory for a test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("jara-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, bytes: &[u8]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn elements_are_searched_in_order() {
+        let dir = temp_dir("class-path-order");
+        write(&dir.join("first/p/A.class"), b"first A");
+        write(&dir.join("second/p/A.class"), b"second A");
+        write(&dir.join("second/p/B.class"), b"second B");
+        let class_path = ClassPath::parse(&[
+            dir.join("missing"), dir.join("first"), dir.join("second"), dir.join("first")
+        ].map(|path| path.to_string_lossy().into_owned()).join(&CLASS_PATH_SEPARATOR.to_string()));
+        // a missing element is ignored and a repeated one is searched once
+        assert_eq!(class_path.entries.len(), 2);
+        assert_eq!(class_path.find_class("p/A").unwrap(), b"first A");
+        assert_eq!(class_path.find_class("p/B").unwrap(), b"second B");
+        assert!(class_path.find_class("p/C").is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn empty_element_is_the_current_directory() {
+        let class_path = ClassPath::parse(&format!("{}", CLASS_PATH_SEPARATOR));
+        assert!(matches!(class_path.entries.as_slice(), [ClassPathEntry::Directory(dir)] if dir == Path::new(".")));
+    }
+
+    #[test]
+    fn wildcard_expands_to_the_jars_of_a_directory() {
+        let dir = temp_dir("class-path-wildcard");
+        for name in ["b.jar", "a.JAR", "notes.txt", "jar", "nested/c.jar"] {
+            write(&dir.join(name), b"");
+        }
+        fs::create_dir(dir.join("d.jar")).unwrap();
+        let wildcard = dir.join(CLASS_PATH_WILDCARD);
+        // sorted, neither recursive nor matching directories
+        assert_eq!(expand_wildcard(&wildcard.to_string_lossy()), vec![dir.join("a.JAR"), dir.join("b.jar")]);
+        assert_eq!(expand_wildcard(&dir.join("b.jar").to_string_lossy()), vec![dir.join("b.jar")]);
+        assert!(expand_wildcard(&dir.join("missing").join(CLASS_PATH_WILDCARD).to_string_lossy()).is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn runtime_falls_back_to_jmods() {
+        let java_home = temp_dir("jmods-java-home");
+        // a jmod is a zip archive after a 4 bytes header, the classes are under classes/; the entries are stored
+        for (module, class) in [("java.base", "java/lang/Object"), ("java.sql", "java/sql/Driver"), ("jdk.jshell", "jdk/jshell/JShell")] {
+            let resource_name = [JMOD_CLASSES_DIR, class, CLASS_FILE_SUFFIX].concat();
+            let bytes = crate::zip::tests::archive(b"JM\x01\x00", &[(&resource_name, 0, class.as_bytes(), class.len())]);
+            write(&java_home.join(JMODS_DIR).join([module, JMOD_SUFFIX].concat()), &bytes);
+        }
+        assert!(!java_home.join(JIMAGE_FILE).exists());
+        let (boot, platform, app) = ClassPath::runtime(&java_home).unwrap();
+        assert!(matches!(boot.entries.as_slice(), [ClassPathEntry::Jmod(_)]));
+        assert_eq!(boot.find_class("java/lang/Object").unwrap(), b"java/lang/Object");
+        assert_eq!(boot.module_of("java/lang/Object").as_deref(), Some("java.base"));
+        assert!(boot.find_class("java/sql/Driver").is_none());
+        assert_eq!(platform.find_class("java/sql/Driver").unwrap(), b"java/sql/Driver");
+        assert_eq!(app.find_class("jdk/jshell/JShell").unwrap(), b"jdk/jshell/JShell");
+        assert_eq!(app.module_of("jdk/jshell/JShell").as_deref(), Some("jdk.jshell"));
+        fs::remove_dir_all(java_home).unwrap();
+    }
+}
@@ -12,6 +12,23 @@ pub const JVM_TAG_UTF8                  : u8 = 1;
 pub const JVM_TAG_METHOD_HANDLE         : u8 = 15;
 pub const JVM_TAG_METHOD_TYPE           : u8 = 16;
 pub const JVM_TAG_INVOKE_DYNAMIC        : u8 = 18;
+pub const JVM_TAG_DYNAMIC               : u8 = 17;
+pub const JVM_TAG_MODULE                : u8 = 19;
+pub const JVM_TAG_PACKAGE               : u8 = 20;
+
+// JVMS 5.4.3.5: the kinds of method handles
+pub const JVM_REF_GET_FIELD             : u8 = 1;
+#[allow(dead_code)]
+pub const JVM_REF_GET_STATIC            : u8 = 2;
+#[allow(dead_code)]
+pub const JVM_REF_PUT_FIELD             : u8 = 3;
+#[allow(dead_code)]
+pub const JVM_REF_PUT_STATIC            : u8 = 4;
+pub const JVM_REF_INVOKE_VIRTUAL        : u8 = 5;
+pub const JVM_REF_INVOKE_STATIC         : u8 = 6;
+pub const JVM_REF_INVOKE_SPECIAL        : u8 = 7;
+pub const JVM_REF_NEW_INVOKE_SPECIAL    : u8 = 8;
+pub const JVM_REF_INVOKE_INTERFACE      : u8 = 9;
 
 
 pub const JVM_SIGNATURE_ARRAY    : &str =  "[";
@@ -19,10 +36,13 @@ pub const JVM_SIGNATURE_BYTE     : &str =  "B";
 pub const JVM_SIGNATURE_CHAR     : &str =  "C";
 pub const JVM_SIGNATURE_CLASS    : &str =  "L";
 pub const JVM_SIGNATURE_ENDCLASS : &str =  ";";
+#[allow(dead_code)]
 pub const JVM_SIGNATURE_ENUM     : &str =  "E";
 pub const JVM_SIGNATURE_FLOAT    : &str =  "F";
 pub const JVM_SIGNATURE_DOUBLE   : &str =  "D";
+#[allow(dead_code)]
 pub const JVM_SIGNATURE_FUNC     : &str =  "(";
+#[allow(dead_code)]
 pub const JVM_SIGNATURE_ENDFUNC  : &str =  ")";
 pub const JVM_SIGNATURE_INT      : &str =  "I";
 pub const JVM_SIGNATURE_LONG     : &str =  "J";
@@ -31,5 +51,80 @@ pub const JVM_SIGNATURE_VOID     : &str =  "V";
 pub const JVM_SIGNATURE_BOOLEAN  : &str =  "Z";
 
 
+pub const JAVA_LANG_OBJECT: &str = "java/lang/Object";
+pub const JAVA_LANG_CLASS: &str = "java/lang/Class";
+pub const JAVA_LANG_STRING: &str = "java/lang/String";
+pub const JAVA_LANG_CLONEABLE: &str = "java/lang/Cloneable";
+pub const JAVA_IO_SERIALIZABLE: &str = "java/io/Serializable";
+
+// a field of java.lang.Class injected by the VM, as HotSpot's java_lang_Class::_klass_offset
+pub const INJECTED_KLASS_FIELD_NAME: &str = "klass";
+
+// JVMS 4.4.1: an array type descriptor is valid only if it represents 255 or fewer dimensions
+pub const MAX_ARRAY_DIMENSIONS: usize = 255;
+
+pub const CLASS_INITIALIZER_NAME: &str = "<clinit>";
+pub const CLASS_INITIALIZER_DESCRIPTOR: &str = "()V";
+pub const OBJECT_INITIALIZER_NAME: &str = "<init>";
+pub const MAIN_METHOD_NAME: &str = "main";
+pub const MAIN_METHOD_DESCRIPTOR: &str = "([Ljava/lang/String;)V";
+
+
 pub type FieldAccessFlag = u16;
-pub type MethodAccessFlag = u16;
\ No newline at end of file
+pub type MethodAccessFlag = u16;
+
+pub const ACC_PUBLIC       : u16 = 0x0001;
+pub const ACC_PRIVATE      : u16 = 0x0002;
+pub const ACC_PROTECTED    : u16 = 0x0004;
+pub const ACC_STATIC       : u16 = 0x0008;
+pub const ACC_FINAL        : u16 = 0x0010;
+pub const ACC_SUPER        : u16 = 0x0020;
+#[allow(dead_code)]
+pub const ACC_SYNCHRONIZED : u16 = 0x0020;
+#[allow(dead_code)]
+pub const ACC_VOLATILE     : u16 = 0x0040;
+pub const ACC_BRIDGE       : u16 = 0x0040;
+#[allow(dead_code)]
+pub const ACC_TRANSIENT    : u16 = 0x0080;
+#[allow(dead_code)]
+pub const ACC_VARARGS      : u16 = 0x0080;
+pub const ACC_NATIVE       : u16 = 0x0100;
+pub const ACC_INTERFACE    : u16 = 0x0200;
+pub const ACC_ABSTRACT     : u16 = 0x0400;
+#[allow(dead_code)]
+pub const ACC_STRICT       : u16 = 0x0800;
+pub const ACC_SYNTHETIC    : u16 = 0x1000;
+#[allow(dead_code)]
+pub const ACC_ANNOTATION   : u16 = 0x2000;
+#[allow(dead_code)]
+pub const ACC_ENUM         : u16 = 0x4000;
+#[allow(dead_code)]
+pub const ACC_MODULE       : u16 = 0x8000;
+
+
+pub const JAVA_LANG_CLASS_NOT_FOUND_EXCEPTION     : &str = "java/lang/ClassNotFoundException";
+pub const JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR      : &str = "java/lang/NoClassDefFoundError";
+pub const JAVA_LANG_LINKAGE_ERROR                 : &str = "java/lang/LinkageError";
+pub const JAVA_LANG_CLASS_FORMAT_ERROR            : &str = "java/lang/ClassFormatError";
+pub const JAVA_LANG_UNSUPPORTED_CLASS_VERSION_ERROR: &str = "java/lang/UnsupportedClassVersionError";
+pub const JAVA_LANG_CLASS_CIRCULARITY_ERROR       : &str = "java/lang/ClassCircularityError";
+pub const JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR: &str = "java/lang/IncompatibleClassChangeError";
+pub const JAVA_LANG_VERIFY_ERROR                  : &str = "java/lang/VerifyError";
+pub const JAVA_LANG_ERROR                         : &str = "java/lang/Error";
+pub const JAVA_LANG_EXCEPTION_IN_INITIALIZER_ERROR: &str = "java/lang/ExceptionInInitializerError";
+pub const JAVA_LANG_INTERNAL_ERROR                : &str = "java/lang/InternalError";
+pub const JAVA_LANG_UNSATISFIED_LINK_ERROR        : &str = "java/lang/UnsatisfiedLinkError";
+pub const JAVA_LANG_NULL_POINTER_EXCEPTION        : &str = "java/lang/NullPointerException";
+pub const JAVA_LANG_NO_SUCH_FIELD_ERROR           : &str = "java/lang/NoSuchFieldError";
+pub const JAVA_LANG_NO_SUCH_METHOD_ERROR          : &str = "java/lang/NoSuchMethodError";
+pub const JAVA_LANG_ABSTRACT_METHOD_ERROR         : &str = "java/lang/AbstractMethodError";
+pub const JAVA_LANG_ILLEGAL_ACCESS_ERROR          : &str = "java/lang/IllegalAccessError";
+pub const JAVA_LANG_BOOTSTRAP_METHOD_ERROR        : &str = "java/lang/BootstrapMethodError";
+pub const JAVA_LANG_INSTANTIATION_ERROR           : &str = "java/lang/InstantiationError";
+pub const JAVA_LANG_STACK_OVERFLOW_ERROR          : &str = "java/lang/StackOverflowError";
+pub const JAVA_LANG_OUT_OF_MEMORY_ERROR           : &str = "java/lang/OutOfMemoryError";
+pub const JAVA_LANG_CLASS_CAST_EXCEPTION          : &str = "java/lang/ClassCastException";
+pub const JAVA_LANG_ARITHMETIC_EXCEPTION          : &str = "java/lang/ArithmeticException";
+pub const JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION: &str = "java/lang/ArrayIndexOutOfBoundsException";
+pub const JAVA_LANG_NEGATIVE_ARRAY_SIZE_EXCEPTION : &str = "java/lang/NegativeArraySizeException";
+pub const JAVA_LANG_ARRAY_STORE_EXCEPTION         : &str = "java/lang/ArrayStoreException";
@@ -0,0 +1,548 @@
+use std::rc::Rc;
+use crate::constants::*;
+use crate::exception::Throwable;
+use crate::interpreter::{method_name, method_signature};
+use crate::types::{Class, Field, ItableEntry, Method, Selection, Type};
+use crate::verifier::is_same_package;
+use crate::vm::Vm;
+
+/// lay out the vtable and the itable of `class`, its superclass and superinterfaces are linked.
+/// A method takes the vtable entries of the methods it overrides (JVMS 5.4.5), or a new one after them.
+pub fn link_methods(class: &Rc<Class>) {
+    let mut vtable: Vec<Rc<Method>> = match &class.super_class {
+        Some(super_class) if !class.is_interface() => super_class.vtable().to_vec(),
+        _ => vec![]
+    };
+    if !class.is_interface() {
+        for method in class.methods.iter().filter(|method| is_virtual(method)) {
+            let mut index = None;
+            for (i, entry) in vtable.iter_mut().enumerate() {
+                if entry.name() == method.name() && entry.descriptor() == method.descriptor() && can_override(method, entry) {
+                    *entry = method.clone();
+                    index.get_or_insert(i);
+                }
+            }
+            let index = index.unwrap_or_else(|| {
+                vtable.push(method.clone());
+                vtable.len() - 1
+            });
+            let _ = method.vtable_index.set(index);
+        }
+    }
+    let itable = if class.is_interface() {
+        vec![]
+    } else {
+        superinterfaces(class).iter().map(|interface| {
+            let methods = interface.methods.iter()
+                .map(|method| if is_virtual(method) { select_interface_method(class, method) } else { Selection::Missing })
+                .collect();
+            ItableEntry { interface: interface.id, methods }
+        }).collect()
+    };
+    let _ = class.vtable.set(vtable);
+    let _ = class.itable.set(itable);
+}
+
+// a method selected by invokevirtual or invokeinterface rather than invoked as resolved
+fn is_virtual(method: &Method) -> bool {
+    !method.is_static() && method.access_flags() & ACC_PRIVATE == 0
+        && method.name() != OBJECT_INITIALIZER_NAME && method.name() != CLASS_INITIALIZER_NAME
+}
+
+// a package private method is overridden in its run-time package only
+fn can_override(method: &Method, overridden: &Method) -> bool {
+    overridden.access_flags() & (ACC_PUBLIC | ACC_PROTECTED) != 0 || is_same_package(&method.class(), &overridden.class())
+}
+
+/// the interfaces implemented by `class` and its superclasses, direct or indirect, each once
+fn superinterfaces(class: &Rc<Class>) -> Vec<Rc<Class>> {
+    let mut result: Vec<Rc<Class>> = vec![];
+    let mut pending: Vec<Rc<Class>> = superclasses(class).flat_map(|class| class.interfaces.clone()).collect();
+    pending.reverse();
+    while let Some(interface) = pending.pop() {
+        if !result.iter().any(|found| found.id == interface.id) {
+            pending.extend(interface.interfaces.iter().rev().cloned());
+            result.push(interface);
+        }
+    }
+    result
+}
+
+/// the class and its superclasses, nearest first
+pub fn superclasses(class: &Rc<Class>) -> impl Iterator<Item = Rc<Class>> {
+    std::iter::successors(Some(class.clone()), |class| class.super_class.clone())
+}
+
+/// JVMS 5.4.6 selection of the interface method `method` for an object of `class`: an instance method of the class
+/// or its superclasses, otherwise the default method of the superinterfaces
+fn select_interface_method(class: &Rc<Class>, method: &Method) -> Selection {
+    let declared = superclasses(class).find_map(|class| class.find_method(method.name(), method.descriptor())
+        .filter(|selected| !selected.is_static() && selected.access_flags() & ACC_PRIVATE == 0));
+    match declared {
+        Some(declared) => Selection::Method(declared),
+        None => select_default(class, method.name(), method.descriptor())
+    }
+}
+
+/// the only default method among the maximally-specific superinterface methods of `class`
+pub fn select_default(class: &Rc<Class>, name: &str, descriptor: &str) -> Selection {
+    let mut defaults = maximally_specific_methods(class, name, descriptor).into_iter().filter(|method| !method.is_abstract());
+    match (defaults.next(), defaults.next()) {
+        (Some(method), None) => Selection::Method(method),
+        (Some(method), Some(other)) => Selection::Conflict(method, other),
+        (None, _) => Selection::Missing
+    }
+}
+
+/// JVMS 5.4.3.3 the maximally-specific superinterface methods of `class`: the instance methods of its superinterfaces,
+/// unless a subinterface of the one declaring it declares one too
+pub fn maximally_specific_methods(class: &Rc<Class>, name: &str, descriptor: &str) -> Vec<Rc<Method>> {
+    let candidates: Vec<Rc<Method>> = superinterfaces(class).iter()
+        .filter_map(|interface| interface.find_method(name, descriptor))
+        .filter(|method| !method.is_static() && method.access_flags() & ACC_PRIVATE == 0)
+        .collect();
+    candidates.iter()
+        .filter(|method| {
+            let interface = method.class();
+            !candidates.iter().any(|other| other.class().id != interface.id && other.class().implements(&interface))
+        })
+        .cloned()
+        .collect()
+}
+
+/// the method of the superinterfaces of `class` method resolution finds (JVMS 5.4.3.3): the only maximally-specific
+/// default method, otherwise any instance method of a superinterface
+pub fn find_interface_method(class: &Rc<Class>, name: &str, descriptor: &str) -> Option<Rc<Method>> {
+    match select_default(class, name, descriptor) {
+        Selection::Method(method) => Some(method),
+        _ => superinterfaces(class).iter()
+            .filter_map(|interface| interface.find_method(name, descriptor))
+            .find(|method| !method.is_static() && method.access_flags() & ACC_PRIVATE == 0)
+    }
+}
+
+/// invokevirtual on an object of `class`: the vtable entry of the resolved method, which a private method has not.
+/// A method of a superinterface is selected by the itable.
+pub fn select_virtual(class: &Class, resolved: Rc<Method>) -> Result<Rc<Method>, Throwable> {
+    if resolved.access_flags() & ACC_PRIVATE != 0 {
+        return Ok(resolved);
+    }
+    if resolved.class().is_interface() {
+        return select_interface(class, resolved);
+    }
+    let selected = resolved.vtable_index.get().and_then(|&index| class.vtable().get(index))
+        .ok_or_else(|| Throwable::new(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, format!("Class {} does not extend {}",
+            class.name.replace('/', "."), resolved.class().name.replace('/', "."))))?;
+    implementation(Selection::Method(selected.clone()), &resolved)
+}
+
+/// invokeinterface on an object of `class`: the itable entry of the resolved method. A method of Object is selected
+/// by the vtable, a private one is invoked as resolved.
+pub fn select_interface(class: &Class, resolved: Rc<Method>) -> Result<Rc<Method>, Throwable> {
+    let interface = resolved.class();
+    if resolved.access_flags() & ACC_PRIVATE != 0 {
+        return Ok(resolved);
+    }
+    if !interface.is_interface() {
+        return select_virtual(class, resolved);
+    }
+    let methods = class.itable().iter().find(|entry| entry.interface == interface.id).map(|entry| &entry.methods)
+        .ok_or_else(|| Throwable::new(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, format!("Class {} does not implement the requested interface {}",
+            class.name.replace('/', "."), interface.name.replace('/', "."))))?;
+    implementation(methods[resolved.index()].clone(), &resolved)
+}
+
+/// invokespecial from `class` of a method resolved from `referenced`. Constructors and private methods are invoked
+/// as resolved. When ACC_SUPER is set and `referenced` is a superclass of `class`, the method is selected for the direct
+/// superclass instead. A class selects from its vtable or itable, an interface declaring no such method selects
+/// the default method of its superinterfaces, `I.super.m()`.
+pub fn select_special(class: &Class, referenced: &Rc<Class>, resolved: Rc<Method>) -> Result<Rc<Method>, Throwable> {
+    if resolved.name() == OBJECT_INITIALIZER_NAME || resolved.access_flags() & ACC_PRIVATE != 0 {
+        return Ok(resolved);
+    }
+    let referenced = match &class.super_class {
+        Some(super_class) if class.access_flags & ACC_SUPER != 0 && !referenced.is_interface() && super_class.is_subclass_of(referenced) =>
+            super_class,
+        _ => referenced
+    };
+    if !referenced.is_interface() {
+        return select_virtual(referenced, resolved);
+    }
+    let selected = match referenced.find_method(resolved.name(), resolved.descriptor()) {
+        Some(declared) if !declared.is_static() => Selection::Method(declared),
+        // the public methods of Object are members of an interface
+        _ if resolved.class().id != referenced.id && !resolved.class().is_interface() => Selection::Method(resolved.clone()),
+        _ => select_default(referenced, resolved.name(), resolved.descriptor())
+    };
+    implementation(selected, &resolved)
+}
+
+// the access of a member in the message of IllegalAccessError
+fn access(access_flags: u16) -> &'static str {
+    match access_flags & (ACC_PRIVATE | ACC_PROTECTED) {
+        ACC_PRIVATE => "private ",
+        ACC_PROTECTED => "protected ",
+        _ => ""
+    }
+}
+
+// an abstract method has no implementation to invoke, and a class can not inherit two default methods
+fn implementation(selected: Selection, resolved: &Method) -> Result<Rc<Method>, Throwable> {
+    match selected {
+        Selection::Method(method) if !method.is_abstract() => Ok(method),
+        Selection::Method(_) | Selection::Missing => Err(Throwable::new(JAVA_LANG_ABSTRACT_METHOD_ERROR, method_name(resolved))),
+        Selection::Conflict(method, other) => Err(Throwable::new(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, format!(
+            "Conflicting default methods: {}.{} {}.{}", method.class().name.replace('/', "."), method.name(),
+            other.class().name.replace('/', "."), other.name())))
+    }
+}
+
+impl Vm {
+    /// JVMS 5.4.4 access control of a class or interface resolved by `class`, an array class is as accessible as its
+    /// element class
+    pub fn check_class_access(&mut self, class: &Rc<Class>, resolved: &Rc<Class>) -> Result<(), Throwable> {
+        let resolved = match resolved.element_type.as_deref() {
+            Some(Type::Class(element)) => element,
+            Some(_) => return Ok(()),
+            None => resolved
+        };
+        if resolved.access_flags & ACC_PUBLIC != 0 || is_same_package(class, resolved) {
+            return Ok(());
+        }
+        Err(Throwable::new(JAVA_LANG_ILLEGAL_ACCESS_ERROR, format!("failed to access class {} from class {}",
+            resolved.name.replace('/', "."), class.name.replace('/', "."))))
+    }
+
+    /// JVMS 5.4.4 access control of a method resolved by `class`, a private method is accessible to the nestmates of its class
+    pub fn check_method_access(&mut self, class: &Rc<Class>, method: &Method) -> Result<(), Throwable> {
+        let declaring_class = method.class();
+        if self.is_accessible(class, &declaring_class, method.access_flags()) {
+            return Ok(());
+        }
+        Err(Throwable::new(JAVA_LANG_ILLEGAL_ACCESS_ERROR, format!("class {} tried to access {}method '{}'",
+            class.name.replace('/', "."), access(method.access_flags()), method_signature(&declaring_class, method.name(), method.descriptor()))))
+    }
+
+    /// JVMS 5.4.4 access control of a field resolved by `class`, as for a method
+    pub fn check_field_access(&mut self, class: &Rc<Class>, field: &Field) -> Result<(), Throwable> {
+        let declaring_class = field.class();
+        if self.is_accessible(class, &declaring_class, field.access_flags()) {
+            return Ok(());
+        }
+        Err(Throwable::new(JAVA_LANG_ILLEGAL_ACCESS_ERROR, format!("class {} tried to access {}field {}.{}",
+            class.name.replace('/', "."), access(field.access_flags()), declaring_class.name.replace('/', "."), field.name())))
+    }
+
+    // a member of `declaring_class` with `access_flags` is accessible to `class`
+    fn is_accessible(&mut self, class: &Rc<Class>, declaring_class: &Rc<Class>, access_flags: u16) -> bool {
+        if access_flags & ACC_PUBLIC != 0 {
+            true
+        } else if access_flags & ACC_PRIVATE != 0 {
+            class.id == declaring_class.id || self.nest_host(class).id == self.nest_host(declaring_class).id
+        } else if access_flags & ACC_PROTECTED != 0 {
+            is_same_package(class, declaring_class) || class.is_subclass_of(declaring_class)
+        } else {
+            is_same_package(class, declaring_class)
+        }
+    }
+
+    /// JVMS 5.4.5: the methods of `class` overriding methods of classes or interfaces defined by other loaders
+    /// constrain the classes in their descriptors to be the same for both loaders
+    pub fn add_override_constraints(&mut self, class: &Rc<Class>) -> Result<(), Throwable> {
+        let mut overrides: Vec<(Rc<Method>, Rc<Method>)> = vec![];
+        if let Some(super_class) = class.super_class.as_ref().filter(|_| !class.is_interface()) {
+            overrides.extend(super_class.vtable().iter().zip(class.vtable()).map(|(overridden, method)| (method.clone(), overridden.clone())));
+        }
+        for entry in class.itable() {
+            let interface = self.method_area.class(entry.interface).clone();
+            for (overridden, selection) in interface.methods.iter().zip(&entry.methods) {
+                if let Selection::Method(method) = selection {
+                    overrides.push((method.clone(), overridden.clone()));
+                }
+            }
+        }
+        for (method, overridden) in overrides {
+            self.class_loaders.add_descriptor_constraints(&self.method_area, method.descriptor(),
+                method.class().defining_loader, overridden.class().defining_loader)?;
+        }
+        Ok(())
+    }
+
+    /// the host of the nest of `class` (JVMS 5.4.4), the class itself unless its NestHost attribute names a class
+    /// of the same run-time package listing it as a member
+    pub fn nest_host(&mut self, class: &Rc<Class>) -> Rc<Class> {
+        if class.nest_host_name.is_empty() {
+            return class.clone();
+        }
+        match self.resolve_class(class.defining_loader, &class.nest_host_name) {
+            Ok(host) if is_same_package(&host, class) && host.nest_member_names.contains(&class.name) => host,
+            _ => class.clone()
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_builder::{test_vm, ClassBuilder, Code};
+    use crate::class_loader::{ClassLoaders, BOOTSTRAP_LOADER};
+    use crate::class_path::ClassPath;
+    use crate::interpreter;
+    use crate::method_area::MethodArea;
+    use crate::thread::JavaThread;
+    use crate::vm::VmOptions;
+
+    const INTERFACE: u16 = ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT;
+    const DEFAULT: u16 = ACC_PUBLIC;
+    const ABSTRACT: u16 = ACC_PUBLIC | ACC_ABSTRACT;
+
+    /// the classes of a test, defined by the bootstrap loader from class files without code
+    struct Hierarchy {
+        loaders: ClassLoaders,
+        method_area: MethodArea
+    }
+
+    impl Hierarchy {
+        fn new() -> Self {
+            let mut hierarchy = Hierarchy {
+                loaders: ClassLoaders::new(ClassPath::new(vec![]), ClassPath::new(vec![]), ClassPath::new(vec![])),
+                method_area: MethodArea::new()
+            };
+            hierarchy.define(JAVA_LANG_OBJECT, ACC_PUBLIC, None, &[], &[]);
+            hierarchy
+        }
+
+        /// define and link a class declaring the methods `m()I` with the access flags in `methods`
+        fn define(&mut self, name: &str, access_flags: u16, super_class: Option<&str>, interfaces: &[&str], methods: &[u16]) -> Rc<Class> {
+            let mut constants: Vec<Vec<u8>> = vec![];
+            let this_class = class_constant(&mut constants, name);
+            let super_class = super_class.map_or(0, |super_class| class_constant(&mut constants, super_class));
+            let interfaces: Vec<u16> = interfaces.iter().map(|interface| class_constant(&mut constants, interface)).collect();
+            let (method_name, descriptor) = (utf8_constant(&mut constants, "m"), utf8_constant(&mut constants, "()I"));
+
+            let mut bytes = vec![0xCA, 0xFE, 0xBA, 0xBE, 0, 0, 0, 52];
+            bytes.extend((constants.len() as u16 + 1).to_be_bytes());
+            bytes.extend(constants.concat());
+            for u2 in [access_flags, this_class, super_class, interfaces.len() as u16].iter().chain(&interfaces) {
+                bytes.extend(u2.to_be_bytes());
+            }
+            bytes.extend([0, 0]); // fields
+            bytes.extend((methods.len() as u16).to_be_bytes());
+            for &access_flags in methods {
+                for u2 in [access_flags, method_name, descriptor, 0] {
+                    bytes.extend(u2.to_be_bytes());
+                }
+            }
+            bytes.extend([0, 0]); // attributes
+            let class = self.loaders.define_class(&mut self.method_area, BOOTSTRAP_LOADER, name, bytes).expect("defined class");
+            link_methods(&class);
+            class
+        }
+    }
+
+    fn utf8_constant(constants: &mut Vec<Vec<u8>>, value: &str) -> u16 {
+        constants.push([&[JVM_TAG_UTF8][..], &(value.len() as u16).to_be_bytes(), value.as_bytes()].concat());
+        constants.len() as u16
+    }
+
+    fn class_constant(constants: &mut Vec<Vec<u8>>, name: &str) -> u16 {
+        let name = utf8_constant(constants, name);
+        constants.push([&[JVM_TAG_CLASS][..], &name.to_be_bytes()].concat());
+        constants.len() as u16
+    }
+
+    /// the class declaring the method `invokeinterface interface.m()I` selects, or the error it throws
+    fn select(class: &Rc<Class>, interface: &Rc<Class>) -> Result<String, String> {
+        let resolved = interface.find_method("m", "()I")
+            .or_else(|| find_interface_method(interface, "m", "()I"))
+            .expect("resolved method");
+        select_interface(class, resolved)
+            .map(|selected| selected.class().name.clone())
+            .map_err(|throwable| throwable.class_name)
+    }
+
+    #[test]
+    fn diamond_inherits_the_shared_default() {
+        let mut hierarchy = Hierarchy::new();
+        let i = hierarchy.define("I", INTERFACE, Some(JAVA_LANG_OBJECT), &[], &[DEFAULT]);
+        let j = hierarchy.define("J", INTERFACE, Some(JAVA_LANG_OBJECT), &["I"], &[]);
+        hierarchy.define("K", INTERFACE, Some(JAVA_LANG_OBJECT), &["I"], &[]);
+        let c = hierarchy.define("C", ACC_PUBLIC, Some(JAVA_LANG_OBJECT), &["J", "K"], &[]);
+        assert_eq!(select(&c, &i), Ok("I".to_string()));
+        assert_eq!(select(&c, &j), Ok("I".to_string()));
+    }
+
+    #[test]
+    fn diamond_selects_the_more_specific_default() {
+        let mut hierarchy = Hierarchy::new();
+        let i = hierarchy.define("I", INTERFACE, Some(JAVA_LANG_OBJECT), &[], &[DEFAULT]);
+        hierarchy.define("J", INTERFACE, Some(JAVA_LANG_OBJECT), &["I"], &[DEFAULT]);
+        let k = hierarchy.define("K", INTERFACE, Some(JAVA_LANG_OBJECT), &["I"], &[]);
+        let c = hierarchy.define("C", ACC_PUBLIC, Some(JAVA_LANG_OBJECT), &["J", "K"], &[]);
+        assert_eq!(select(&c, &i), Ok("J".to_string()));
+        assert_eq!(select(&c, &k), Ok("J".to_string()));
+    }
+
+    #[test]
+    fn diamond_with_conflicting_defaults_is_incompatible() {
+        let mut hierarchy = Hierarchy::new();
+        let i = hierarchy.define("I", INTERFACE, Some(JAVA_LANG_OBJECT), &[], &[DEFAULT]);
+        hierarchy.define("J", INTERFACE, Some(JAVA_LANG_OBJECT), &["I"], &[DEFAULT]);
+        hierarchy.define("K", INTERFACE, Some(JAVA_LANG_OBJECT), &["I"], &[DEFAULT]);
+        let c = hierarchy.define("C", ACC_PUBLIC, Some(JAVA_LANG_OBJECT), &["J", "K"], &[]);
+        assert_eq!(select(&c, &i), Err(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR.to_string()));
+        // resolution still finds one of them
+        assert!(find_interface_method(&c, "m", "()I").is_some());
+    }
+
+    #[test]
+    fn diamond_with_reabstracted_default_is_abstract() {
+        let mut hierarchy = Hierarchy::new();
+        let i = hierarchy.define("I", INTERFACE, Some(JAVA_LANG_OBJECT), &[], &[DEFAULT]);
+        hierarchy.define("J", INTERFACE, Some(JAVA_LANG_OBJECT), &["I"], &[ABSTRACT]);
+        hierarchy.define("K", INTERFACE, Some(JAVA_LANG_OBJECT), &["I"], &[]);
+        let c = hierarchy.define("C", ACC_PUBLIC, Some(JAVA_LANG_OBJECT), &["J", "K"], &[]);
+        assert_eq!(select(&c, &i), Err(JAVA_LANG_ABSTRACT_METHOD_ERROR.to_string()));
+    }
+
+    #[test]
+    fn class_method_wins_over_conflicting_defaults() {
+        let mut hierarchy = Hierarchy::new();
+        hierarchy.define("J", INTERFACE, Some(JAVA_LANG_OBJECT), &[], &[DEFAULT]);
+        let k = hierarchy.define("K", INTERFACE, Some(JAVA_LANG_OBJECT), &[], &[DEFAULT]);
+        hierarchy.define("B", ACC_PUBLIC, Some(JAVA_LANG_OBJECT), &[], &[ACC_PUBLIC]);
+        let c = hierarchy.define("C", ACC_PUBLIC, Some("B"), &["J", "K"], &[]);
+        assert_eq!(select(&c, &k), Ok("B".to_string()));
+    }
+
+    #[test]
+    fn super_call_on_diamond_interface() {
+        let mut hierarchy = Hierarchy::new();
+        hierarchy.define("I", INTERFACE, Some(JAVA_LANG_OBJECT), &[], &[DEFAULT]);
+        hierarchy.define("J", INTERFACE, Some(JAVA_LANG_OBJECT), &["I"], &[DEFAULT]);
+        hierarchy.define("K", INTERFACE, Some(JAVA_LANG_OBJECT), &["I"], &[DEFAULT]);
+        // L inherits the conflicting defaults of J and K, M those of I and J
+        let l = hierarchy.define("L", INTERFACE, Some(JAVA_LANG_OBJECT), &["J", "K"], &[]);
+        let m = hierarchy.define("M", INTERFACE, Some(JAVA_LANG_OBJECT), &["I", "J"], &[]);
+        let c = hierarchy.define("C", ACC_PUBLIC | ACC_SUPER, Some(JAVA_LANG_OBJECT), &["L", "M"], &[ACC_PUBLIC]);
+        let special = |referenced: &Rc<Class>| {
+            let resolved = find_interface_method(referenced, "m", "()I").expect("resolved method");
+            select_special(&c, referenced, resolved)
+                .map(|selected| selected.class().name.clone())
+                .map_err(|throwable| throwable.class_name)
+        };
+        assert_eq!(special(&m), Ok("J".to_string()));
+        assert_eq!(special(&l), Err(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR.to_string()));
+    }
+
+    /// define and link a class declaring `m()I` with `access_flags` unless none, returning 0
+    fn class(vm: &mut Vm, name: &str, super_class: &str, interfaces: &[&str], access_flags: Option<u16>) -> Rc<Class> {
+        let mut builder = ClassBuilder::new(name, Some(super_class));
+        for interface in interfaces {
+            builder = builder.interface(interface);
+        }
+        if let Some(access_flags) = access_flags {
+            builder = builder.method(access_flags, "m", "()I", Some(Code::new(1, 1, vec![0x03, 0xac])));
+        }
+        let class = builder.define(vm, BOOTSTRAP_LOADER);
+        vm.link_class(&class).expect("linked class");
+        class
+    }
+
+    fn m(class: &Class) -> Rc<Method> {
+        class.find_method("m", "()I").expect("m")
+    }
+
+    fn declaring_class(selected: Result<Rc<Method>, Throwable>) -> Result<String, String> {
+        selected.map(|method| method.class().name.clone()).map_err(|throwable| throwable.to_string())
+    }
+
+    #[test]
+    fn overriding_method_takes_the_vtable_entry() {
+        let mut vm = test_vm(VmOptions::default());
+        let a = class(&mut vm, "A", JAVA_LANG_OBJECT, &[], Some(ACC_PUBLIC));
+        let b = class(&mut vm, "B", "A", &[], Some(ACC_PUBLIC));
+        let c = class(&mut vm, "C", "B", &[], None);
+        assert_eq!(m(&b).vtable_index.get(), m(&a).vtable_index.get());
+        assert_eq!(c.vtable().len(), a.vtable().len());
+        assert_eq!(declaring_class(select_virtual(&c, m(&a))), Ok("B".to_string()));
+        assert_eq!(declaring_class(select_virtual(&a, m(&a))), Ok("A".to_string()));
+    }
+
+    #[test]
+    fn package_private_method_is_overridden_in_its_package_only() {
+        let mut vm = test_vm(VmOptions::default());
+        let a = class(&mut vm, "p/A", JAVA_LANG_OBJECT, &[], Some(0));
+        let b = class(&mut vm, "q/B", "p/A", &[], Some(ACC_PUBLIC));
+        let c = class(&mut vm, "p/C", "q/B", &[], Some(0));
+        // q/B.m takes a vtable entry of its own, p/C.m overrides both p/A.m and q/B.m
+        assert_ne!(m(&b).vtable_index.get(), m(&a).vtable_index.get());
+        assert_eq!(declaring_class(select_virtual(&b, m(&a))), Ok("p/A".to_string()));
+        assert_eq!(declaring_class(select_virtual(&c, m(&a))), Ok("p/C".to_string()));
+        assert_eq!(declaring_class(select_virtual(&c, m(&b))), Ok("p/C".to_string()));
+    }
+
+    #[test]
+    fn interface_method_is_selected_from_the_itable() {
+        let mut vm = test_vm(VmOptions::default());
+        let i = ClassBuilder::new("I", Some(JAVA_LANG_OBJECT))
+            .access_flags(INTERFACE)
+            .method(ABSTRACT, "m", "()I", None)
+            .define(&mut vm, BOOTSTRAP_LOADER);
+        class(&mut vm, "B", JAVA_LANG_OBJECT, &[], Some(ACC_PUBLIC));
+        // the method of the superclass implements the interface
+        let c = class(&mut vm, "C", "B", &["I"], None);
+        assert_eq!(declaring_class(select_interface(&c, m(&i))), Ok("B".to_string()));
+        // a private method does not
+        let d = class(&mut vm, "D", JAVA_LANG_OBJECT, &["I"], Some(ACC_PRIVATE));
+        assert_eq!(declaring_class(select_interface(&d, m(&i))), Err("java.lang.AbstractMethodError: I.m()I".to_string()));
+        let e = class(&mut vm, "E", JAVA_LANG_OBJECT, &[], Some(ACC_PUBLIC));
+        assert_eq!(declaring_class(select_interface(&e, m(&i))),
+            Err("java.lang.IncompatibleClassChangeError: Class E does not implement the requested interface I".to_string()));
+    }
+
+    #[test]
+    fn private_method_is_accessible_to_nestmates() {
+        let mut vm = test_vm(VmOptions::default());
+        let nest_members = |builder: &mut ClassBuilder, members: &[&str]| -> Vec<u8> {
+            let mut info = (members.len() as u16).to_be_bytes().to_vec();
+            for member in members {
+                info.extend(builder.class(member).to_be_bytes());
+            }
+            info
+        };
+        let mut builder = ClassBuilder::new("H", Some(JAVA_LANG_OBJECT));
+        let members = nest_members(&mut builder, &["H$M"]);
+        builder.class_attribute("NestMembers", members)
+            .method(ACC_PRIVATE | ACC_STATIC, "p", "()V", Some(Code::new(0, 0, vec![0xb1])))
+            .define(&mut vm, BOOTSTRAP_LOADER);
+        // H$M is a member of the nest of H, O and other/X claim to be one
+        let mut resolve = |name: &str| {
+            let mut builder = ClassBuilder::new(name, Some(JAVA_LANG_OBJECT));
+            let (host, p) = (builder.class("H"), builder.method_ref("H", "p", "()V"));
+            let class = builder.class_attribute("NestHost", host.to_be_bytes().to_vec()).define(&mut vm, BOOTSTRAP_LOADER);
+            interpreter::resolve_method(&mut vm, &class, p).map(|_| ()).map_err(|throwable| throwable.to_string())
+        };
+        assert_eq!(resolve("H$M"), Ok(()));
+        assert_eq!(resolve("O"), Err("java.lang.IllegalAccessError: class O tried to access private method 'void H.p()'".to_string()));
+        assert_eq!(resolve("other/X"), Err("java.lang.IllegalAccessError: class other.X tried to access private method 'void H.p()'".to_string()));
+    }
+
+    #[test]
+    fn method_reference_is_resolved_once() {
+        let mut vm = test_vm(VmOptions::default());
+        let mut builder = ClassBuilder::new("C", Some(JAVA_LANG_OBJECT));
+        let [high, low] = builder.method_ref("C", "m", "()V").to_be_bytes();
+        let class = builder.method(ACC_STATIC, "m", "()V", Some(Code::new(0, 0, vec![0xb1])))
+            .method(ACC_STATIC, "run", "()V", Some(Code::new(0, 0, vec![0xb8, high, low, 0xb8, high, low, 0xb1])))
+            .define(&mut vm, BOOTSTRAP_LOADER);
+        let run = class.find_method("run", "()V").expect("run");
+        interpreter::invoke(&mut vm, &mut JavaThread::main(), &run, vec![]).expect("invoked");
+        let resolved_methods = class.resolved_methods.borrow();
+        assert_eq!(resolved_methods.len(), 1);
+        let (referenced, method) = &resolved_methods[&u16::from_be_bytes([high, low])];
+        assert_eq!(referenced.id, class.id);
+        assert!(Rc::ptr_eq(method, &class.find_method("m", "()V").expect("m")));
+    }
+}
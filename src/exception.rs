@@ -0,0 +1,260 @@
+use std::fmt;
+use std::rc::Rc;
+use crate::class_loader::BOOTSTRAP_LOADER;
+use crate::constants::*;
+use crate::method_area::ClassId;
+use std::cell::RefCell;
+use crate::frame::Frame;
+use crate::object::{instance_size, Array, Object};
+use crate::interpreter;
+use crate::thread::JavaThread;
+use crate::types::Field;
+use crate::values::{Reference, Value};
+use crate::vm::Vm;
+
+// the message of the OutOfMemoryError thrown when the heap is full
+pub const JAVA_HEAP_SPACE: &str = "Java heap space";
+
+/// an exception or error thrown inside the VM, identified by the binary name of its class
+pub struct Throwable {
+    pub(crate) class_name: String,
+    pub(crate) message: String,
+    pub(crate) cause: Option<Box<Throwable>>,
+    // the java.lang.Throwable object, null for an exception thrown by the VM until the interpreter creates it
+    pub(crate) exception: Reference
+}
+
+impl Throwable {
+    pub fn new(class_name: &str, message: impl Into<String>) -> Self {
+        Throwable {
+            class_name: class_name.to_string(),
+            message: message.into(),
+            cause: None,
+            exception: Reference::null()
+        }
+    }
+
+    pub fn with_cause(class_name: &str, message: impl Into<String>, cause: Throwable) -> Self {
+        Throwable {
+            cause: Some(Box::new(cause)),
+            ..Throwable::new(class_name, message)
+        }
+    }
+}
+
+/// the OutOfMemoryError thrown once the heap is full. It is created at startup out of the heap, as HotSpot
+/// preallocates it, and so is the backtrace of its latest throw.
+pub struct PreallocatedError {
+    pub(crate) error: Box<Object>,
+    backtrace: Option<Box<Object>>
+}
+
+/// the backtrace of a throwable from `frames`, the innermost first. A frame takes 3 ints, the id of its class,
+/// the index of its method and its pc.
+pub fn backtrace<'a>(frames: impl Iterator<Item = &'a Frame>) -> Vec<i32> {
+    frames.flat_map(|frame| {
+        let class = frame.method.class();
+        [class.id.0 as i32, frame.method.index() as i32, frame.pc as i32]
+    }).collect()
+}
+
+impl Vm {
+    /// create the OutOfMemoryError thrown when the heap is full, its message is interned
+    pub fn preallocate_out_of_memory_error(&mut self, thread: &mut JavaThread) -> Result<(), Throwable> {
+        let class = self.load_class(BOOTSTRAP_LOADER, JAVA_LANG_OUT_OF_MEMORY_ERROR)?;
+        let message: Vec<u16> = JAVA_HEAP_SPACE.encode_utf16().collect();
+        let message = self.intern(thread, &message)?;
+        let error = Object::new(class);
+        error.set_field(&throwable_field(&error, "detailMessage", "Ljava/lang/String;"), Value::Reference(message));
+        self.out_of_memory_error = Some(PreallocatedError { error: Box::new(error), backtrace: None });
+        Ok(())
+    }
+
+    /// the preallocated OutOfMemoryError thrown by the current frame of `thread`, or a new one before it is created
+    fn out_of_memory_error(&mut self, thread: &JavaThread) -> Throwable {
+        let int_array_class = match self.load_class(BOOTSTRAP_LOADER, "[I") {
+            Ok(int_array_class) if self.out_of_memory_error.is_some() => int_array_class,
+            _ => return Throwable::new(JAVA_LANG_OUT_OF_MEMORY_ERROR, JAVA_HEAP_SPACE)
+        };
+        let preallocated = self.out_of_memory_error.as_mut().expect("preallocated OutOfMemoryError");
+        let frames = backtrace(thread.frames.iter().rev());
+        let depth = Value::Int((frames.len() / 3) as i32);
+        let frames = Box::new(Object::from_array(int_array_class, Array::Int(RefCell::new(frames.into()))));
+        let error = &preallocated.error;
+        error.set_field(&throwable_field(error, "backtrace", "Ljava/lang/Object;"), Value::Reference(Reference::to(&frames)));
+        error.set_field(&throwable_field(error, "depth", JVM_SIGNATURE_INT), depth);
+        // a stack trace computed from the former backtrace is stale
+        error.set_field(&throwable_field(error, "stackTrace", "[Ljava/lang/StackTraceElement;"), Value::Reference(Reference::null()));
+        preallocated.backtrace = Some(frames);
+        Throwable {
+            exception: Reference::to(error),
+            ..Throwable::new(JAVA_LANG_OUT_OF_MEMORY_ERROR, JAVA_HEAP_SPACE)
+        }
+    }
+
+    /// the exception thrown by `athrow`, its class and message are read from the object. Its causes are not copied,
+    /// they stay in the object where a collection updates them.
+    pub fn thrown(&self, exception: Reference) -> Throwable {
+        let object = exception.object().expect("non-null exception");
+        let message = object.get_field(&throwable_field(object, "detailMessage", "Ljava/lang/String;"));
+        Throwable {
+            class_name: object.class.name.clone(),
+            message: message.as_reference().object().map(|message| self.string_value(message)).unwrap_or_default(),
+            cause: None,
+            exception
+        }
+    }
+
+    // `throwable` and its causes, those of an exception object are read from the object
+    fn with_causes(&self, throwable: &Throwable) -> Throwable {
+        if !throwable.exception.is_null() {
+            return self.caused_by(throwable.exception.clone(), &mut vec![]);
+        }
+        Throwable {
+            cause: throwable.cause.as_ref().map(|cause| Box::new(self.with_causes(cause))),
+            exception: Reference::null(),
+            ..Throwable::new(&throwable.class_name, throwable.message.clone())
+        }
+    }
+
+    /// a copy of `throwable` and its causes without their objects, thrown anew by every later attempt of a resolution
+    /// which failed with `throwable` (JVMS 5.4.3)
+    pub fn resolution_error(&self, throwable: &Throwable) -> Throwable {
+        fn copy(throwable: &Throwable) -> Throwable {
+            Throwable {
+                cause: throwable.cause.as_deref().map(|cause| Box::new(copy(cause))),
+                ..Throwable::new(&throwable.class_name, throwable.message.clone())
+            }
+        }
+        copy(&self.with_causes(throwable))
+    }
+
+    // `enclosing` are the exceptions caused by `exception`, a cycle of causes is cut
+    fn caused_by(&self, exception: Reference, enclosing: &mut Vec<Reference>) -> Throwable {
+        let object = exception.object().expect("non-null exception");
+        let cause = object.get_field(&throwable_field(object, "cause", "Ljava/lang/Throwable;")).as_reference().clone();
+        enclosing.push(exception.clone());
+        // a throwable without a cause is its own cause
+        let cause = if cause.is_null() || enclosing.iter().any(|enclosing| enclosing.is_equal(&cause)) {
+            None
+        } else {
+            Some(Box::new(self.caused_by(cause, enclosing)))
+        };
+        Throwable { cause, ..self.thrown(exception) }
+    }
+
+    /// `throwable` and its causes as printed by Throwable#printStackTrace, the frames a cause has in common
+    /// with the exception it caused are elided. The causes are read from the exception objects as they are now.
+    pub fn stack_trace(&self, throwable: &Throwable) -> String {
+        let throwable = &self.with_causes(throwable);
+        let mut result = format!("{}\n", throwable);
+        let mut enclosing_frames = vec![];
+        let mut current = Some(throwable);
+        while let Some(throwable) = current {
+            let frames = self.backtrace(&throwable.exception);
+            let in_common = frames.iter().rev().zip(enclosing_frames.iter().rev()).take_while(|(frame, enclosing)| frame == enclosing).count();
+            for frame in &frames[..frames.len() - in_common] {
+                result += &format!("\tat {}\n", frame);
+            }
+            if in_common > 0 {
+                result += &format!("\t... {} more\n", in_common);
+            }
+            current = throwable.cause.as_deref();
+            if let Some(cause) = current {
+                result += &format!("Caused by: {}\n", cause);
+            }
+            enclosing_frames = frames;
+        }
+        result
+    }
+
+    /// the frames recorded by Throwable#fillInStackTrace as StackTraceElement#toString, e.g.
+    /// `java.base/java.lang.Integer.parseInt(Integer.java:652)`, none for an exception the VM has not created
+    fn backtrace(&self, exception: &Reference) -> Vec<String> {
+        let backtrace = match exception.object() {
+            Some(object) => object.get_field(&throwable_field(object, "backtrace", "Ljava/lang/Object;")),
+            None => return vec![]
+        };
+        let frames = match backtrace.as_reference().object().map(Object::array) {
+            Some(Array::Int(frames)) => frames.borrow().to_vec(),
+            _ => return vec![]
+        };
+        frames.chunks(3).map(|frame| {
+            let class = self.method_area.class(ClassId(frame[0] as usize));
+            let method = &class.methods[frame[1] as usize];
+            let source = match method.line_number(frame[2] as usize) {
+                _ if method.is_native() => "Native Method".to_string(),
+                _ if class.source_file.is_empty() => "Unknown Source".to_string(),
+                Some(line_number) => format!("{}:{}", class.source_file, line_number),
+                None => class.source_file.clone()
+            };
+            let module = self.class_loaders.loader(class.defining_loader).module_of(&class.name)
+                .map_or(String::new(), |module| module + "/");
+            format!("{}{}.{}({})", module, class.name.replace('/', "."), method.name(), source)
+        }).collect()
+    }
+
+    /// the object of `throwable`, created by its constructor taking the message on the first throw.
+    /// The references are kept on the operand stack of the current frame while allocating, a collection may move them.
+    pub fn exception_object(&mut self, thread: &mut JavaThread, throwable: &Throwable) -> Result<Reference, Throwable> {
+        if !throwable.exception.is_null() {
+            return Ok(throwable.exception.clone());
+        }
+        // there may be no room for another error
+        if throwable.class_name == JAVA_LANG_OUT_OF_MEMORY_ERROR && throwable.message == JAVA_HEAP_SPACE && throwable.cause.is_none() {
+            return Ok(self.out_of_memory_error(thread).exception);
+        }
+        let cause = match &throwable.cause {
+            Some(cause) => self.exception_object(thread, cause)?,
+            None => Reference::null()
+        };
+        thread.frame().push(Value::Reference(cause));
+        let result = self.new_exception(thread, &throwable.class_name, &throwable.message);
+        let cause = thread.frame().pop_reference();
+        let exception = result?;
+        if !cause.is_null() {
+            let object = exception.object().expect("new exception");
+            let cause = Value::Reference(cause);
+            self.heap.write_barrier(object, &cause);
+            object.set_field(&throwable_field(object, "cause", "Ljava/lang/Throwable;"), cause);
+        }
+        Ok(exception)
+    }
+
+    fn new_exception(&mut self, thread: &mut JavaThread, class_name: &str, message: &str) -> Result<Reference, Throwable> {
+        let class = self.load_class(BOOTSTRAP_LOADER, class_name)?;
+        let constructor = class.find_method(OBJECT_INITIALIZER_NAME, "(Ljava/lang/String;)V")
+            .ok_or_else(|| Throwable::new(JAVA_LANG_NO_SUCH_METHOD_ERROR, format!("{}.<init>(Ljava/lang/String;)V", class.name.replace('/', "."))))?;
+        self.initialize_class(thread, &class)?;
+        self.reserve(thread, instance_size(&class))?;
+        let exception = self.heap.allocate(Object::new(class))?;
+        // an empty message is null, as for `new NullPointerException()`
+        let message = if message.is_empty() { Reference::null() } else { self.new_string(message)? };
+        thread.frame().push(Value::Reference(exception.clone()));
+        let result = interpreter::invoke(self, thread, &constructor, vec![Value::Reference(exception), Value::Reference(message)]);
+        let exception = thread.frame().pop_reference();
+        result.map(|_| exception)
+    }
+}
+
+fn throwable_field(object: &Object, name: &str, descriptor: &str) -> Rc<Field> {
+    object.class.find_field(name, descriptor).unwrap_or_else(|| panic!("java.lang.Throwable#{}", name))
+}
+
+/// `java.lang.NoClassDefFoundError: com/acme/Foo` as java.lang.Throwable#toString
+impl fmt::Display for Throwable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let class_name = self.class_name.replace('/', ".");
+        if self.message.is_empty() {
+            write!(f, "{}", class_name)
+        } else {
+            write!(f, "{}: {}", class_name, self.message)
+        }
+    }
+}
+
+impl fmt::Debug for Throwable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,99 @@
+use std::rc::Rc;
+use crate::types::Method;
+use crate::values::{Reference, Value};
+
+/// the frame of a method invocation (JVMS 2.6), the local variables and the operand stack are sized
+/// by the Code attribute of the method. Both are made of slots, a long or double takes two.
+pub struct Frame {
+    pub(crate) method: Rc<Method>,
+    pub(crate) locals: Vec<Value>,
+    pub(crate) stack: Vec<Value>,
+    // the offset of the instruction being executed
+    pub(crate) pc: usize
+}
+
+impl Frame {
+    /// the frame of `method` with the slots of its arguments, including the receiver of an instance method,
+    /// in its first local variables
+    pub fn new(method: Rc<Method>, args: Vec<Value>) -> Self {
+        let mut locals = args;
+        locals.resize(method.max_locals(), Value::Top);
+        let stack = Vec::with_capacity(method.max_stack());
+        Frame { method, locals, stack, pc: 0 }
+    }
+
+    /// push a value, a long or double takes two slots
+    pub fn push(&mut self, value: Value) {
+        let category2 = value.is_category2();
+        self.stack.push(value);
+        if category2 {
+            self.stack.push(Value::Top);
+        }
+    }
+
+    /// pop a value, the two slots of a long or double
+    pub fn pop(&mut self) -> Value {
+        match self.pop_slot() {
+            Value::Top => self.pop_slot(),
+            value => value
+        }
+    }
+
+    /// pop a single slot as the stack manipulation instructions do regardless of the types
+    pub fn pop_slot(&mut self) -> Value {
+        self.stack.pop().expect("operand stack underflow")
+    }
+
+    pub fn push_slots(&mut self, slots: impl IntoIterator<Item = Value>) {
+        self.stack.extend(slots);
+    }
+
+    /// the `count` slots on top of the operand stack in the order they were pushed
+    pub fn pop_slots(&mut self, count: usize) -> Vec<Value> {
+        self.stack.split_off(self.stack.len() - count)
+    }
+
+    #[allow(dead_code)]
+    pub fn push_int(&mut self, value: i32) {
+        self.stack.push(Value::Int(value));
+    }
+
+    pub fn pop_int(&mut self) -> i32 {
+        self.pop_slot().as_int()
+    }
+
+    pub fn pop_long(&mut self) -> i64 {
+        self.pop().as_long()
+    }
+
+    pub fn pop_float(&mut self) -> f32 {
+        self.pop_slot().as_float()
+    }
+
+    pub fn pop_double(&mut self) -> f64 {
+        self.pop().as_double()
+    }
+
+    pub fn pop_reference(&mut self) -> Reference {
+        match self.pop_slot() {
+            Value::Reference(reference) => reference,
+            value => value.as_reference().clone()
+        }
+    }
+
+    pub fn local(&self, index: usize) -> Value {
+        self.locals[index].clone()
+    }
+
+    pub fn local_int(&self, index: usize) -> i32 {
+        self.locals[index].as_int()
+    }
+
+    /// store a value to a local variable, a long or double takes the next one too
+    pub fn set_local(&mut self, index: usize, value: Value) {
+        if value.is_category2() {
+            self.locals[index + 1] = Value::Top;
+        }
+        self.locals[index] = value;
+    }
+}
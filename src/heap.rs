@@ -0,0 +1,649 @@
+use std::cell::RefMut;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::time::{Duration, Instant};
+use crate::constants::JAVA_LANG_OUT_OF_MEMORY_ERROR;
+use crate::exception::{Throwable, JAVA_HEAP_SPACE};
+use crate::object::Object;
+use crate::strings::StringTable;
+use crate::thread::JavaThread;
+use crate::values::{Reference, Value};
+use crate::vm::Vm;
+
+pub const DEFAULT_MAX_HEAP_SIZE: usize = 256 * 1024 * 1024;
+
+// the heap grows to this size before the first collection
+const INITIAL_THRESHOLD: usize = 8 * 1024 * 1024;
+
+// the bytes of the eden of the young generation, and of a survivor space as HotSpot's -XX:SurvivorRatio=8
+const EDEN_SIZE: usize = 4 * 1024 * 1024;
+const SURVIVOR_RATIO: usize = 8;
+// the young collections an object survives before it is promoted
+const TENURING_THRESHOLD: u8 = 2;
+// the smallest object takes a header, which bounds the number of objects of a space
+const MIN_OBJECT_SIZE: usize = crate::object::HEADER_SIZE;
+
+/// the collector of the heap, `-XX:+UseGenerationalGC` selects the generational one
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Collector {
+    MarkSweep,
+    Generational
+}
+
+/// the objects of a young space, allocated by bumping its length. The vector never grows beyond its capacity
+/// so the objects stay where their references point until a young collection copies them out.
+struct Space {
+    objects: Vec<Option<Object>>,
+    used: usize,
+    size: usize
+}
+
+impl Space {
+    fn new(size: usize) -> Self {
+        Space { objects: Vec::with_capacity(size / MIN_OBJECT_SIZE), used: 0, size }
+    }
+
+    fn has_room(&self, size: usize) -> bool {
+        self.objects.len() < self.objects.capacity() && self.used.saturating_add(size) <= self.size
+    }
+
+    fn bump(&mut self, object: Object, size: usize) -> &Object {
+        self.used += size;
+        self.objects.push(Some(object));
+        self.objects.last().and_then(Option::as_ref).expect("object just allocated")
+    }
+
+    /// the index of `object` if it is in this space
+    fn index_of(&self, object: &Object) -> Option<usize> {
+        let start = self.objects.as_ptr() as usize;
+        let address = object as *const Object as usize;
+        let element_size = mem::size_of::<Option<Object>>();
+        if address >= start && address < start + self.objects.capacity() * element_size {
+            Some((address - start) / element_size)
+        } else {
+            None
+        }
+    }
+}
+
+/// the young generation: new objects are allocated in eden, a young collection copies the live ones to a survivor
+/// space, or promotes them to the old generation once old enough
+struct YoungGeneration {
+    eden: Space,
+    survivors: Space,
+    // the objects out of the young generation which may refer to young objects, recorded by the write barrier
+    remembered: HashSet<*const Object>
+}
+
+impl YoungGeneration {
+    fn contains(&self, object: &Object) -> bool {
+        self.eden.index_of(object).is_some() || self.survivors.index_of(object).is_some()
+    }
+}
+
+/// a stop-the-world collection, reported with `-Xlog:gc`
+pub struct Pause {
+    kind: &'static str,
+    before: usize,
+    after: usize,
+    duration: Duration
+}
+
+/// the heap of the objects of every thread (JVMS 2.5.3). It owns the objects, which are freed by a precise
+/// collector once unreachable, and it never takes more than `-Xmx` bytes.
+pub struct Heap {
+    // the old generation, or every object with the mark-sweep collector. Boxed so that an object stays
+    // where its references point as the generation grows.
+    #[allow(clippy::vec_box)]
+    old: Vec<Box<Object>>,
+    // the bytes taken by the objects of the old generation, estimated from their layouts
+    old_used: usize,
+    young: Option<YoungGeneration>,
+    max_size: usize,
+    // the bytes of the old generation beyond which an allocation at a safepoint collects first
+    threshold: usize,
+    // the current mark-sweep collection, an object is marked reachable with it
+    epoch: u32,
+    pub(crate) pauses: Vec<Pause>,
+    // -Xlog:gc, print every pause as it ends and a summary on exit
+    log: bool,
+    started: Instant
+}
+
+fn out_of_memory() -> Throwable {
+    Throwable::new(JAVA_LANG_OUT_OF_MEMORY_ERROR, JAVA_HEAP_SPACE)
+}
+
+fn megabytes(bytes: usize) -> usize {
+    bytes / (1024 * 1024)
+}
+
+impl Heap {
+    pub fn new(max_size: usize, collector: Collector, log: bool) -> Self {
+        // the young generation takes at most a third of the heap
+        let young = match collector {
+            Collector::MarkSweep => None,
+            Collector::Generational => {
+                let eden_size = EDEN_SIZE.min(max_size / 3);
+                Some(YoungGeneration {
+                    eden: Space::new(eden_size),
+                    survivors: Space::new(eden_size / SURVIVOR_RATIO),
+                    remembered: HashSet::new()
+                })
+            }
+        };
+        let max_size = max_size - young.as_ref().map_or(0, |young| young.eden.size + young.survivors.size);
+        Heap {
+            old: vec![],
+            old_used: 0,
+            young,
+            max_size,
+            threshold: INITIAL_THRESHOLD.min(max_size),
+            epoch: 0,
+            pauses: vec![],
+            log,
+            started: Instant::now()
+        }
+    }
+
+    /// the bytes taken by the objects of both generations
+    pub fn used(&self) -> usize {
+        self.old_used + self.young.as_ref().map_or(0, |young| young.eden.used + young.survivors.used)
+    }
+
+    fn capacity(&self) -> usize {
+        self.max_size + self.young.as_ref().map_or(0, |young| young.eden.size + young.survivors.size)
+    }
+
+    fn fits_young(&self, size: usize) -> bool {
+        self.young.as_ref().is_some_and(|young| young.eden.has_room(size))
+    }
+
+    /// move `object` into eden, or into the old generation if eden is full, OutOfMemoryError if it does not fit.
+    /// Nothing is collected, the VM may allocate while it holds references the collector can not find.
+    pub fn allocate(&mut self, object: Object) -> Result<Reference, Throwable> {
+        let size = object.size();
+        if let Some(young) = self.young.as_mut().filter(|young| young.eden.has_room(size)) {
+            return Ok(Reference::to(young.eden.bump(object, size)));
+        }
+        if self.old_used.saturating_add(size) > self.max_size {
+            return Err(out_of_memory());
+        }
+        let reference = self.promote(object, size);
+        // the variables of an object allocated by the VM were set before, out of the write barrier
+        let object = reference.object().expect("object just allocated");
+        object.visit_references(|field| self.write_barrier(object, &Value::Reference(field.clone())));
+        Ok(reference)
+    }
+
+    fn promote(&mut self, object: Object, size: usize) -> Reference {
+        self.old_used += size;
+        let object = Box::new(object);
+        let reference = Reference::to(&object);
+        self.old.push(object);
+        reference
+    }
+
+    /// the pause statistics, printed on exit with -Xlog:gc
+    pub fn report(&self) {
+        if !self.log {
+            return;
+        }
+        for kind in ["Pause Young", "Pause Full"] {
+            let durations: Vec<Duration> = self.pauses.iter().filter(|pause| pause.kind == kind).map(|pause| pause.duration).collect();
+            let total: Duration = durations.iter().sum();
+            let max = durations.iter().max().copied().unwrap_or_default();
+            let average = if durations.is_empty() { Duration::ZERO } else { total / durations.len() as u32 };
+            println!("[{:.3}s][info][gc] {}: {} pauses, total {:.3}ms, average {:.3}ms, max {:.3}ms", self.started.elapsed().as_secs_f64(),
+                kind, durations.len(), total.as_secs_f64() * 1000.0, average.as_secs_f64() * 1000.0, max.as_secs_f64() * 1000.0);
+        }
+    }
+
+    /// the write barrier of the generational collector: `holder` storing `value` is remembered if it is not young
+    /// but the value is, so that a young collection finds the value without scanning the old generation
+    pub fn write_barrier(&mut self, holder: &Object, value: &Value) {
+        if let (Some(young), Value::Reference(reference)) = (self.young.as_mut(), value) {
+            if reference.object().is_some_and(|object| young.contains(object)) && !young.contains(holder) {
+                young.remembered.insert(holder);
+            }
+        }
+    }
+
+    /// mark the objects reachable from `roots` and the fields of `root_objects`, then sweep the others
+    fn mark_sweep(&mut self, roots: Vec<Reference>, root_objects: &[&Object]) {
+        self.epoch = self.epoch.wrapping_add(1);
+        let epoch = self.epoch;
+        let mut pending = roots;
+        for object in root_objects {
+            object.visit_references(|reference| pending.push(reference.clone()));
+        }
+        while let Some(reference) = pending.pop() {
+            if let Some(object) = reference.object() {
+                if object.mark.get() != epoch {
+                    object.mark.set(epoch);
+                    object.visit_references(|reference| pending.push(reference.clone()));
+                }
+            }
+        }
+        self.old.retain(|object| object.mark.get() == epoch);
+        self.old_used = self.old.iter().map(|object| object.size()).sum();
+        // collect again once the live objects have doubled
+        self.threshold = (self.old_used * 2).clamp(INITIAL_THRESHOLD.min(self.max_size), self.max_size);
+    }
+
+    /// copy the young objects reachable from `roots`, the fields of `root_objects` and the remembered objects
+    /// out of eden and the survivor space, to the other survivor space or to the old generation. A young object
+    /// is promoted if `tenure_all`, once it is old enough or when the survivor space is full.
+    fn copy_young(&mut self, roots: Vec<&mut Reference>, root_objects: &[&Object], tenure_all: bool) {
+        let young = self.young.take().expect("young generation");
+        let mut evacuation = Evacuation {
+            to_space: Space::new(young.survivors.size),
+            young,
+            forwarded: HashMap::new(),
+            pending: vec![],
+            tenure_all
+        };
+        for root in roots {
+            evacuation.evacuate(self, root);
+        }
+        // the objects out of the young generation whose fields may refer to young objects
+        let mut holders: Vec<Reference> = root_objects.iter().map(|object| Reference::to(object)).collect();
+        holders.extend(evacuation.young.remembered.drain().map(|object| Reference::to(unsafe { &*object })));
+        let mut remembered = HashSet::new();
+        while let Some(holder) = holders.pop().or_else(|| evacuation.pending.pop()) {
+            let object = holder.object().expect("holder");
+            let mut refers_young = false;
+            object.visit_references(|reference| {
+                evacuation.evacuate(self, reference);
+                refers_young |= reference.object().is_some_and(|object| evacuation.to_space.index_of(object).is_some());
+            });
+            // a promoted or remembered object keeps being remembered while it refers to a survivor
+            if refers_young && evacuation.to_space.index_of(object).is_none() {
+                remembered.insert(object as *const Object);
+            }
+        }
+        let Evacuation { mut young, to_space, .. } = evacuation;
+        // what is left in eden and the former survivor space is garbage
+        young.eden.objects.clear();
+        young.eden.used = 0;
+        young.survivors = to_space;
+        young.remembered = remembered;
+        self.young = Some(young);
+    }
+}
+
+/// the state of a young collection
+struct Evacuation {
+    young: YoungGeneration,
+    to_space: Space,
+    // the copies of the objects copied so far by their former addresses
+    forwarded: HashMap<*const Object, Reference>,
+    // the copies whose fields are still to be updated
+    pending: Vec<Reference>,
+    tenure_all: bool
+}
+
+impl Evacuation {
+    /// copy the object `reference` refers to out of the young generation, once, and update the reference to the copy
+    fn evacuate(&mut self, heap: &mut Heap, reference: &mut Reference) {
+        let object = match reference.object() {
+            Some(object) => object,
+            None => return
+        };
+        let address = object as *const Object;
+        if let Some(copy) = self.forwarded.get(&address) {
+            *reference = copy.clone();
+            return;
+        }
+        let slot = match (self.young.eden.index_of(object), self.young.survivors.index_of(object)) {
+            (Some(index), _) => &mut self.young.eden.objects[index],
+            (None, Some(index)) => &mut self.young.survivors.objects[index],
+            (None, None) => return
+        };
+        let object = slot.take().expect("young object copied once");
+        let size = object.size();
+        object.age.set(object.age.get().saturating_add(1));
+        let copy = if self.tenure_all || object.age.get() >= TENURING_THRESHOLD || !self.to_space.has_room(size) {
+            heap.promote(object, size)
+        } else {
+            Reference::to(self.to_space.bump(object, size))
+        };
+        self.forwarded.insert(address, copy.clone());
+        self.pending.push(copy.clone());
+        *reference = copy;
+    }
+}
+
+fn reference(value: &mut Value) -> Option<&mut Reference> {
+    match value {
+        Value::Reference(reference) if !reference.is_null() => Some(reference),
+        _ => None
+    }
+}
+
+impl Vm {
+    /// a safepoint of `thread` before it allocates `size` bytes: the garbage is collected if the heap is filling up,
+    /// OutOfMemoryError is thrown if the bytes do not fit even then
+    pub fn reserve(&mut self, thread: &mut JavaThread, size: usize) -> Result<(), Throwable> {
+        let mut young = self.heap.fits_young(size);
+        if let (Some(generation), false) = (&self.heap.young, young) {
+            // every young object may be promoted, a full collection makes room for them in the old generation first
+            let full = self.heap.old_used + generation.eden.used + generation.survivors.used > self.heap.threshold;
+            self.collect_garbage(thread, full);
+            young = self.heap.fits_young(size);
+        }
+        if self.heap.old_used.saturating_add(if young { 0 } else { size }) > self.heap.threshold {
+            self.collect_garbage(thread, true);
+            young = self.heap.fits_young(size);
+        }
+        // the promoted objects may not fit either
+        if self.heap.old_used.saturating_add(if young { 0 } else { size }) > self.heap.max_size {
+            return Err(out_of_memory());
+        }
+        Ok(())
+    }
+
+    /// collect the objects unreachable from the roots: the local variables and operand stacks of `thread`, which is
+    /// the only one, the static variables, the interned strings, the mirrors of the classes and the preallocated error. There are no JNI handles, a native method never
+    /// reaches a safepoint. Only the young generation is collected unless `full`, the mark-sweep collector is always full.
+    pub fn collect_garbage(&mut self, thread: &mut JavaThread, full: bool) {
+        let started = Instant::now();
+        let before = self.heap.used();
+        // the mirrors and the preallocated error are out of the heap, the objects they refer to are in it
+        let mut root_objects = self.method_area.mirrors();
+        root_objects.extend(self.out_of_memory_error.as_ref().map(|preallocated| preallocated.error.as_ref()));
+        let mut statics: Vec<RefMut<Vec<Value>>> = self.method_area.classes().map(|class| class.static_vars.borrow_mut()).collect();
+        let generational = self.heap.young.is_some();
+        if generational {
+            // a full collection empties the young generation before marking the old one
+            self.heap.copy_young(roots(thread, &mut statics, &mut self.string_table), &root_objects, full);
+        }
+        let full = full || !generational;
+        if full {
+            let roots = roots(thread, &mut statics, &mut self.string_table).into_iter().map(|reference| reference.clone()).collect();
+            self.heap.mark_sweep(roots, &root_objects);
+        }
+        let pause = Pause {
+            kind: if full { "Pause Full" } else { "Pause Young" },
+            before,
+            after: self.heap.used(),
+            duration: started.elapsed()
+        };
+        if self.heap.log {
+            println!("[{:.3}s][info][gc] GC({}) {} {}M->{}M({}M) {:.3}ms", self.heap.started.elapsed().as_secs_f64(),
+                self.heap.pauses.len(), pause.kind, megabytes(pause.before), megabytes(pause.after),
+                megabytes(self.heap.capacity()), pause.duration.as_secs_f64() * 1000.0);
+        }
+        self.heap.pauses.push(pause);
+    }
+}
+
+/// the references held by the frames of `thread`, by the static variables and by the string table
+fn roots<'a>(thread: &'a mut JavaThread, statics: &'a mut [RefMut<Vec<Value>>], string_table: &'a mut StringTable) -> Vec<&'a mut Reference> {
+    thread.frames.iter_mut()
+        .flat_map(|frame| frame.locals.iter_mut().chain(frame.stack.iter_mut()))
+        .chain(statics.iter_mut().flat_map(|static_vars| static_vars.iter_mut()))
+        .filter_map(reference)
+        .chain(string_table.strings.values_mut())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_builder::{test_vm, ClassBuilder, Code};
+    use crate::class_loader::BOOTSTRAP_LOADER;
+    use crate::constants::*;
+    use crate::frame::Frame;
+    use crate::interpreter;
+    use crate::object::array_size;
+    use crate::types::Class;
+    use crate::vm::VmOptions;
+    use std::rc::Rc;
+
+    /// a VM with the classes the collector needs: mirrors with a reference field, strings, and `Node`,
+    /// whose instances link to each other and whose static variable `root` holds one. `Node.store` and
+    /// `Node.storeElement` store with putfield and aastore.
+    fn vm(collector: Collector, max_heap_size: usize) -> (Vm, Rc<Class>, JavaThread) {
+        let mut vm = test_vm(VmOptions { collector, max_heap_size, ..VmOptions::default() });
+        ClassBuilder::new(JAVA_LANG_CLASS, Some(JAVA_LANG_OBJECT))
+            .field(ACC_PRIVATE, "classData", "Ljava/lang/Object;")
+            .define(&mut vm, BOOTSTRAP_LOADER);
+        ClassBuilder::new(JAVA_LANG_STRING, Some(JAVA_LANG_OBJECT))
+            .field(ACC_PRIVATE | ACC_FINAL, "value", "[B")
+            .field(ACC_PRIVATE | ACC_FINAL, "coder", JVM_SIGNATURE_BYTE)
+            .define(&mut vm, BOOTSTRAP_LOADER);
+        let mut node = ClassBuilder::new("Node", Some(JAVA_LANG_OBJECT));
+        let [high, low] = node.field_ref("Node", "next", "LNode;").to_be_bytes();
+        let node = node
+            .field(0, "next", "LNode;")
+            .field(ACC_STATIC, "root", "LNode;")
+            .method(ACC_STATIC, "run", "()V", Some(Code::new(16, 4, vec![0xb1])))
+            // aload_0, aload_1, putfield #next, return
+            .method(ACC_STATIC, "store", "(LNode;LNode;)V", Some(Code::new(2, 2, vec![0x2a, 0x2b, 0xb5, high, low, 0xb1])))
+            // aload_0, iconst_0, aload_1, aastore, return
+            .method(ACC_STATIC, "storeElement", "([Ljava/lang/Object;Ljava/lang/Object;)V", Some(Code::new(3, 2, vec![0x2a, 0x03, 0x2b, 0x53, 0xb1])))
+            .define(&mut vm, BOOTSTRAP_LOADER);
+        let mut thread = JavaThread::main();
+        let run = node.find_method("run", "()V").expect("Node.run");
+        thread.push_frame(Frame::new(run, vec![])).expect("frame");
+        (vm, node, thread)
+    }
+
+    fn new_node(vm: &mut Vm, node: &Rc<Class>) -> Reference {
+        vm.heap.allocate(Object::new(node.clone())).expect("node")
+    }
+
+    fn link(node: &Rc<Class>, from: &Reference, to: &Reference) {
+        let next = node.find_field("next", "LNode;").expect("Node.next");
+        from.object().expect("node").set_field(&next, Value::Reference(to.clone()));
+    }
+
+    fn next(node: &Rc<Class>, from: &Reference) -> Reference {
+        let next = node.find_field("next", "LNode;").expect("Node.next");
+        from.object().expect("node").get_field(&next).as_reference().clone()
+    }
+
+    #[test]
+    fn unreachable_cycles_are_reclaimed() {
+        let (mut vm, node, mut thread) = vm(Collector::MarkSweep, DEFAULT_MAX_HEAP_SIZE);
+        let (a, b) = (new_node(&mut vm, &node), new_node(&mut vm, &node));
+        link(&node, &a, &b);
+        link(&node, &b, &a);
+        let c = new_node(&mut vm, &node);
+        link(&node, &c, &c);
+        assert_eq!(vm.heap.old.len(), 3);
+        vm.collect_garbage(&mut thread, true);
+        assert_eq!(vm.heap.old.len(), 0);
+        assert_eq!(vm.heap.used(), 0);
+    }
+
+    #[test]
+    fn objects_reachable_from_frames_survive() {
+        let (mut vm, node, mut thread) = vm(Collector::MarkSweep, DEFAULT_MAX_HEAP_SIZE);
+        let (local, stacked, linked) = (new_node(&mut vm, &node), new_node(&mut vm, &node), new_node(&mut vm, &node));
+        link(&node, &stacked, &linked);
+        new_node(&mut vm, &node);
+        thread.frame().set_local(0, Value::Reference(local));
+        thread.frame().push(Value::Reference(stacked.clone()));
+        vm.collect_garbage(&mut thread, true);
+        assert_eq!(vm.heap.old.len(), 3);
+        assert!(next(&node, &stacked).is_equal(&linked));
+    }
+
+    #[test]
+    fn objects_reachable_from_static_variables_survive() {
+        let (mut vm, node, mut thread) = vm(Collector::MarkSweep, DEFAULT_MAX_HEAP_SIZE);
+        let (root, linked) = (new_node(&mut vm, &node), new_node(&mut vm, &node));
+        link(&node, &root, &linked);
+        new_node(&mut vm, &node);
+        let field = node.find_field("root", "LNode;").expect("Node.root");
+        node.static_vars.borrow_mut()[field.offset()] = Value::Reference(root.clone());
+        vm.collect_garbage(&mut thread, true);
+        assert_eq!(vm.heap.old.len(), 2);
+        assert!(next(&node, &root).is_equal(&linked));
+    }
+
+    #[test]
+    fn interned_strings_survive() {
+        let (mut vm, node, mut thread) = vm(Collector::MarkSweep, DEFAULT_MAX_HEAP_SIZE);
+        let chars: Vec<u16> = "interned".encode_utf16().collect();
+        let string = vm.intern(&mut thread, &chars).expect("interned string");
+        vm.new_string("garbage").expect("string");
+        new_node(&mut vm, &node);
+        vm.collect_garbage(&mut thread, true);
+        // the string and its bytes
+        assert_eq!(vm.heap.old.len(), 2);
+        assert_eq!(vm.string_value(string.object().expect("string")), "interned");
+        assert!(vm.intern(&mut thread, &chars).expect("interned string").is_equal(&string));
+    }
+
+    #[test]
+    fn objects_reachable_from_mirrors_survive() {
+        let (mut vm, node, mut thread) = vm(Collector::MarkSweep, DEFAULT_MAX_HEAP_SIZE);
+        let java_lang_class = vm.load_class(BOOTSTRAP_LOADER, JAVA_LANG_CLASS).expect("java.lang.Class");
+        let class_data = java_lang_class.find_field("classData", "Ljava/lang/Object;").expect("Class.classData");
+        let data = new_node(&mut vm, &node);
+        new_node(&mut vm, &node);
+        let mirror = vm.method_area.mirror_of(&node);
+        mirror.object().expect("mirror").set_field(&class_data, Value::Reference(data.clone()));
+        vm.collect_garbage(&mut thread, true);
+        assert_eq!(vm.heap.old.len(), 1);
+        assert!(mirror.object().expect("mirror").get_field(&class_data).as_reference().is_equal(&data));
+    }
+
+    #[test]
+    fn out_of_memory_beyond_the_maximum_heap_size() {
+        let max_heap_size = 1024 * 1024;
+        let (mut vm, _, mut thread) = vm(Collector::MarkSweep, max_heap_size);
+        let array_class = vm.load_class(BOOTSTRAP_LOADER, "[I").expect("int[]");
+        let length = 16 * 1024;
+        let mut allocated = 0;
+        let error = loop {
+            if let Err(error) = vm.reserve(&mut thread, array_size(JVM_SIGNATURE_INT, length)) {
+                break error;
+            }
+            let array = vm.heap.allocate(Object::new_array(array_class.clone(), length)).expect("reserved array");
+            // every array stays reachable
+            thread.frame().push(Value::Reference(array));
+            allocated += 1;
+            assert!(allocated < 100, "the heap grows beyond -Xmx");
+        };
+        assert_eq!(error.class_name, JAVA_LANG_OUT_OF_MEMORY_ERROR);
+        assert_eq!(error.message, "Java heap space");
+        assert!(allocated >= 14, "only {} arrays of 64K fit in 1M", allocated);
+        assert!(vm.heap.used() <= max_heap_size);
+        // the heap is full of live objects, a collection frees nothing
+        vm.collect_garbage(&mut thread, true);
+        assert_eq!(vm.heap.old.len(), allocated);
+    }
+
+    fn young(vm: &Vm) -> &YoungGeneration {
+        vm.heap.young.as_ref().expect("young generation")
+    }
+
+    fn set_root(node: &Rc<Class>, value: &Reference) {
+        let field = node.find_field("root", "LNode;").expect("Node.root");
+        node.static_vars.borrow_mut()[field.offset()] = Value::Reference(value.clone());
+    }
+
+    fn root(node: &Rc<Class>) -> Reference {
+        let field = node.find_field("root", "LNode;").expect("Node.root");
+        node.static_vars.borrow()[field.offset()].as_reference().clone()
+    }
+
+    /// an object of the old generation, promoted by a full collection and held by `Node.root`
+    fn old_object(vm: &mut Vm, node: &Rc<Class>, thread: &mut JavaThread, object: Object) -> Reference {
+        let reference = vm.heap.allocate(object).expect("object");
+        set_root(node, &reference);
+        vm.collect_garbage(thread, true);
+        let reference = root(node);
+        assert!(!young(vm).contains(reference.object().expect("old object")));
+        reference
+    }
+
+    fn invoke(vm: &mut Vm, thread: &mut JavaThread, node: &Rc<Class>, name: &str, descriptor: &str, args: Vec<Value>) {
+        let method = node.find_method(name, descriptor).expect("method");
+        interpreter::invoke(vm, thread, &method, args).expect("stored");
+    }
+
+    #[test]
+    fn survivors_are_promoted_after_the_tenuring_threshold() {
+        let (mut vm, node, mut thread) = vm(Collector::Generational, DEFAULT_MAX_HEAP_SIZE);
+        let survivor = new_node(&mut vm, &node);
+        new_node(&mut vm, &node);
+        assert!(young(&vm).eden.index_of(survivor.object().expect("node")).is_some());
+        thread.frame().set_local(0, Value::Reference(survivor));
+
+        vm.collect_garbage(&mut thread, false);
+        let survivor = thread.frame().local(0).as_reference().clone();
+        assert!(young(&vm).survivors.index_of(survivor.object().expect("survivor")).is_some());
+        assert_eq!(young(&vm).eden.objects.len(), 0);
+        assert_eq!(vm.heap.old.len(), 0);
+
+        for _ in 1..TENURING_THRESHOLD {
+            vm.collect_garbage(&mut thread, false);
+        }
+        let promoted = thread.frame().local(0).as_reference().clone();
+        assert!(!young(&vm).contains(promoted.object().expect("promoted")));
+        assert_eq!(vm.heap.old.len(), 1);
+        assert_eq!(young(&vm).survivors.used, 0);
+    }
+
+    #[test]
+    fn full_collection_promotes_every_live_young_object() {
+        let (mut vm, node, mut thread) = vm(Collector::Generational, DEFAULT_MAX_HEAP_SIZE);
+        let (first, second) = (new_node(&mut vm, &node), new_node(&mut vm, &node));
+        link(&node, &first, &second);
+        new_node(&mut vm, &node);
+        thread.frame().push(Value::Reference(first));
+        vm.collect_garbage(&mut thread, true);
+        assert_eq!(vm.heap.old.len(), 2);
+        assert_eq!(young(&vm).eden.used + young(&vm).survivors.used, 0);
+        let first = thread.frame().pop_reference();
+        assert!(next(&node, &first).object().is_some_and(|second| !young(&vm).contains(second)));
+    }
+
+    #[test]
+    fn putfield_remembers_an_old_object_referring_to_a_young_one() {
+        let (mut vm, node, mut thread) = vm(Collector::Generational, DEFAULT_MAX_HEAP_SIZE);
+        let old = old_object(&mut vm, &node, &mut thread, Object::new(node.clone()));
+        let young_node = new_node(&mut vm, &node);
+        invoke(&mut vm, &mut thread, &node, "store", "(LNode;LNode;)V", vec![Value::Reference(old.clone()), Value::Reference(young_node)]);
+        assert!(young(&vm).remembered.contains(&(old.object().expect("old") as *const Object)));
+
+        // the old object is the only one referring to the young one
+        vm.collect_garbage(&mut thread, false);
+        let survivor = next(&node, &old);
+        assert!(young(&vm).survivors.index_of(survivor.object().expect("survivor")).is_some());
+        assert!(young(&vm).remembered.contains(&(old.object().expect("old") as *const Object)));
+        assert_eq!(young(&vm).eden.objects.len(), 0);
+    }
+
+    #[test]
+    fn aastore_remembers_an_old_array_referring_to_a_young_object() {
+        let (mut vm, node, mut thread) = vm(Collector::Generational, DEFAULT_MAX_HEAP_SIZE);
+        let array_class = vm.load_class(BOOTSTRAP_LOADER, "[Ljava/lang/Object;").expect("Object[]");
+        let old = old_object(&mut vm, &node, &mut thread, Object::new_array(array_class, 1));
+        let young_node = new_node(&mut vm, &node);
+        invoke(&mut vm, &mut thread, &node, "storeElement", "([Ljava/lang/Object;Ljava/lang/Object;)V",
+            vec![Value::Reference(old.clone()), Value::Reference(young_node)]);
+        assert!(young(&vm).remembered.contains(&(old.object().expect("old") as *const Object)));
+
+        vm.collect_garbage(&mut thread, false);
+        let element = old.object().expect("old").array().load(0);
+        assert!(young(&vm).survivors.index_of(element.as_reference().object().expect("survivor")).is_some());
+    }
+
+    #[test]
+    fn young_objects_referring_to_each_other_are_not_remembered() {
+        let (mut vm, node, mut thread) = vm(Collector::Generational, DEFAULT_MAX_HEAP_SIZE);
+        let (first, second) = (new_node(&mut vm, &node), new_node(&mut vm, &node));
+        invoke(&mut vm, &mut thread, &node, "store", "(LNode;LNode;)V", vec![Value::Reference(first.clone()), Value::Reference(second.clone())]);
+        assert!(young(&vm).remembered.is_empty());
+        assert!(next(&node, &first).is_equal(&second));
+        // nothing refers to the young objects
+        vm.collect_garbage(&mut thread, false);
+        assert_eq!(young(&vm).survivors.used, 0);
+    }
+}
@@ -0,0 +1,289 @@
+// DEFLATE decompressor (RFC 1951), the layout follows zlib's puff.c
+
+const MAX_BITS: usize = 15;
+const MAX_LITERAL_LENGTH_CODES: usize = 288;
+const MAX_DISTANCE_CODES: usize = 30;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13
+];
+// the order of code length code lengths in a dynamic block header
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// inflate a raw deflate stream, `size_hint` is the expected uncompressed size if known
+pub fn inflate(input: &[u8], size_hint: usize) -> Result<Vec<u8>, String> {
+    let mut inflater = Inflater {
+        input,
+        pos: 0,
+        bit_buf: 0,
+        bit_count: 0,
+        output: Vec::with_capacity(size_hint)
+    };
+    loop {
+        let last = inflater.bits(1)?;
+        match inflater.bits(2)? {
+            0 => inflater.stored()?,
+            1 => inflater.fixed()?,
+            2 => inflater.dynamic()?,
+            _ => return Err("invalid deflate block type".to_string())
+        }
+        if last == 1 {
+            break;
+        }
+    }
+    Ok(inflater.output)
+}
+
+/// inflate a zlib (RFC 1950) wrapped deflate stream
+pub fn inflate_zlib(input: &[u8], size_hint: usize) -> Result<Vec<u8>, String> {
+    if input.len() < 2 {
+        return Err("truncated zlib header".to_string());
+    }
+    let (cmf, flg) = (input[0], input[1]);
+    if cmf & 0x0f != 8 || !((cmf as u16) << 8 | flg as u16).is_multiple_of(31) {
+        return Err("invalid zlib header".to_string());
+    }
+    if flg & 0x20 != 0 {
+        return Err("zlib preset dictionary is not supported".to_string());
+    }
+    inflate(&input[2..], size_hint)
+}
+
+struct Inflater<'a> {
+    input: &'a [u8],
+    pos: usize,
+    bit_buf: u64,
+    bit_count: u32,
+    output: Vec<u8>
+}
+
+/// canonical huffman code: number of symbols of each code length and the symbols ordered by code
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, String> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        // check for an over-subscribed set of lengths, incomplete codes are allowed
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left <<= 1;
+            left -= count as i32;
+            if left < 0 {
+                return Err("over-subscribed huffman code".to_string());
+            }
+        }
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for length in 1..MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+}
+
+impl<'a> Inflater<'a> {
+    fn bits(&mut self, need: u32) -> Result<u32, String> {
+        while self.bit_count < need {
+            if self.pos >= self.input.len() {
+                return Err("unexpected end of deflate stream".to_string());
+            }
+            self.bit_buf |= (self.input[self.pos] as u64) << self.bit_count;
+            self.pos += 1;
+            self.bit_count += 8;
+        }
+        let value = (self.bit_buf & ((1u64 << need) - 1)) as u32;
+        self.bit_buf >>= need;
+        self.bit_count -= need;
+        Ok(value)
+    }
+
+    fn stored(&mut self) -> Result<(), String> {
+        // discard the remaining bits of the current byte
+        self.bit_buf = 0;
+        self.bit_count = 0;
+        if self.pos + 4 > self.input.len() {
+            return Err("truncated stored block".to_string());
+        }
+        let length = u16::from_le_bytes([self.input[self.pos], self.input[self.pos + 1]]);
+        let complement = u16::from_le_bytes([self.input[self.pos + 2], self.input[self.pos + 3]]);
+        if length != !complement {
+            return Err("stored block length does not match its complement".to_string());
+        }
+        self.pos += 4;
+        let end = self.pos + length as usize;
+        if end > self.input.len() {
+            return Err("truncated stored block".to_string());
+        }
+        self.output.extend_from_slice(&self.input[self.pos..end]);
+        self.pos = end;
+        Ok(())
+    }
+
+    fn fixed(&mut self) -> Result<(), String> {
+        let mut lengths = [0u8; MAX_LITERAL_LENGTH_CODES];
+        lengths[..144].iter_mut().for_each(|length| *length = 8);
+        lengths[144..256].iter_mut().for_each(|length| *length = 9);
+        lengths[256..280].iter_mut().for_each(|length| *length = 7);
+        lengths[280..].iter_mut().for_each(|length| *length = 8);
+        let literal_length = Huffman::new(&lengths)?;
+        let distance = Huffman::new(&[5u8; MAX_DISTANCE_CODES])?;
+        self.codes(&literal_length, &distance)
+    }
+
+    fn dynamic(&mut self) -> Result<(), String> {
+        let literal_length_count = self.bits(5)? as usize + 257;
+        let distance_count = self.bits(5)? as usize + 1;
+        let code_length_count = self.bits(4)? as usize + 4;
+        if literal_length_count > MAX_LITERAL_LENGTH_CODES || distance_count > MAX_DISTANCE_CODES {
+            return Err("too many length or distance codes".to_string());
+        }
+
+        let mut code_lengths = [0u8; 19];
+        for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_lengths[index] = self.bits(3)? as u8;
+        }
+        let code_length = Huffman::new(&code_lengths)?;
+
+        let mut lengths = vec![0u8; literal_length_count + distance_count];
+        let mut index = 0;
+        while index < lengths.len() {
+            let symbol = self.decode(&code_length)?;
+            if symbol < 16 {
+                lengths[index] = symbol as u8;
+                index += 1;
+                continue;
+            }
+            let (length, repeat) = match symbol {
+                16 => {
+                    if index == 0 {
+                        return Err("repeat with no previous code length".to_string());
+                    }
+                    (lengths[index - 1], 3 + self.bits(2)? as usize)
+                }
+                17 => (0, 3 + self.bits(3)? as usize),
+                _ => (0, 11 + self.bits(7)? as usize)
+            };
+            if index + repeat > lengths.len() {
+                return Err("too many code lengths".to_string());
+            }
+            lengths[index..index + repeat].iter_mut().for_each(|l| *l = length);
+            index += repeat;
+        }
+        if lengths[256] == 0 {
+            return Err("missing end-of-block code".to_string());
+        }
+        let literal_length = Huffman::new(&lengths[..literal_length_count])?;
+        let distance = Huffman::new(&lengths[literal_length_count..])?;
+        self.codes(&literal_length, &distance)
+    }
+
+    fn decode(&mut self, huffman: &Huffman) -> Result<u16, String> {
+        let mut code: i32 = 0;  // bits being decoded
+        let mut first: i32 = 0; // first code of the current length
+        let mut index: i32 = 0; // index of the first code of the current length in symbols
+        for length in 1..=MAX_BITS {
+            code |= self.bits(1)? as i32;
+            let count = huffman.counts[length] as i32;
+            if code - count < first {
+                return Ok(huffman.symbols[(index + (code - first)) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err("invalid huffman code".to_string())
+    }
+
+    fn codes(&mut self, literal_length: &Huffman, distance: &Huffman) -> Result<(), String> {
+        loop {
+            let symbol = self.decode(literal_length)? as usize;
+            match symbol {
+                0..=255 => self.output.push(symbol as u8),
+                256 => return Ok(()),
+                _ => {
+                    let symbol = symbol - 257;
+                    if symbol >= LENGTH_BASE.len() {
+                        return Err("invalid literal/length symbol".to_string());
+                    }
+                    let length = LENGTH_BASE[symbol] as usize + self.bits(LENGTH_EXTRA[symbol] as u32)? as usize;
+                    let symbol = self.decode(distance)? as usize;
+                    if symbol >= DISTANCE_BASE.len() {
+                        return Err("invalid distance symbol".to_string());
+                    }
+                    let distance = DISTANCE_BASE[symbol] as usize + self.bits(DISTANCE_EXTRA[symbol] as u32)? as usize;
+                    if distance > self.output.len() {
+                        return Err("distance too far back".to_string());
+                    }
+                    let start = self.output.len() - distance;
+                    // the copy may overlap with its own output
+                    for i in 0..length {
+                        let byte = self.output[start + i];
+                        self.output.push(byte);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "hello, hello, hello!" compressed with the fixed codes
+    const FIXED: [u8; 12] = [203, 72, 205, 201, 201, 215, 81, 200, 64, 162, 20, 1];
+    // "a baaa bbab acaabacb" compressed with dynamic codes
+    const DYNAMIC: [u8; 21] = [5, 193, 1, 1, 0, 0, 8, 195, 160, 42, 175, 198, 236, 223, 65, 176, 176, 210, 28, 185, 30];
+
+    #[test]
+    fn inflate_fixed_and_dynamic_blocks() {
+        assert_eq!(inflate(&FIXED, 0), Ok(b"hello, hello, hello!".to_vec()));
+        assert_eq!(inflate(&DYNAMIC, 0), Ok(b"a baaa bbab acaabacb".to_vec()));
+    }
+
+    #[test]
+    fn inflate_stored_block_followed_by_another_block() {
+        let input = [&[0x00, 5, 0, !5, !0][..], b"hello", &FIXED].concat();
+        assert_eq!(inflate(&input, 0), Ok(b"hellohello, hello, hello!".to_vec()));
+    }
+
+    #[test]
+    fn inflate_zlib_stream() {
+        let input = [&[0x78, 0x9c][..], &FIXED, &[0x4b, 0x1e, 0x06, 0xf6]].concat();
+        assert_eq!(inflate_zlib(&input, 0), Ok(b"hello, hello, hello!".to_vec()));
+        assert_eq!(inflate_zlib(&[0x78, 0x9d], 0), Err("invalid zlib header".to_string()));
+    }
+
+    #[test]
+    fn invalid_deflate_streams_are_rejected() {
+        assert_eq!(inflate(&[0x07], 0), Err("invalid deflate block type".to_string()));
+        assert_eq!(inflate(&[0x01, 5, 0, 5, 0], 0), Err("stored block length does not match its complement".to_string()));
+        assert_eq!(inflate(&FIXED[..6], 0), Err("unexpected end of deflate stream".to_string()));
+    }
+}
@@ -0,0 +1,256 @@
+use std::rc::Rc;
+use crate::class_loader::BOOTSTRAP_LOADER;
+use crate::constants::*;
+use crate::exception::Throwable;
+use crate::interpreter;
+use crate::thread::JavaThread;
+use crate::types::{Class, Constant, InitState};
+use crate::values::Value;
+use crate::vm::Vm;
+
+impl Vm {
+    /// initialize `class` on `thread` (JVMS 5.5), triggered by `new`, `getstatic`, `putstatic`, `invokestatic`
+    /// and the invocation of the main method. It is a no-op for a class initialized or being initialized by `thread`.
+    pub fn initialize_class(&mut self, thread: &mut JavaThread, class: &Rc<Class>) -> Result<(), Throwable> {
+        match class.init_state.get() {
+            InitState::Initialized => return Ok(()),
+            // a recursive request, e.g. <clinit> creating an instance of its own class
+            InitState::BeingInitialized(id) if id == thread.id => return Ok(()),
+            // threads run one at a time and never switch inside <clinit>, the other thread can not complete the
+            // initialization while this one waits for it, so the class is as good as erroneous
+            InitState::BeingInitialized(_) | InitState::Erroneous => return Err(Throwable::new(JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR,
+                format!("Could not initialize class {}", class.name.replace('/', ".")))),
+            InitState::Uninitialized => {}
+        }
+        // a class failing verification stays uninitialized, and fails again on the next attempt
+        self.link_class(class)?;
+        class.init_state.set(InitState::BeingInitialized(thread.id));
+        if let Err(throwable) = self.initialize_constant_fields(thread, class) {
+            class.init_state.set(InitState::Erroneous);
+            return Err(throwable);
+        }
+
+        if !class.is_interface() {
+            let mut supers: Vec<Rc<Class>> = class.super_class.iter().cloned().collect();
+            supers.extend(superinterfaces_with_defaults(class));
+            for super_class in supers {
+                if let Err(throwable) = self.initialize_class(thread, &super_class) {
+                    class.init_state.set(InitState::Erroneous);
+                    return Err(throwable);
+                }
+            }
+        }
+
+        let result = match class.find_method(CLASS_INITIALIZER_NAME, CLASS_INITIALIZER_DESCRIPTOR) {
+            Some(clinit) if clinit.is_static() => interpreter::invoke(self, thread, &clinit, vec![]).map(|_| ()),
+            _ => Ok(())
+        };
+        match result {
+            Ok(()) => {
+                class.init_state.set(InitState::Initialized);
+                Ok(())
+            }
+            Err(throwable) => {
+                class.init_state.set(InitState::Erroneous);
+                if self.is_error(&throwable) {
+                    Err(throwable)
+                } else {
+                    Err(Throwable::with_cause(JAVA_LANG_EXCEPTION_IN_INITIALIZER_ERROR, "", throwable))
+                }
+            }
+        }
+    }
+
+    // the final static fields with a ConstantValue attribute are set before any <clinit> runs, a String constant
+    // is the interned string of its literal
+    fn initialize_constant_fields(&mut self, thread: &mut JavaThread, class: &Class) -> Result<(), Throwable> {
+        for field in &class.static_var_fields {
+            if field.access_flags() & ACC_FINAL == 0 || field.constant_value_index() == 0 {
+                continue;
+            }
+            let value = match &class.constant_pool[field.constant_value_index() as usize] {
+                Constant::Integer(i) => Value::Int(*i),
+                Constant::Long(l) => Value::Long(*l),
+                Constant::Float(f) => Value::Float(*f),
+                Constant::Double(d) => Value::Double(*d),
+                // interning may collect garbage, the string is stored before the next one is interned
+                Constant::String(chars) => Value::Reference(self.intern(thread, chars)?),
+                _ => continue
+            };
+            class.static_vars.borrow_mut()[field.offset()] = value;
+        }
+        Ok(())
+    }
+
+    // an Error is rethrown as is, any other exception thrown by <clinit> is wrapped. The class of an exception thrown
+    // by java code may be defined by any loader, only the exceptions the VM throws are of bootstrap classes.
+    fn is_error(&mut self, throwable: &Throwable) -> bool {
+        let class = match throwable.exception.object() {
+            Some(exception) => Ok(exception.class.clone()),
+            None => self.load_class(BOOTSTRAP_LOADER, &throwable.class_name)
+        };
+        match (class, self.load_class(BOOTSTRAP_LOADER, JAVA_LANG_ERROR)) {
+            (Ok(class), Ok(error)) => class.is_subclass_of(&error),
+            _ => false
+        }
+    }
+}
+
+/// the superinterfaces, direct or indirect, declaring a non-abstract instance method, i.e. a default method.
+/// The superinterfaces of an interface come before it, in the order of the `interfaces` array.
+fn superinterfaces_with_defaults(class: &Class) -> Vec<Rc<Class>> {
+    let mut result: Vec<Rc<Class>> = vec![];
+    for interface in &class.interfaces {
+        for superinterface in superinterfaces_with_defaults(interface) {
+            if !result.iter().any(|found| found.id == superinterface.id) {
+                result.push(superinterface);
+            }
+        }
+        let declares_default = interface.methods.iter().any(|method| !method.is_abstract() && !method.is_static());
+        if declares_default && !result.iter().any(|found| found.id == interface.id) {
+            result.push(interface.clone());
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_builder::{test_vm, ClassBuilder, Code};
+    use crate::class_loader::APP_LOADER;
+    use crate::thread::ThreadId;
+    use crate::vm::VmOptions;
+
+    // the opcodes of the static initializers
+    const ICONST_1: u8 = 0x04;
+    const IADD: u8 = 0x60;
+    const NEW: u8 = 0xbb;
+    const DUP: u8 = 0x59;
+    const INVOKESPECIAL: u8 = 0xb7;
+    const INVOKESTATIC: u8 = 0xb8;
+    const GETSTATIC: u8 = 0xb2;
+    const PUTSTATIC: u8 = 0xb3;
+    const ATHROW: u8 = 0xbf;
+    const RETURN: u8 = 0xb1;
+
+    /// a VM whose bootstrap loader defines java/lang/Throwable, java/lang/Error and java/lang/Exception
+    fn vm() -> (Vm, JavaThread) {
+        let mut vm = test_vm(VmOptions::default());
+        ClassBuilder::new("java/lang/Throwable", Some(JAVA_LANG_OBJECT))
+            .field(ACC_PRIVATE, "detailMessage", "Ljava/lang/String;")
+            .field(ACC_PRIVATE, "cause", "Ljava/lang/Throwable;")
+            .constructor(JAVA_LANG_OBJECT)
+            .define(&mut vm, BOOTSTRAP_LOADER);
+        for class in [JAVA_LANG_ERROR, "java/lang/Exception"] {
+            ClassBuilder::new(class, Some("java/lang/Throwable")).constructor("java/lang/Throwable").define(&mut vm, BOOTSTRAP_LOADER);
+        }
+        (vm, JavaThread::main())
+    }
+
+    fn op(opcode: u8, index: u16) -> Vec<u8> {
+        [&[opcode][..], &index.to_be_bytes()].concat()
+    }
+
+    /// `static <name> = <name> + 1`
+    fn increment(builder: &mut ClassBuilder, class: &str, name: &str) -> Vec<u8> {
+        let field = builder.field_ref(class, name, "I");
+        [op(GETSTATIC, field), vec![ICONST_1, IADD], op(PUTSTATIC, field)].concat()
+    }
+
+    /// a class whose static initializer throws a new `exception`, a subclass of `super_class` defined by the application loader
+    fn throwing(vm: &mut Vm, exception: &str, super_class: &str) -> Rc<Class> {
+        ClassBuilder::new(exception, Some(super_class)).constructor(super_class).define(vm, APP_LOADER);
+        let mut builder = ClassBuilder::new("T", Some(JAVA_LANG_OBJECT));
+        let (class, init) = (builder.class(exception), builder.method_ref(exception, "<init>", "()V"));
+        let code = [op(NEW, class), vec![DUP], op(INVOKESPECIAL, init), vec![ATHROW]].concat();
+        builder.method(ACC_STATIC, CLASS_INITIALIZER_NAME, CLASS_INITIALIZER_DESCRIPTOR, Some(Code::new(2, 0, code)))
+            .define(vm, APP_LOADER)
+    }
+
+    fn static_int(class: &Class, name: &str) -> i32 {
+        let field = class.find_field(name, "I").expect("static field");
+        class.static_vars.borrow()[field.offset()].as_int()
+    }
+
+    #[test]
+    fn static_initializer_runs_once() {
+        let (mut vm, mut thread) = vm();
+        let mut builder = ClassBuilder::new("C", Some(JAVA_LANG_OBJECT));
+        // a recursive request by <clinit> returns at once
+        let m = builder.method_ref("C", "m", "()V");
+        let code = [increment(&mut builder, "C", "count"), op(INVOKESTATIC, m), vec![RETURN]].concat();
+        let class = builder.field(ACC_STATIC, "count", "I")
+            .method(ACC_STATIC, "m", "()V", Some(Code::new(0, 0, vec![RETURN])))
+            .method(ACC_STATIC, CLASS_INITIALIZER_NAME, CLASS_INITIALIZER_DESCRIPTOR, Some(Code::new(2, 0, code)))
+            .define(&mut vm, APP_LOADER);
+        assert_eq!(class.init_state.get(), InitState::Uninitialized);
+        vm.initialize_class(&mut thread, &class).expect("initialized");
+        vm.initialize_class(&mut thread, &class).expect("initialized");
+        assert_eq!(class.init_state.get(), InitState::Initialized);
+        assert_eq!(static_int(&class, "count"), 1);
+    }
+
+    #[test]
+    fn constants_and_superclasses_are_initialized_before_the_static_initializer() {
+        let (mut vm, mut thread) = vm();
+        let mut builder = ClassBuilder::new("S", Some(JAVA_LANG_OBJECT));
+        let code = [increment(&mut builder, "S", "value"), vec![RETURN]].concat();
+        builder.field(ACC_STATIC, "value", "I")
+            .method(ACC_STATIC, CLASS_INITIALIZER_NAME, CLASS_INITIALIZER_DESCRIPTOR, Some(Code::new(2, 0, code)))
+            .define(&mut vm, APP_LOADER);
+        // an interface without default methods is not initialized along with its implementations
+        let mut builder = ClassBuilder::new("I", Some(JAVA_LANG_OBJECT));
+        let code = [increment(&mut builder, "I", "touched"), vec![RETURN]].concat();
+        let interface = builder.access_flags(ACC_PUBLIC | ACC_INTERFACE | ACC_ABSTRACT)
+            .field(ACC_PUBLIC | ACC_STATIC | ACC_FINAL, "touched", "I")
+            .method(ACC_STATIC, CLASS_INITIALIZER_NAME, CLASS_INITIALIZER_DESCRIPTOR, Some(Code::new(2, 0, code)))
+            .define(&mut vm, APP_LOADER);
+        // C.copy = S.value + C.K, where K is a constant
+        let mut builder = ClassBuilder::new("C", Some("S"));
+        let (value, k, copy, forty_one) = (builder.field_ref("S", "value", "I"), builder.field_ref("C", "K", "I"),
+            builder.field_ref("C", "copy", "I"), builder.integer(41));
+        let code = [op(GETSTATIC, value), op(GETSTATIC, k), vec![IADD], op(PUTSTATIC, copy), vec![RETURN]].concat();
+        let class = builder.interface("I")
+            .field_with_constant(ACC_STATIC | ACC_FINAL, "K", "I", forty_one)
+            .field(ACC_STATIC, "copy", "I")
+            .method(ACC_STATIC, CLASS_INITIALIZER_NAME, CLASS_INITIALIZER_DESCRIPTOR, Some(Code::new(2, 0, code)))
+            .define(&mut vm, APP_LOADER);
+        vm.initialize_class(&mut thread, &class).expect("initialized");
+        assert_eq!(static_int(&class, "copy"), 42);
+        assert_eq!(class.super_class.as_ref().expect("S").init_state.get(), InitState::Initialized);
+        assert_eq!(interface.init_state.get(), InitState::Uninitialized);
+    }
+
+    #[test]
+    fn error_of_the_static_initializer_is_rethrown() {
+        let (mut vm, mut thread) = vm();
+        // the error is defined by the application loader
+        let class = throwing(&mut vm, "E", JAVA_LANG_ERROR);
+        let throwable = vm.initialize_class(&mut thread, &class).expect_err("E");
+        assert_eq!(throwable.class_name, "E");
+        assert_eq!(class.init_state.get(), InitState::Erroneous);
+        let throwable = vm.initialize_class(&mut thread, &class).expect_err("NoClassDefFoundError");
+        assert_eq!(throwable.to_string(), "java.lang.NoClassDefFoundError: Could not initialize class T");
+    }
+
+    #[test]
+    fn exception_of_the_static_initializer_is_wrapped() {
+        let (mut vm, mut thread) = vm();
+        let class = throwing(&mut vm, "X", "java/lang/Exception");
+        let throwable = vm.initialize_class(&mut thread, &class).expect_err("ExceptionInInitializerError");
+        assert_eq!(throwable.class_name, JAVA_LANG_EXCEPTION_IN_INITIALIZER_ERROR);
+        assert_eq!(throwable.cause.expect("cause").class_name, "X");
+        assert_eq!(class.init_state.get(), InitState::Erroneous);
+    }
+
+    #[test]
+    fn class_being_initialized_by_another_thread_is_not_initialized() {
+        let (mut vm, mut thread) = vm();
+        let class = ClassBuilder::new("C", Some(JAVA_LANG_OBJECT)).define(&mut vm, APP_LOADER);
+        class.init_state.set(InitState::BeingInitialized(ThreadId(2)));
+        let throwable = vm.initialize_class(&mut thread, &class).expect_err("NoClassDefFoundError");
+        assert_eq!(throwable.to_string(), "java.lang.NoClassDefFoundError: Could not initialize class C");
+        assert_eq!(class.init_state.get(), InitState::BeingInitialized(ThreadId(2)));
+    }
+}
@@ -0,0 +1,624 @@
+use std::rc::Rc;
+use crate::arithmetic;
+use crate::bytecode::*;
+use crate::dispatch::{find_interface_method, select_interface, select_special, select_virtual, superclasses};
+use crate::class_loader::BOOTSTRAP_LOADER;
+use crate::constants::*;
+use crate::exception::Throwable;
+use crate::frame::Frame;
+use crate::natives;
+use crate::heap::Heap;
+use crate::object::{array_size, instance_size, Object};
+use crate::thread::JavaThread;
+use crate::types::{parse_method_descriptor, Class, Constant, Field, Method, ResolvedMethod, Type};
+use crate::values::{Reference, Value};
+use crate::vm::Vm;
+
+/// execute `method` on `thread`, `args` are the slots of its parameters including the receiver of an instance method
+pub fn invoke(vm: &mut Vm, thread: &mut JavaThread, method: &Rc<Method>, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    if method.is_native() {
+        let class = method.class();
+        // the interface methods of a class spun for a lambda
+        if let Some(lambda) = vm.lambdas.get(&class.id).cloned() {
+            return lambda.invoke(vm, thread, method, args);
+        }
+        return match natives::find_native(&class.name, method.name(), method.descriptor()) {
+            Some(native) => native(vm, thread, args),
+            None => Err(Throwable::new(JAVA_LANG_UNSATISFIED_LINK_ERROR, format!(
+                "{}.{}{}", class.name.replace('/', "."), method.name(), method.descriptor())))
+        };
+    }
+    if method.is_abstract() {
+        return Err(Throwable::new(JAVA_LANG_ABSTRACT_METHOD_ERROR, method_name(method)));
+    }
+    thread.push_frame(Frame::new(method.clone(), args))?;
+    let result = execute(vm, thread);
+    thread.pop_frame();
+    result
+}
+
+/// `java.lang.Object.hashCode()I` as in the messages of linkage errors
+pub(crate) fn method_name(method: &Method) -> String {
+    format!("{}.{}{}", method.class().name.replace('/', "."), method.name(), method.descriptor())
+}
+
+/// the int stored to a variable of `descriptor` is truncated to its type, a boolean to its lowest bit
+fn narrow(value: Value, descriptor: &str) -> Value {
+    match (value, &descriptor[..1]) {
+        (Value::Int(value), JVM_SIGNATURE_BOOLEAN) => Value::Int(value & 1),
+        (Value::Int(value), JVM_SIGNATURE_BYTE) => Value::Int(value as i8 as i32),
+        (Value::Int(value), JVM_SIGNATURE_CHAR) => Value::Int(value as u16 as i32),
+        (Value::Int(value), JVM_SIGNATURE_SHORT) => Value::Int(value as i16 as i32),
+        (value, _) => value
+    }
+}
+
+/// the number of slots the parameters of a method take
+fn parameter_slots(method: &Method) -> usize {
+    method.parameter_descriptors().iter()
+        .map(|descriptor| if descriptor == JVM_SIGNATURE_LONG || descriptor == JVM_SIGNATURE_DOUBLE { 2 } else { 1 })
+        .sum()
+}
+
+/// run the current frame of `thread` until its method returns, or throws an exception none of its handlers catches
+fn execute(vm: &mut Vm, thread: &mut JavaThread) -> Result<Option<Value>, Throwable> {
+    loop {
+        let mut throwable = match interpret(vm, thread) {
+            Err(throwable) => throwable,
+            result => return result
+        };
+        // an exception thrown by the VM becomes an object in the frame throwing it, its causes are kept by the object
+        // rather than by copies a collection would leave behind
+        if throwable.exception.is_null() {
+            let exception = vm.exception_object(thread, &throwable)?;
+            throwable = vm.thrown(exception);
+        }
+        match find_handler(vm, thread, &throwable)? {
+            Some(handler_pc) => {
+                let frame = thread.frame();
+                frame.stack.clear();
+                frame.push(Value::Reference(throwable.exception));
+                frame.pc = handler_pc;
+            }
+            None => return Err(throwable)
+        }
+    }
+}
+
+/// the first handler of the current frame covering its pc whose class the exception is an instance of (JVMS 2.10),
+/// a handler without a class catches any exception
+fn find_handler(vm: &mut Vm, thread: &mut JavaThread, throwable: &Throwable) -> Result<Option<usize>, Throwable> {
+    let frame = thread.frame();
+    let (method, pc) = (frame.method.clone(), frame.pc);
+    let class = method.class();
+    let exception_class = throwable.exception.object().expect("exception object").class.clone();
+    for handler in method.exceptions() {
+        if pc < handler.start_pc as usize || pc >= handler.end_pc as usize {
+            continue;
+        }
+        if handler.catch_type.is_empty() {
+            return Ok(Some(handler.handler_pc as usize));
+        }
+        let catch_class = vm.resolve_class(class.defining_loader, &handler.catch_type)?;
+        if exception_class.is_subclass_of(&catch_class) {
+            return Ok(Some(handler.handler_pc as usize));
+        }
+    }
+    Ok(None)
+}
+
+/// interpret the instructions of the current frame of `thread` from its pc
+fn interpret(vm: &mut Vm, thread: &mut JavaThread) -> Result<Option<Value>, Throwable> {
+    let method = thread.frame().method.clone();
+    let class = method.class();
+    let code = method.code();
+    loop {
+        let frame = thread.frame();
+        let pc = frame.pc;
+        let opcode = code[pc];
+        let mut next = pc + instruction_length(code, pc).expect("verified instruction");
+        match opcode {
+            NOP => {}
+            ACONST_NULL => frame.push(Value::Reference(Reference::null())),
+            ICONST_M1..=ICONST_5 => frame.push(Value::Int(opcode as i32 - ICONST_0 as i32)),
+            LCONST_0 | LCONST_1 => frame.push(Value::Long((opcode - LCONST_0) as i64)),
+            FCONST_0..=FCONST_2 => frame.push(Value::Float((opcode - FCONST_0) as f32)),
+            DCONST_0 | DCONST_1 => frame.push(Value::Double((opcode - DCONST_0) as f64)),
+            BIPUSH => frame.push(Value::Int(code[pc + 1] as i8 as i32)),
+            SIPUSH => frame.push(Value::Int(i2(code, pc + 1) as i32)),
+            LDC | LDC_W | LDC2_W => {
+                let index = if opcode == LDC { u1(code, pc + 1) as usize } else { u2(code, pc + 1) as usize };
+                let value = load_constant(vm, thread, &class, index)?;
+                thread.frame().push(value);
+            }
+            ILOAD..=ALOAD => frame.push(frame.local(u1(code, pc + 1) as usize)),
+            ILOAD_0..=ALOAD_3 => frame.push(frame.local(((opcode - ILOAD_0) % 4) as usize)),
+            ISTORE..=ASTORE => {
+                let value = frame.pop();
+                frame.set_local(u1(code, pc + 1) as usize, value);
+            }
+            ISTORE_0..=ASTORE_3 => {
+                let value = frame.pop();
+                frame.set_local(((opcode - ISTORE_0) % 4) as usize, value);
+            }
+            IALOAD..=SALOAD => {
+                let index = frame.pop_int();
+                let array = frame.pop_reference();
+                let array = array_element(&array, index)?;
+                frame.push(array.array().load(index as usize));
+            }
+            IASTORE..=SASTORE => {
+                let value = frame.pop();
+                let index = frame.pop_int();
+                let array = frame.pop_reference();
+                let array = array_element(&array, index)?;
+                if opcode == AASTORE {
+                    check_array_store(array, &value)?;
+                    vm.heap.write_barrier(array, &value);
+                }
+                array.array().store(index as usize, value);
+            }
+            WIDE => {
+                let index = u2(code, pc + 2) as usize;
+                match code[pc + 1] {
+                    ILOAD..=ALOAD => frame.push(frame.local(index)),
+                    ISTORE..=ASTORE => {
+                        let value = frame.pop();
+                        frame.set_local(index, value);
+                    }
+                    IINC => {
+                        let value = frame.local_int(index);
+                        frame.set_local(index, Value::Int(value.wrapping_add(i2(code, pc + 4) as i32)));
+                    }
+                    RET => next = frame.local(index).as_return_address(),
+                    modified => return Err(unsupported(&method, pc, modified))
+                }
+            }
+            IINC => {
+                let index = u1(code, pc + 1) as usize;
+                let value = frame.local_int(index);
+                frame.set_local(index, Value::Int(value.wrapping_add(code[pc + 2] as i8 as i32)));
+            }
+            // the stack manipulation instructions work on slots whatever their types (JVMS 2.11.1)
+            POP => {
+                frame.pop_slot();
+            }
+            POP2 => {
+                frame.pop_slots(2);
+            }
+            DUP => {
+                let v1 = frame.pop_slot();
+                frame.push_slots([v1.clone(), v1]);
+            }
+            DUP_X1 => {
+                let (v1, v2) = (frame.pop_slot(), frame.pop_slot());
+                frame.push_slots([v1.clone(), v2, v1]);
+            }
+            DUP_X2 => {
+                let (v1, v2, v3) = (frame.pop_slot(), frame.pop_slot(), frame.pop_slot());
+                frame.push_slots([v1.clone(), v3, v2, v1]);
+            }
+            DUP2 => {
+                let slots = frame.pop_slots(2);
+                frame.push_slots(slots.clone());
+                frame.push_slots(slots);
+            }
+            DUP2_X1 => {
+                let slots = frame.pop_slots(2);
+                let v3 = frame.pop_slot();
+                frame.push_slots(slots.clone());
+                frame.push_slots([v3]);
+                frame.push_slots(slots);
+            }
+            DUP2_X2 => {
+                let slots = frame.pop_slots(2);
+                let under = frame.pop_slots(2);
+                frame.push_slots(slots.clone());
+                frame.push_slots(under);
+                frame.push_slots(slots);
+            }
+            SWAP => {
+                let (v1, v2) = (frame.pop_slot(), frame.pop_slot());
+                frame.push_slots([v1, v2]);
+            }
+            IADD | ISUB | IMUL | IDIV | IREM | ISHL | ISHR | IUSHR | IAND | IOR | IXOR => {
+                let (value2, value1) = (frame.pop_int(), frame.pop_int());
+                frame.push(Value::Int(arithmetic::int_binary(opcode, value1, value2)?));
+            }
+            LADD | LSUB | LMUL | LDIV | LREM | LAND | LOR | LXOR => {
+                let (value2, value1) = (frame.pop_long(), frame.pop_long());
+                frame.push(Value::Long(arithmetic::long_binary(opcode, value1, value2)?));
+            }
+            LSHL | LSHR | LUSHR => {
+                let (distance, value) = (frame.pop_int(), frame.pop_long());
+                frame.push(Value::Long(arithmetic::long_shift(opcode, value, distance)));
+            }
+            FADD | FSUB | FMUL | FDIV | FREM => {
+                let (value2, value1) = (frame.pop_float(), frame.pop_float());
+                frame.push(Value::Float(arithmetic::float_binary(opcode, value1, value2)));
+            }
+            DADD | DSUB | DMUL | DDIV | DREM => {
+                let (value2, value1) = (frame.pop_double(), frame.pop_double());
+                frame.push(Value::Double(arithmetic::double_binary(opcode, value1, value2)));
+            }
+            INEG | LNEG | FNEG | DNEG => {
+                let value = frame.pop();
+                frame.push(arithmetic::negate(opcode, value));
+            }
+            I2L..=I2S => {
+                let value = frame.pop();
+                frame.push(arithmetic::convert(opcode, value));
+            }
+            LCMP => {
+                let (value2, value1) = (frame.pop_long(), frame.pop_long());
+                frame.push(Value::Int(arithmetic::lcmp(value1, value2)));
+            }
+            FCMPL | FCMPG => {
+                let (value2, value1) = (frame.pop_float(), frame.pop_float());
+                frame.push(Value::Int(arithmetic::float_compare(opcode, value1, value2)));
+            }
+            DCMPL | DCMPG => {
+                let (value2, value1) = (frame.pop_double(), frame.pop_double());
+                frame.push(Value::Int(arithmetic::double_compare(opcode, value1, value2)));
+            }
+            IFEQ..=IFLE => {
+                let value = frame.pop_int();
+                let jump = match opcode {
+                    IFEQ => value == 0,
+                    IFNE => value != 0,
+                    IFLT => value < 0,
+                    IFGE => value >= 0,
+                    IFGT => value > 0,
+                    _ => value <= 0
+                };
+                if jump {
+                    next = branch(pc, i2(code, pc + 1) as i32);
+                }
+            }
+            IF_ICMPEQ..=IF_ICMPLE => {
+                let (value2, value1) = (frame.pop_int(), frame.pop_int());
+                let jump = match opcode {
+                    IF_ICMPEQ => value1 == value2,
+                    IF_ICMPNE => value1 != value2,
+                    IF_ICMPLT => value1 < value2,
+                    IF_ICMPGE => value1 >= value2,
+                    IF_ICMPGT => value1 > value2,
+                    _ => value1 <= value2
+                };
+                if jump {
+                    next = branch(pc, i2(code, pc + 1) as i32);
+                }
+            }
+            IF_ACMPEQ | IF_ACMPNE => {
+                let (value2, value1) = (frame.pop_reference(), frame.pop_reference());
+                if value1.is_equal(&value2) == (opcode == IF_ACMPEQ) {
+                    next = branch(pc, i2(code, pc + 1) as i32);
+                }
+            }
+            IFNULL | IFNONNULL => {
+                if frame.pop_reference().is_null() == (opcode == IFNULL) {
+                    next = branch(pc, i2(code, pc + 1) as i32);
+                }
+            }
+            GOTO => next = branch(pc, i2(code, pc + 1) as i32),
+            JSR => {
+                frame.push(Value::ReturnAddress(next));
+                next = branch(pc, i2(code, pc + 1) as i32);
+            }
+            JSR_W => {
+                frame.push(Value::ReturnAddress(next));
+                next = branch(pc, i4(code, pc + 1));
+            }
+            RET => next = frame.local(u1(code, pc + 1) as usize).as_return_address(),
+            GOTO_W => next = branch(pc, i4(code, pc + 1)),
+            TABLESWITCH => {
+                let index = frame.pop_int();
+                let operands = switch_operands(pc);
+                let (low, high) = (i4(code, operands + 4), i4(code, operands + 8));
+                let offset = if index < low || index > high {
+                    i4(code, operands)
+                } else {
+                    i4(code, operands + 12 + 4 * (index as i64 - low as i64) as usize)
+                };
+                next = branch(pc, offset);
+            }
+            LOOKUPSWITCH => {
+                let key = frame.pop_int();
+                let operands = switch_operands(pc);
+                let pairs = i4(code, operands + 4) as usize;
+                let offset = (0..pairs).map(|i| operands + 8 + 8 * i)
+                    .find(|&pair| i4(code, pair) == key)
+                    .map_or_else(|| i4(code, operands), |pair| i4(code, pair + 4));
+                next = branch(pc, offset);
+            }
+            IRETURN | LRETURN | FRETURN | DRETURN | ARETURN => return Ok(Some(frame.pop())),
+            RETURN => return Ok(None),
+            GETSTATIC | PUTSTATIC => {
+                let field = resolve_field(vm, &class, u2(code, pc + 1), true)?;
+                let field_class = field.class();
+                vm.initialize_class(thread, &field_class)?;
+                let frame = thread.frame();
+                if opcode == GETSTATIC {
+                    frame.push(field_class.static_vars.borrow()[field.offset()].clone());
+                } else {
+                    let value = narrow(frame.pop(), field.descriptor());
+                    field_class.static_vars.borrow_mut()[field.offset()] = value;
+                }
+            }
+            GETFIELD | PUTFIELD => {
+                let field = resolve_field(vm, &class, u2(code, pc + 1), false)?;
+                let frame = thread.frame();
+                let value = if opcode == PUTFIELD { Some(narrow(frame.pop(), field.descriptor())) } else { None };
+                let object = frame.pop_reference();
+                let object = object.object().ok_or_else(|| Throwable::new(JAVA_LANG_NULL_POINTER_EXCEPTION, ""))?;
+                match value {
+                    Some(value) => {
+                        vm.heap.write_barrier(object, &value);
+                        object.set_field(&field, value);
+                    }
+                    None => frame.push(object.get_field(&field))
+                }
+            }
+            INVOKEVIRTUAL | INVOKESPECIAL | INVOKESTATIC | INVOKEINTERFACE => {
+                let (referenced, resolved) = resolved_method(vm, &class, u2(code, pc + 1))?;
+                if resolved.is_static() != (opcode == INVOKESTATIC) {
+                    return Err(Throwable::new(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, format!(
+                        "Expecting {} method {}", if opcode == INVOKESTATIC { "static" } else { "non-static" }, method_name(&resolved))));
+                }
+                if opcode == INVOKESTATIC {
+                    // <clinit> may collect garbage, the arguments stay on the operand stack until it returns
+                    vm.initialize_class(thread, &resolved.class())?;
+                }
+                let count = parameter_slots(&resolved) + if opcode == INVOKESTATIC { 0 } else { 1 };
+                let args = thread.frame().pop_slots(count);
+                let selected = match opcode {
+                    INVOKESTATIC => resolved,
+                    _ => {
+                        let receiver = args[0].as_reference().object()
+                            .ok_or_else(|| Throwable::new(JAVA_LANG_NULL_POINTER_EXCEPTION, ""))?;
+                        // the VM creates objects, e.g. strings, of classes not linked yet
+                        vm.link_class(&receiver.class)?;
+                        match opcode {
+                            INVOKESPECIAL => select_special(&class, &referenced, resolved)?,
+                            INVOKEVIRTUAL => select_virtual(&receiver.class, resolved)?,
+                            _ => select_interface(&receiver.class, resolved)?
+                        }
+                    }
+                };
+                if let Some(value) = invoke(vm, thread, &selected, args)? {
+                    thread.frame().push(value);
+                }
+            }
+            INVOKEDYNAMIC => {
+                let call_site = vm.call_site(&method, pc)?;
+                if let Some(value) = vm.invoke_call_site(thread, &call_site)? {
+                    thread.frame().push(value);
+                }
+            }
+            ATHROW => {
+                let exception = frame.pop_reference();
+                if exception.is_null() {
+                    return Err(Throwable::new(JAVA_LANG_NULL_POINTER_EXCEPTION, ""));
+                }
+                return Err(vm.thrown(exception));
+            }
+            NEW => {
+                let new_class = resolve_class(vm, &class, u2(code, pc + 1))?;
+                if new_class.is_interface() || new_class.access_flags & ACC_ABSTRACT != 0 {
+                    return Err(Throwable::new(JAVA_LANG_INSTANTIATION_ERROR, new_class.name.replace('/', ".")));
+                }
+                vm.initialize_class(thread, &new_class)?;
+                vm.reserve(thread, instance_size(&new_class))?;
+                let object = vm.heap.allocate(Object::new(new_class))?;
+                thread.frame().push(Value::Reference(object));
+            }
+            NEWARRAY | ANEWARRAY => {
+                let array_class = if opcode == NEWARRAY {
+                    let descriptor = newarray_descriptor(code[pc + 1]).expect("verified array type");
+                    vm.load_class(BOOTSTRAP_LOADER, descriptor)?
+                } else {
+                    let component = resolve_class(vm, &class, u2(code, pc + 1))?;
+                    let name = if component.is_array() { format!("[{}", component.name) } else { format!("[L{};", component.name) };
+                    vm.resolve_class(class.defining_loader, &name)?
+                };
+                let length = thread.frame().pop_int();
+                let array = new_array(vm, thread, &array_class, &[length])?;
+                thread.frame().push(Value::Reference(array));
+            }
+            ARRAYLENGTH => {
+                let array = frame.pop_reference();
+                let array = array.object().ok_or_else(|| Throwable::new(JAVA_LANG_NULL_POINTER_EXCEPTION, ""))?;
+                frame.push(Value::Int(array.array_length() as i32));
+            }
+            CHECKCAST | INSTANCEOF => {
+                let target = resolve_class(vm, &class, u2(code, pc + 1))?;
+                let frame = thread.frame();
+                let reference = frame.pop_reference();
+                let instance_of = reference.object().is_some_and(|object| object.class.is_assignable_to(&target));
+                if opcode == INSTANCEOF {
+                    frame.push(Value::Int(instance_of as i32));
+                } else if reference.is_null() || instance_of {
+                    frame.push(Value::Reference(reference));
+                } else {
+                    let object_class = &reference.object().expect("non-null").class;
+                    return Err(Throwable::new(JAVA_LANG_CLASS_CAST_EXCEPTION, format!("class {} cannot be cast to class {}",
+                        object_class.name.replace('/', "."), target.name.replace('/', "."))));
+                }
+            }
+            // monitors are no-ops while there is a single thread, only the null check is done
+            MONITORENTER | MONITOREXIT => {
+                if frame.pop_reference().is_null() {
+                    return Err(Throwable::new(JAVA_LANG_NULL_POINTER_EXCEPTION, ""));
+                }
+            }
+            MULTIANEWARRAY => {
+                let array_class = resolve_class(vm, &class, u2(code, pc + 1))?;
+                let lengths: Vec<i32> = thread.frame().pop_slots(code[pc + 3] as usize).iter().map(Value::as_int).collect();
+                let array = new_array(vm, thread, &array_class, &lengths)?;
+                thread.frame().push(Value::Reference(array));
+            }
+            _ => return Err(unsupported(&method, pc, opcode))
+        }
+        thread.frame().pc = next;
+    }
+}
+
+/// the array `reference` refers to if `index` is within its bounds
+fn array_element(reference: &Reference, index: i32) -> Result<&Object, Throwable> {
+    let array = reference.object().ok_or_else(|| Throwable::new(JAVA_LANG_NULL_POINTER_EXCEPTION, ""))?;
+    let length = array.array_length();
+    if index < 0 || index as usize >= length {
+        return Err(Throwable::new(JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, format!("Index {} out of bounds for length {}", index, length)));
+    }
+    Ok(array)
+}
+
+/// aastore only stores a reference assignable to the component type of the runtime class of the array
+fn check_array_store(array: &Object, value: &Value) -> Result<(), Throwable> {
+    if let (Some(object), Some(Type::Class(component))) = (value.as_reference().object(), array.class.component_type.as_deref()) {
+        if !object.class.is_assignable_to(component) {
+            return Err(Throwable::new(JAVA_LANG_ARRAY_STORE_EXCEPTION, object.class.name.replace('/', ".")));
+        }
+    }
+    Ok(())
+}
+
+/// a new array of `class` with the first of `lengths` elements, each of them an array of the next lengths in turn.
+/// Every length is checked and the heap is reserved for all the arrays before any of them is allocated.
+fn new_array(vm: &mut Vm, thread: &mut JavaThread, class: &Rc<Class>, lengths: &[i32]) -> Result<Reference, Throwable> {
+    if let Some(length) = lengths.iter().find(|&&length| length < 0) {
+        return Err(Throwable::new(JAVA_LANG_NEGATIVE_ARRAY_SIZE_EXCEPTION, length.to_string()));
+    }
+    vm.reserve(thread, arrays_size(class, lengths))?;
+    allocate_arrays(&mut vm.heap, class, lengths)
+}
+
+/// the bytes of an array along with its subarrays, the arrays of the dimensions after a length of 0 are not created
+fn arrays_size(class: &Class, lengths: &[i32]) -> usize {
+    let length = lengths[0] as usize;
+    let size = array_size(&class.name[1..], length);
+    match class.component_type.as_deref() {
+        Some(Type::Class(component)) if lengths.len() > 1 =>
+            size.saturating_add(arrays_size(component, &lengths[1..]).saturating_mul(length)),
+        _ => size
+    }
+}
+
+fn allocate_arrays(heap: &mut Heap, class: &Rc<Class>, lengths: &[i32]) -> Result<Reference, Throwable> {
+    let reference = heap.allocate(Object::new_array(class.clone(), lengths[0] as usize))?;
+    if let (Some(array), Some(Type::Class(component))) = (reference.object(), class.component_type.as_deref()) {
+        if lengths.len() > 1 {
+            for index in 0..lengths[0] as usize {
+                let element = Value::Reference(allocate_arrays(heap, component, &lengths[1..])?);
+                heap.write_barrier(array, &element);
+                array.array().store(index, element);
+            }
+        }
+    }
+    Ok(reference)
+}
+
+fn branch(pc: usize, offset: i32) -> usize {
+    (pc as i64 + offset as i64) as usize
+}
+
+fn unsupported(method: &Method, pc: usize, opcode: u8) -> Throwable {
+    Throwable::new(JAVA_LANG_INTERNAL_ERROR, format!("unsupported opcode 0x{:02x} at {} @{}", opcode, method_name(method), pc))
+}
+
+/// the value of a loadable constant (JVMS 5.1), strings and classes are resolved to their objects, strings are interned
+fn load_constant(vm: &mut Vm, thread: &mut JavaThread, class: &Rc<Class>, index: usize) -> Result<Value, Throwable> {
+    Ok(match &class.constant_pool[index] {
+        Constant::Integer(value) => Value::Int(*value),
+        Constant::Float(value) => Value::Float(*value),
+        Constant::Long(value) => Value::Long(*value),
+        Constant::Double(value) => Value::Double(*value),
+        Constant::String(chars) => Value::Reference(vm.intern(thread, chars)?),
+        Constant::Class(_) => {
+            let resolved = resolve_class(vm, class, index as u16)?;
+            Value::Reference(vm.method_area.mirror_of(&resolved))
+        }
+        _ => return Err(Throwable::new(JAVA_LANG_INTERNAL_ERROR, format!("unsupported constant at constant pool index {}", index)))
+    })
+}
+
+fn resolve_class(vm: &mut Vm, class: &Rc<Class>, index: u16) -> Result<Rc<Class>, Throwable> {
+    match &class.constant_pool[index as usize] {
+        Constant::Class(name) => {
+            let name = name.clone();
+            let resolved = vm.resolve_class(class.defining_loader, &name)?;
+            vm.check_class_access(class, &resolved)?;
+            Ok(resolved)
+        }
+        _ => panic!("class expected at constant pool index {}", index)
+    }
+}
+
+/// JVMS 5.4.3.2 field resolution, the field must be static for getstatic and putstatic only
+pub(crate) fn resolve_field(vm: &mut Vm, class: &Rc<Class>, index: u16, is_static: bool) -> Result<Rc<Field>, Throwable> {
+    let (class_name, name, descriptor) = match &class.constant_pool[index as usize] {
+        Constant::FieldRef { class, name, descriptor } => (class.clone(), name.clone(), descriptor.clone()),
+        _ => panic!("field reference expected at constant pool index {}", index)
+    };
+    let field_class = vm.resolve_class(class.defining_loader, &class_name)?;
+    let field = field_class.find_field(&name, &descriptor)
+        .ok_or_else(|| Throwable::new(JAVA_LANG_NO_SUCH_FIELD_ERROR, name.clone()))?;
+    vm.check_field_access(class, &field)?;
+    if field.is_static() != is_static {
+        return Err(Throwable::new(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, format!("Expected {} field {}.{}",
+            if is_static { "static" } else { "non-static" }, field.class().name.replace('/', "."), name)));
+    }
+    // JVMS 5.4.3.2: the type of the field is the same class for the referencing and the declaring loader
+    vm.class_loaders.add_descriptor_constraints(&vm.method_area, &descriptor, class.defining_loader, field.class().defining_loader)?;
+    Ok(field)
+}
+
+/// JVMS 5.4.3.3 and 5.4.3.4 method resolution: the class and its superclasses, then its superinterfaces.
+/// An interface has the public methods of Object. The class referenced is resolved along with the method.
+pub(crate) fn resolve_method(vm: &mut Vm, class: &Rc<Class>, index: u16) -> Result<ResolvedMethod, Throwable> {
+    let (class_name, name, descriptor, is_interface) = match &class.constant_pool[index as usize] {
+        Constant::MethodRef { class, name, descriptor } => (class.clone(), name.clone(), descriptor.clone(), false),
+        Constant::InterfaceMethodRef { class, name, descriptor } => (class.clone(), name.clone(), descriptor.clone(), true),
+        _ => panic!("method reference expected at constant pool index {}", index)
+    };
+    let method_class = vm.resolve_class(class.defining_loader, &class_name)?;
+    if method_class.is_interface() != is_interface {
+        return Err(Throwable::new(JAVA_LANG_INCOMPATIBLE_CLASS_CHANGE_ERROR, format!("Found {} {}, but {} was expected",
+            if is_interface { "class" } else { "interface" }, method_class.name.replace('/', "."), if is_interface { "interface" } else { "class" })));
+    }
+    let found = superclasses(&method_class).find_map(|class| class.find_method(&name, &descriptor))
+        .filter(|method| !is_interface || method.class().id == method_class.id || method.access_flags() & ACC_PUBLIC != 0)
+        .or_else(|| find_interface_method(&method_class, &name, &descriptor))
+        .ok_or_else(|| Throwable::new(JAVA_LANG_NO_SUCH_METHOD_ERROR, format!("'{}'", method_signature(&method_class, &name, &descriptor))))?;
+    vm.check_method_access(class, &found)?;
+    // JVMS 5.4.3.3: the types of the descriptor are the same classes for the referencing and the declaring loader
+    vm.class_loaders.add_descriptor_constraints(&vm.method_area, &descriptor, class.defining_loader, found.class().defining_loader)?;
+    Ok((method_class, found))
+}
+
+/// the method reference at `index` of `class` resolved the first time, as an invokedynamic call site is linked.
+/// A failed resolution is not recorded, it fails again the next time.
+fn resolved_method(vm: &mut Vm, class: &Rc<Class>, index: u16) -> Result<ResolvedMethod, Throwable> {
+    if let Some(resolved) = class.resolved_methods.borrow().get(&index) {
+        return Ok(resolved.clone());
+    }
+    let resolved = resolve_method(vm, class, index)?;
+    class.resolved_methods.borrow_mut().insert(index, resolved.clone());
+    Ok(resolved)
+}
+
+/// `void HelloWorld.main(java.lang.String[])` as in the message of NoSuchMethodError
+pub(crate) fn method_signature(class: &Class, name: &str, descriptor: &str) -> String {
+    let java_name = |descriptor: &str| {
+        let dimensions = descriptor.bytes().take_while(|&b| b == b'[').count();
+        let element = &descriptor[dimensions..];
+        let element = match crate::method_area::PRIMITIVE_TYPES.iter().find(|(_, primitive)| *primitive == element) {
+            Some((java_name, _)) => java_name.to_string(),
+            None => element[1..element.len() - 1].replace('/', ".")
+        };
+        element + &"[]".repeat(dimensions)
+    };
+    let (parameters, return_descriptor) = parse_method_descriptor(descriptor).unwrap_or((vec![], JVM_SIGNATURE_VOID));
+    let parameters: Vec<String> = parameters.into_iter().map(java_name).collect();
+    format!("{} {}.{}({})", java_name(return_descriptor), class.name.replace('/', "."), name, parameters.join(", "))
+}
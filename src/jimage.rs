@@ -0,0 +1,726 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use crate::inflate;
+
+// JDK 9+ runtime image `lib/modules`, see jdk.internal.jimage.BasicImageReader
+
+const IMAGE_MAGIC: u32 = 0xCAFEDADA;
+const IMAGE_MAJOR_VERSION: u16 = 1;
+const IMAGE_HEADER_SIZE: usize = 7 * 4;
+const HASH_MULTIPLIER: i32 = 0x01000193;
+
+const ATTRIBUTE_END: usize = 0;
+const ATTRIBUTE_MODULE: usize = 1;
+const ATTRIBUTE_PARENT: usize = 2;
+const ATTRIBUTE_BASE: usize = 3;
+const ATTRIBUTE_EXTENSION: usize = 4;
+const ATTRIBUTE_OFFSET: usize = 5;
+const ATTRIBUTE_COMPRESSED: usize = 6;
+const ATTRIBUTE_UNCOMPRESSED: usize = 7;
+const ATTRIBUTE_COUNT: usize = 8;
+
+const COMPRESSED_HEADER_MAGIC: u32 = 0xCAFEFAFA;
+const COMPRESSED_HEADER_SIZE: usize = 29;
+const DECOMPRESSOR_ZIP: &[u8] = b"zip";
+const DECOMPRESSOR_STRING_SHARING: &[u8] = b"compact-cp";
+
+// constant pool tags written by the string sharing plugin
+const EXTERNALIZED_STRING: u8 = 23;
+const EXTERNALIZED_STRING_DESCRIPTOR: u8 = 25;
+
+/*
+header {
+    u4 magic;
+    u2 minor_version;
+    u2 major_version;
+    u4 flags;
+    u4 resource_count;
+    u4 table_length;
+    u4 locations_size;
+    u4 strings_size;
+}
+index {
+    header  header;
+    s4      redirect[table_length];
+    u4      offsets[table_length];
+    u1      locations[locations_size];
+    u1      strings[strings_size];
+}
+resources follow the index
+*/
+pub struct JImage {
+    pub(crate) path: PathBuf,
+    file: RefCell<File>,
+    big_endian: bool,
+    #[allow(dead_code)]
+    resource_count: u32,
+    redirect: Vec<i32>,
+    offsets: Vec<u32>,
+    locations: Vec<u8>,
+    strings: Vec<u8>,
+    index_size: u64,
+    packages: RefCell<HashMap<String, Option<String>>>
+}
+
+/// attributes of a resource location, strings are offsets into the strings table
+struct ImageLocation {
+    attributes: [u64; ATTRIBUTE_COUNT]
+}
+
+fn invalid(path: &Path, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), message))
+}
+
+/// hash of a name's modified UTF-8 bytes, `seed` is the redirect value or `HASH_MULTIPLIER` for the first probe
+pub fn hash_code(name: &[u8], seed: i32) -> i32 {
+    let mut hash = seed;
+    for &byte in name {
+        hash = hash.wrapping_mul(HASH_MULTIPLIER) ^ byte as i32;
+    }
+    hash & 0x7fffffff
+}
+
+impl JImage {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut header = [0u8; IMAGE_HEADER_SIZE];
+        file.read_exact(&mut header)?;
+        let big_endian = match u32::from_le_bytes([header[0], header[1], header[2], header[3]]) {
+            IMAGE_MAGIC => false,
+            magic if magic.swap_bytes() == IMAGE_MAGIC => true,
+            _ => return Err(invalid(path, "not a jimage file"))
+        };
+        let u4 = |pos: usize| {
+            let bytes = [header[pos], header[pos + 1], header[pos + 2], header[pos + 3]];
+            if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+        };
+        let version = u4(4);
+        if (version >> 16) as u16 != IMAGE_MAJOR_VERSION {
+            return Err(invalid(path, &format!("unsupported jimage version {}.{}", version >> 16, version & 0xffff)));
+        }
+        let resource_count = u4(12);
+        let table_length = u4(16) as usize;
+        let locations_size = u4(20) as usize;
+        let strings_size = u4(24) as usize;
+
+        let mut index = vec![0u8; table_length * 8 + locations_size + strings_size];
+        file.read_exact(&mut index)?;
+        let word = |pos: usize| {
+            let bytes = [index[pos], index[pos + 1], index[pos + 2], index[pos + 3]];
+            if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+        };
+        let redirect = (0..table_length).map(|i| word(i * 4) as i32).collect();
+        let offsets = (0..table_length).map(|i| word((table_length + i) * 4)).collect();
+        let locations_start = table_length * 8;
+        let strings_start = locations_start + locations_size;
+        let locations = index[locations_start..strings_start].to_vec();
+        let strings = index[strings_start..].to_vec();
+        Ok(JImage {
+            path: path.to_path_buf(),
+            file: RefCell::new(file),
+            big_endian,
+            resource_count,
+            redirect,
+            offsets,
+            locations,
+            strings,
+            index_size: (IMAGE_HEADER_SIZE + index.len()) as u64,
+            packages: RefCell::new(HashMap::new())
+        })
+    }
+
+    /// NUL terminated modified UTF-8 string in the strings table
+    fn string(&self, offset: usize) -> &[u8] {
+        if offset >= self.strings.len() {
+            return &[];
+        }
+        let end = self.strings[offset..].iter().position(|&byte| byte == 0).map_or(self.strings.len(), |i| offset + i);
+        &self.strings[offset..end]
+    }
+
+    fn location(&self, offset: usize) -> ImageLocation {
+        let mut attributes = [0u64; ATTRIBUTE_COUNT];
+        let mut pos = offset;
+        while pos < self.locations.len() {
+            let byte = self.locations[pos] as usize;
+            pos += 1;
+            let kind = byte >> 3;
+            if kind == ATTRIBUTE_END {
+                break;
+            }
+            let length = (byte & 0x7) + 1;
+            let mut value = 0u64;
+            for &b in self.locations.iter().skip(pos).take(length) {
+                value = value << 8 | b as u64;
+            }
+            attributes[kind] = value;
+            pos += length;
+        }
+        ImageLocation { attributes }
+    }
+
+    /// `/module/parent/base.extension`
+    fn full_name(&self, location: &ImageLocation) -> Vec<u8> {
+        let mut name = vec![];
+        let module = location.attributes[ATTRIBUTE_MODULE] as usize;
+        if module != 0 {
+            name.push(b'/');
+            name.extend_from_slice(self.string(module));
+            name.push(b'/');
+        }
+        let parent = location.attributes[ATTRIBUTE_PARENT] as usize;
+        if parent != 0 {
+            name.extend_from_slice(self.string(parent));
+            name.push(b'/');
+        }
+        name.extend_from_slice(self.string(location.attributes[ATTRIBUTE_BASE] as usize));
+        let extension = location.attributes[ATTRIBUTE_EXTENSION] as usize;
+        if extension != 0 {
+            name.push(b'.');
+            name.extend_from_slice(self.string(extension));
+        }
+        name
+    }
+
+    /// look up a location by its full name through the perfect hash redirect table
+    fn find_location(&self, name: &str) -> Option<ImageLocation> {
+        let length = self.redirect.len() as i32;
+        if length == 0 {
+            return None;
+        }
+        let name = name.as_bytes();
+        let index = hash_code(name, HASH_MULTIPLIER) % length;
+        let index = match self.redirect[index as usize] {
+            0 => return None,
+            value if value < 0 => -1 - value,
+            seed => hash_code(name, seed) % length
+        };
+        let location = self.location(*self.offsets.get(index as usize)? as usize);
+        if self.full_name(&location) == name {
+            Some(location)
+        } else {
+            None
+        }
+    }
+
+    /// read a resource by its full name, e.g. `/java.base/java/lang/Object.class`
+    pub fn read(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        self.find_location(name).map(|location| self.read_location(&location))
+    }
+
+    fn read_location(&self, location: &ImageLocation) -> io::Result<Vec<u8>> {
+        let offset = location.attributes[ATTRIBUTE_OFFSET];
+        let compressed_size = location.attributes[ATTRIBUTE_COMPRESSED] as usize;
+        let uncompressed_size = location.attributes[ATTRIBUTE_UNCOMPRESSED] as usize;
+        let size = if compressed_size != 0 { compressed_size } else { uncompressed_size };
+        let mut bytes = vec![0u8; size];
+        {
+            let mut file = self.file.borrow_mut();
+            file.seek(SeekFrom::Start(self.index_size + offset))?;
+            file.read_exact(&mut bytes)?;
+        }
+        if compressed_size == 0 {
+            return Ok(bytes);
+        }
+        self.decompress(bytes).map_err(|message| invalid(&self.path, &message))
+    }
+
+    /*
+    compressed resource header {
+        u4 magic;
+        u8 compressed_size;
+        u8 uncompressed_size;
+        u4 decompressor_name_offset;
+        u4 decompressor_config_offset;
+        u1 is_terminal;
+    }
+    a resource may be compressed several times, each with a header in front
+    */
+    fn decompress(&self, mut bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        while bytes.len() >= COMPRESSED_HEADER_SIZE && self.u4(&bytes, 0) == COMPRESSED_HEADER_MAGIC {
+            let uncompressed_size = self.u8(&bytes, 12) as usize;
+            let decompressor = self.string(self.u4(&bytes, 20) as usize);
+            let content = &bytes[COMPRESSED_HEADER_SIZE..];
+            bytes = match decompressor {
+                DECOMPRESSOR_ZIP => inflate::inflate_zlib(content, uncompressed_size)?,
+                DECOMPRESSOR_STRING_SHARING => self.expand_shared_strings(content)?,
+                _ => return Err(format!("unknown decompressor {}", String::from_utf8_lossy(decompressor)))
+            };
+        }
+        Ok(bytes)
+    }
+
+    fn u4(&self, bytes: &[u8], pos: usize) -> u32 {
+        let word = [bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]];
+        if self.big_endian { u32::from_be_bytes(word) } else { u32::from_le_bytes(word) }
+    }
+
+    fn u8(&self, bytes: &[u8], pos: usize) -> u64 {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&bytes[pos..pos + 8]);
+        if self.big_endian { u64::from_be_bytes(word) } else { u64::from_le_bytes(word) }
+    }
+
+    /// restore the constant pool of a class whose utf8 constants were moved into the strings table,
+    /// see jdk.internal.jimage.decompressor.StringSharingDecompressor
+    fn expand_shared_strings(&self, content: &[u8]) -> Result<Vec<u8>, String> {
+        let mut reader = SharedStringReader { bytes: content, pos: 0 };
+        let mut out = Vec::with_capacity(content.len() * 2);
+        out.extend_from_slice(reader.bytes(8)?); // magic, minor and major version
+        let count = reader.u2()?;
+        out.extend_from_slice(&count.to_be_bytes());
+        let mut i = 1;
+        while i < count {
+            let tag = reader.u1()?;
+            match tag {
+                EXTERNALIZED_STRING => {
+                    let string = self.string(reader.compressed_int()? as usize);
+                    write_utf8_constant(&mut out, string);
+                }
+                EXTERNALIZED_STRING_DESCRIPTOR => {
+                    let descriptor = self.reconstruct_descriptor(&mut reader)?;
+                    write_utf8_constant(&mut out, &descriptor);
+                }
+                _ => {
+                    let size = match tag {
+                        1 => 2 + u16::from_be_bytes([reader.peek(0)?, reader.peek(1)?]) as usize, // utf8
+                        3 | 4 => 4,                      // integer, float
+                        5 | 6 => { i += 1; 8 }           // long and double take two entries
+                        7 | 8 | 16 | 19 | 20 => 2,       // class, string, method type, module, package
+                        9 | 10 | 11 | 12 | 17 | 18 => 4, // refs, name and type, dynamic, invoke dynamic
+                        15 => 3,                         // method handle
+                        _ => return Err(format!("unknown constant pool tag {}", tag))
+                    };
+                    out.push(tag);
+                    out.extend_from_slice(reader.bytes(size)?);
+                }
+            }
+            i += 1;
+        }
+        out.extend_from_slice(&content[reader.pos..]);
+        Ok(out)
+    }
+
+    /// a descriptor is stored as its shape with every `L` followed by the package and simple class name
+    fn reconstruct_descriptor(&self, reader: &mut SharedStringReader) -> Result<Vec<u8>, String> {
+        let shape = self.string(reader.compressed_int()? as usize);
+        let indexes_length = reader.compressed_int()? as usize;
+        let mut indexes = SharedStringReader { bytes: reader.bytes(indexes_length)?, pos: 0 };
+        let mut descriptor = Vec::with_capacity(shape.len() * 2);
+        for &byte in shape {
+            descriptor.push(byte);
+            if byte == b'L' {
+                let package = self.string(indexes.compressed_int()? as usize);
+                if !package.is_empty() {
+                    descriptor.extend_from_slice(package);
+                    descriptor.push(b'/');
+                }
+                descriptor.extend_from_slice(self.string(indexes.compressed_int()? as usize));
+            }
+        }
+        Ok(descriptor)
+    }
+
+    /// the module which a package belongs to, from the `/packages/<package>` entries
+    fn module_of(&self, package: &str) -> Option<String> {
+        if let Some(module) = self.packages.borrow().get(package) {
+            return module.clone();
+        }
+        let module = self.read(&["/packages/", package].concat())
+            .and_then(|content| content.ok())
+            .and_then(|content| {
+                // pairs of (is_empty, module name offset)
+                content.chunks_exact(8)
+                    .find(|pair| self.u4(pair, 0) == 0)
+                    .or_else(|| content.chunks_exact(8).next())
+                    .map(|pair| String::from_utf8_lossy(self.string(self.u4(pair, 4) as usize)).into_owned())
+            });
+        self.packages.borrow_mut().insert(package.to_string(), module.clone());
+        module
+    }
+
+    /// the module containing a resource named in a class path way, e.g. `java/lang/Object.class` is in `java.base`
+    pub(crate) fn module_of_resource(&self, resource_name: &str) -> Option<String> {
+        let package = resource_name[..resource_name.rfind('/')?].replace('/', ".");
+        self.module_of(&package)
+    }
+
+    /// find a resource by its name in a class path way, e.g. `java/lang/Object.class`
+    #[allow(dead_code)]
+    pub fn find_resource(&self, resource_name: &str) -> Option<io::Result<Vec<u8>>> {
+        let module = self.module_of_resource(resource_name)?;
+        self.read(&["/", &module, "/", resource_name].concat())
+    }
+}
+
+fn write_utf8_constant(out: &mut Vec<u8>, string: &[u8]) {
+    out.push(1); // utf8 tag
+    out.extend_from_slice(&(string.len() as u16).to_be_bytes());
+    out.extend_from_slice(string);
+}
+
+struct SharedStringReader<'a> {
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl<'a> SharedStringReader<'a> {
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], String> {
+        if self.pos + length > self.bytes.len() {
+            return Err("truncated string shared class".to_string());
+        }
+        let bytes = &self.bytes[self.pos..self.pos + length];
+        self.pos += length;
+        Ok(bytes)
+    }
+
+    fn peek(&self, i: usize) -> Result<u8, String> {
+        self.bytes.get(self.pos + i).copied().ok_or_else(|| "truncated string shared class".to_string())
+    }
+
+    fn u1(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u2(&mut self) -> Result<u16, String> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// jdk.internal.jimage.decompressor.CompressIndexes: if the high bit of the first byte is set,
+    /// the next 2 bits are the total length and the low 5 bits are the highest value bits, otherwise it is a 4-byte int
+    fn compressed_int(&mut self) -> Result<u32, String> {
+        let header = self.u1()?;
+        let (length, mut value) = if header & 0x80 != 0 {
+            (((header >> 5) & 0x3) as usize, (header & 0x1f) as u32)
+        } else {
+            (4, header as u32)
+        };
+        for _ in 1..length {
+            value = value << 8 | self.u1()? as u32;
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// writes a jimage in the layout of jlink's ImageFileCreator
+    struct ImageWriter {
+        big_endian: bool,
+        strings: Vec<u8>,
+        string_offsets: HashMap<Vec<u8>, u32>,
+        resources: Vec<(String, Vec<u8>, usize, usize)>
+    }
+
+    impl ImageWriter {
+        fn new(big_endian: bool) -> Self {
+            let mut writer = ImageWriter { big_endian, strings: vec![], string_offsets: HashMap::new(), resources: vec![] };
+            writer.string(b""); // offset 0 is the empty string
+            writer
+        }
+
+        fn string(&mut self, string: &[u8]) -> u32 {
+            if let Some(&offset) = self.string_offsets.get(string) {
+                return offset;
+            }
+            let offset = self.strings.len() as u32;
+            self.strings.extend_from_slice(string);
+            self.strings.push(0);
+            self.string_offsets.insert(string.to_vec(), offset);
+            offset
+        }
+
+        fn u4(&self, value: u32) -> [u8; 4] {
+            if self.big_endian { value.to_be_bytes() } else { value.to_le_bytes() }
+        }
+
+        fn add(&mut self, name: &str, content: Vec<u8>) {
+            let size = content.len();
+            self.resources.push((name.to_string(), content, 0, size));
+        }
+
+        fn add_compressed(&mut self, name: &str, decompressor: &[u8], content: Vec<u8>, uncompressed_size: usize) {
+            let name_offset = self.string(decompressor);
+            let mut compressed = vec![];
+            compressed.extend_from_slice(&self.u4(COMPRESSED_HEADER_MAGIC));
+            let (compressed_size, uncompressed) = if self.big_endian {
+                ((content.len() as u64).to_be_bytes(), (uncompressed_size as u64).to_be_bytes())
+            } else {
+                ((content.len() as u64).to_le_bytes(), (uncompressed_size as u64).to_le_bytes())
+            };
+            compressed.extend_from_slice(&compressed_size);
+            compressed.extend_from_slice(&uncompressed);
+            compressed.extend_from_slice(&self.u4(name_offset));
+            compressed.extend_from_slice(&self.u4(0));
+            compressed.push(1);
+            compressed.extend_from_slice(&content);
+            let compressed_size = compressed.len();
+            self.resources.push((name.to_string(), compressed, compressed_size, uncompressed_size));
+        }
+
+        fn location(&mut self, name: &str, offset: usize, compressed: usize, uncompressed: usize) -> Vec<u8> {
+            let mut parts = name[1..].splitn(2, '/');
+            let module = parts.next().unwrap().to_string();
+            let path = parts.next().unwrap_or("").to_string();
+            let (parent, file) = match path.rfind('/') {
+                Some(i) => (path[..i].to_string(), path[i + 1..].to_string()),
+                None => (String::new(), path.clone())
+            };
+            let (base, extension) = match file.rfind('.') {
+                Some(i) => (file[..i].to_string(), file[i + 1..].to_string()),
+                None => (file.clone(), String::new())
+            };
+            let mut attributes = [0u64; ATTRIBUTE_COUNT];
+            attributes[ATTRIBUTE_MODULE] = self.string(module.as_bytes()) as u64;
+            if !parent.is_empty() {
+                attributes[ATTRIBUTE_PARENT] = self.string(parent.as_bytes()) as u64;
+            }
+            attributes[ATTRIBUTE_BASE] = self.string(base.as_bytes()) as u64;
+            if !extension.is_empty() {
+                attributes[ATTRIBUTE_EXTENSION] = self.string(extension.as_bytes()) as u64;
+            }
+            attributes[ATTRIBUTE_OFFSET] = offset as u64;
+            attributes[ATTRIBUTE_COMPRESSED] = compressed as u64;
+            attributes[ATTRIBUTE_UNCOMPRESSED] = uncompressed as u64;
+            let mut bytes = vec![];
+            for (kind, &value) in attributes.iter().enumerate().skip(1) {
+                if value == 0 {
+                    continue;
+                }
+                let length = (8 - value.leading_zeros() as usize / 8).max(1);
+                bytes.push((kind << 3 | (length - 1)) as u8);
+                bytes.extend_from_slice(&value.to_be_bytes()[8 - length..]);
+            }
+            bytes.push(ATTRIBUTE_END as u8);
+            bytes
+        }
+
+        /// a perfect hash: buckets of colliding names get a seed redirecting them to free slots,
+        /// single names are redirected to a free slot directly
+        fn redirect(names: &[String]) -> (Vec<i32>, Vec<usize>) {
+            let length = names.len() as i32;
+            let mut buckets: Vec<Vec<usize>> = vec![vec![]; names.len()];
+            for (i, name) in names.iter().enumerate() {
+                buckets[(hash_code(name.as_bytes(), HASH_MULTIPLIER) % length) as usize].push(i);
+            }
+            let mut order: Vec<usize> = (0..names.len()).collect();
+            order.sort_by_key(|&bucket| std::cmp::Reverse(buckets[bucket].len()));
+            let mut redirect = vec![0i32; names.len()];
+            let mut slots: Vec<Option<usize>> = vec![None; names.len()];
+            for bucket in order {
+                let members = &buckets[bucket];
+                if members.len() > 1 {
+                    let mut seed = 1;
+                    loop {
+                        let targets: Vec<usize> = members.iter()
+                            .map(|&i| (hash_code(names[i].as_bytes(), seed) % length) as usize)
+                            .collect();
+                        let mut distinct = targets.clone();
+                        distinct.sort();
+                        distinct.dedup();
+                        if distinct.len() == targets.len() && targets.iter().all(|&slot| slots[slot].is_none()) {
+                            for (&i, &slot) in members.iter().zip(targets.iter()) {
+                                slots[slot] = Some(i);
+                            }
+                            redirect[bucket] = seed;
+                            break;
+                        }
+                        seed += 1;
+                    }
+                } else if members.len() == 1 {
+                    let slot = slots.iter().position(|slot| slot.is_none()).unwrap();
+                    slots[slot] = Some(members[0]);
+                    redirect[bucket] = -1 - slot as i32;
+                }
+            }
+            (redirect, slots.into_iter().map(|slot| slot.unwrap()).collect())
+        }
+
+        fn write(mut self, path: &Path) {
+            let resources = std::mem::take(&mut self.resources);
+            let names: Vec<String> = resources.iter().map(|(name, _, _, _)| name.clone()).collect();
+            let mut locations = vec![];
+            let mut location_offsets = vec![];
+            let mut content = vec![];
+            for (name, bytes, compressed, uncompressed) in &resources {
+                location_offsets.push(locations.len() as u32);
+                let location = self.location(name, content.len(), *compressed, *uncompressed);
+                locations.extend_from_slice(&location);
+                content.extend_from_slice(bytes);
+            }
+            let (redirect, slots) = ImageWriter::redirect(&names);
+            let mut image = vec![];
+            image.extend_from_slice(&self.u4(IMAGE_MAGIC));
+            image.extend_from_slice(&self.u4((IMAGE_MAJOR_VERSION as u32) << 16));
+            image.extend_from_slice(&self.u4(0));
+            for value in [names.len(), names.len(), locations.len(), self.strings.len()] {
+                image.extend_from_slice(&self.u4(value as u32));
+            }
+            for value in redirect {
+                image.extend_from_slice(&self.u4(value as u32));
+            }
+            for resource in slots {
+                image.extend_from_slice(&self.u4(location_offsets[resource]));
+            }
+            image.extend_from_slice(&locations);
+            image.extend_from_slice(&self.strings);
+            image.extend_from_slice(&content);
+            fs::write(path, image).unwrap();
+        }
+    }
+
+    /// a zlib stream of a single stored deflate block
+    fn zlib_stored(data: &[u8]) -> Vec<u8> {
+        let mut zlib = vec![0x78, 0x01, 0x01];
+        zlib.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        zlib.extend_from_slice(&(!(data.len() as u16)).to_le_bytes());
+        zlib.extend_from_slice(data);
+        let (mut a, mut b) = (1u32, 0u32);
+        for &byte in data {
+            a = (a + byte as u32) % 65521;
+            b = (b + a) % 65521;
+        }
+        zlib.extend_from_slice(&(b << 16 | a).to_be_bytes());
+        zlib
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("jara-{}-{}", std::process::id(), name))
+    }
+
+    fn packages(writer: &mut ImageWriter, module: &str) -> Vec<u8> {
+        let offset = writer.string(module.as_bytes());
+        [writer.u4(0), writer.u4(offset)].concat()
+    }
+
+    fn write_fixture(big_endian: bool, path: &Path) {
+        let mut writer = ImageWriter::new(big_endian);
+        writer.add("/java.base/java/lang/Object.class", b"\xca\xfe\xba\xbeObject".to_vec());
+        writer.add("/java.base/java/lang/uniName.dat", b"names".to_vec());
+        writer.add("/java.base/module-info.class", b"module".to_vec());
+        writer.add("/java.logging/java/util/logging/Logger.class", b"\xca\xfe\xba\xbeLogger".to_vec());
+        writer.add_compressed("/java.base/java/lang/String.class", DECOMPRESSOR_ZIP,
+                              zlib_stored(b"\xca\xfe\xba\xbeString"), 10);
+        let content = packages(&mut writer, "java.base");
+        writer.add("/packages/java.lang", content);
+        let content = packages(&mut writer, "java.logging");
+        writer.add("/packages/java.util.logging", content);
+        writer.write(path);
+    }
+
+    #[test]
+    fn read_resource_by_full_name() {
+        let path = temp_file("full-name.jimage");
+        write_fixture(false, &path);
+        let jimage = JImage::open(&path).unwrap();
+        assert_eq!(jimage.resource_count, 7);
+        assert_eq!(jimage.read("/java.base/java/lang/Object.class").unwrap().unwrap(), b"\xca\xfe\xba\xbeObject");
+        assert_eq!(jimage.read("/java.base/module-info.class").unwrap().unwrap(), b"module");
+        assert!(jimage.read("/java.base/java/lang/Missing.class").is_none());
+        assert!(jimage.read("/java.logging/java/lang/Object.class").is_none());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn find_resource_through_package_module() {
+        let path = temp_file("packages.jimage");
+        write_fixture(false, &path);
+        let jimage = JImage::open(&path).unwrap();
+        assert_eq!(jimage.find_resource("java/lang/Object.class").unwrap().unwrap(), b"\xca\xfe\xba\xbeObject");
+        assert_eq!(jimage.find_resource("java/lang/uniName.dat").unwrap().unwrap(), b"names");
+        assert_eq!(jimage.find_resource("java/util/logging/Logger.class").unwrap().unwrap(), b"\xca\xfe\xba\xbeLogger");
+        assert!(jimage.find_resource("java/util/Missing.class").is_none());
+        assert!(jimage.find_resource("Default.class").is_none());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn read_big_endian_image() {
+        let path = temp_file("big-endian.jimage");
+        write_fixture(true, &path);
+        let jimage = JImage::open(&path).unwrap();
+        assert_eq!(jimage.find_resource("java/lang/Object.class").unwrap().unwrap(), b"\xca\xfe\xba\xbeObject");
+        assert_eq!(jimage.find_resource("java/lang/String.class").unwrap().unwrap(), b"\xca\xfe\xba\xbeString");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn read_zip_compressed_resource() {
+        let path = temp_file("zip.jimage");
+        write_fixture(false, &path);
+        let jimage = JImage::open(&path).unwrap();
+        assert_eq!(jimage.find_resource("java/lang/String.class").unwrap().unwrap(), b"\xca\xfe\xba\xbeString");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn read_string_shared_then_zip_compressed_class() {
+        let mut writer = ImageWriter::new(false);
+        let foo = writer.string(b"foo");
+        let shape = writer.string(b"(L;I)V");
+        let package = writer.string(b"java/lang");
+        let class = writer.string(b"String");
+        let mut shared = b"\xca\xfe\xba\xbe\x00\x00\x00\x3d".to_vec();
+        shared.extend_from_slice(&6u16.to_be_bytes());
+        shared.push(EXTERNALIZED_STRING);
+        shared.extend_from_slice(&foo.to_be_bytes()); // 4-byte form
+        shared.push(EXTERNALIZED_STRING_DESCRIPTOR);
+        shared.extend_from_slice(&shape.to_be_bytes());
+        let mut indexes = vec![0x80 | 2 << 5 | (package >> 8) as u8, package as u8]; // 2-byte form
+        indexes.extend_from_slice(&class.to_be_bytes());
+        shared.push(0x80 | 1 << 5 | indexes.len() as u8); // 1-byte form
+        shared.extend_from_slice(&indexes);
+        shared.extend_from_slice(&[5, 0, 0, 0, 0, 0, 0, 0, 42]); // long takes two entries
+        shared.extend_from_slice(&[1, 0, 3, b'b', b'a', b'r']);
+        shared.extend_from_slice(b"rest of class");
+
+        let mut expected = b"\xca\xfe\xba\xbe\x00\x00\x00\x3d".to_vec();
+        expected.extend_from_slice(&6u16.to_be_bytes());
+        expected.extend_from_slice(&[1, 0, 3, b'f', b'o', b'o']);
+        expected.extend_from_slice(&[1, 0, 22]);
+        expected.extend_from_slice(b"(Ljava/lang/String;I)V");
+        expected.extend_from_slice(&[5, 0, 0, 0, 0, 0, 0, 0, 42]);
+        expected.extend_from_slice(&[1, 0, 3, b'b', b'a', b'r']);
+        expected.extend_from_slice(b"rest of class");
+
+        // compact-cp is applied first, then zip
+        let name_offset = writer.string(DECOMPRESSOR_STRING_SHARING);
+        let mut inner = vec![];
+        inner.extend_from_slice(&writer.u4(COMPRESSED_HEADER_MAGIC));
+        inner.extend_from_slice(&(shared.len() as u64).to_le_bytes());
+        inner.extend_from_slice(&(expected.len() as u64).to_le_bytes());
+        inner.extend_from_slice(&writer.u4(name_offset));
+        inner.extend_from_slice(&writer.u4(0));
+        inner.push(0);
+        inner.extend_from_slice(&shared);
+        let inner_size = inner.len();
+        writer.add_compressed("/java.base/java/lang/Shared.class", DECOMPRESSOR_ZIP, zlib_stored(&inner), inner_size);
+        let content = packages(&mut writer, "java.base");
+        writer.add("/packages/java.lang", content);
+
+        let path = temp_file("string-sharing.jimage");
+        writer.write(&path);
+        let jimage = JImage::open(&path).unwrap();
+        assert_eq!(jimage.find_resource("java/lang/Shared.class").unwrap().unwrap(), expected);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn compressed_int() {
+        let bytes = [0x80 | 1 << 5 | 7, 0x80 | 2 << 5 | 0x01, 0x02, 0x80 | 3 << 5, 0x01, 0x02, 0x00, 0x01, 0x02, 0x03];
+        let mut reader = SharedStringReader { bytes: &bytes, pos: 0 };
+        assert_eq!(reader.compressed_int().unwrap(), 7);
+        assert_eq!(reader.compressed_int().unwrap(), 0x0102);
+        assert_eq!(reader.compressed_int().unwrap(), 0x0102);
+        assert_eq!(reader.compressed_int().unwrap(), 0x00010203);
+        assert!(reader.compressed_int().is_err());
+    }
+}
@@ -0,0 +1,349 @@
+use std::rc::Rc;
+use crate::class_loader::{ClassLoaderId, BOOTSTRAP_LOADER, CLASS_FILE_MAGIC};
+use crate::constants::*;
+use crate::dispatch::{select_interface, select_virtual};
+use crate::exception::Throwable;
+use crate::interpreter::{self, resolve_method};
+use crate::object::{instance_size, Object};
+use crate::thread::JavaThread;
+use crate::types::{Class, Constant, Field, Method};
+use crate::values::Value;
+use crate::vm::Vm;
+
+pub const JAVA_LANG_INVOKE_LAMBDA_METAFACTORY: &str = "java/lang/invoke/LambdaMetafactory";
+const JAVA_LANG_INVOKE_LAMBDA_CONVERSION_EXCEPTION: &str = "java/lang/invoke/LambdaConversionException";
+// the static field of the class of a lambda capturing nothing
+const LAMBDA_INSTANCE: &str = "LAMBDA_INSTANCE$";
+
+// the flags of LambdaMetafactory.altMetafactory
+const FLAG_SERIALIZABLE: i32 = 1 << 0;
+const FLAG_MARKERS: i32 = 1 << 1;
+const FLAG_BRIDGES: i32 = 1 << 2;
+
+// the primitive types and the classes boxing them
+const WRAPPERS: [(&str, &str); 8] = [
+    (JVM_SIGNATURE_BOOLEAN, "java/lang/Boolean"),
+    (JVM_SIGNATURE_BYTE, "java/lang/Byte"),
+    (JVM_SIGNATURE_CHAR, "java/lang/Character"),
+    (JVM_SIGNATURE_SHORT, "java/lang/Short"),
+    (JVM_SIGNATURE_INT, "java/lang/Integer"),
+    (JVM_SIGNATURE_LONG, "java/lang/Long"),
+    (JVM_SIGNATURE_FLOAT, "java/lang/Float"),
+    (JVM_SIGNATURE_DOUBLE, "java/lang/Double")
+];
+
+/// the implementation method the interface methods of a class spun for a lambda forward to
+pub struct Lambda {
+    // the kind of the method handle of the implementation method
+    reference_kind: u8,
+    implementation: Rc<Method>
+}
+
+/// LambdaMetafactory.metafactory and altMetafactory: spin a class implementing the functional interface returned by
+/// the invokedynamic `descriptor`, its `name` method and the bridges forward to the implementation method. The arguments
+/// of the call site are captured by the fields of the class, in the order of the parameters of `descriptor`.
+/// A class capturing nothing has a static field instead, holding its only instance.
+pub fn spin(vm: &mut Vm, caller: &Rc<Class>, name: &str, descriptor: &str, arguments: &[u16]) -> Result<Rc<Class>, Throwable> {
+    let constant = |index: usize| arguments.get(index).map(|&argument| &caller.constant_pool[argument as usize]);
+    let (interface_method_type, (reference_kind, reference_index)) = match (constant(0), constant(1)) {
+        (Some(Constant::MethodType(method_type)), Some(Constant::MethodHandle { reference_kind, reference_index })) =>
+            (method_type.clone(), (*reference_kind, *reference_index)),
+        _ => return Err(conversion_error("Invalid static arguments of the lambda metafactory".to_string()))
+    };
+    let mut method_types = vec![interface_method_type];
+    let mut interface_names = vec![];
+    // altMetafactory passes its flags after the dynamic method type, then the markers and the bridges they announce
+    if let Some(Constant::Integer(flags)) = constant(3) {
+        let mut index = 4;
+        let mut counted = |flag: i32| {
+            let count = match constant(index) {
+                Some(Constant::Integer(count)) if flags & flag != 0 => *count as usize,
+                _ => return index..index
+            };
+            index += 1 + count;
+            index - count..index
+        };
+        let markers = counted(FLAG_MARKERS);
+        let bridges = counted(FLAG_BRIDGES);
+        if flags & FLAG_SERIALIZABLE != 0 {
+            interface_names.push(JAVA_IO_SERIALIZABLE.to_string());
+        }
+        for index in markers {
+            match constant(index) {
+                Some(Constant::Class(marker)) if !interface_names.contains(marker) => interface_names.push(marker.clone()),
+                _ => {}
+            }
+        }
+        for index in bridges {
+            match constant(index) {
+                Some(Constant::MethodType(bridge)) if !method_types.contains(bridge) => method_types.push(bridge.clone()),
+                _ => {}
+            }
+        }
+    }
+
+    let (captured, interface) = crate::types::parse_method_descriptor(descriptor)
+        .map(|(parameters, return_descriptor)| (parameters.into_iter().map(String::from).collect::<Vec<_>>(), return_descriptor))
+        .filter(|(_, return_descriptor)| return_descriptor.starts_with(JVM_SIGNATURE_CLASS))
+        .ok_or_else(|| conversion_error(format!("Invalid call site type {}", descriptor)))?;
+    let interface = vm.resolve_class(caller.defining_loader, &interface[1..interface.len() - 1])?;
+    if !interface.is_interface() {
+        return Err(conversion_error(format!("{} is not an interface", interface.name.replace('/', "."))));
+    }
+    interface_names.retain(|name| *name != interface.name);
+    interface_names.insert(0, interface.name.clone());
+    let implementation = match reference_kind {
+        JVM_REF_INVOKE_VIRTUAL | JVM_REF_INVOKE_STATIC | JVM_REF_INVOKE_SPECIAL | JVM_REF_NEW_INVOKE_SPECIAL | JVM_REF_INVOKE_INTERFACE =>
+            resolve_method(vm, caller, reference_index)?.1,
+        _ => return Err(conversion_error(format!("Unsupported MethodHandle kind: {}", reference_kind)))
+    };
+
+    // a hidden class of the caller would not be found by name, the lambda classes are numbered instead
+    let class_name = format!("{}$$Lambda${}", caller.name, vm.lambdas.len() + 1);
+    let methods: Vec<(&str, &str, u16)> = method_types.iter().enumerate()
+        .map(|(index, method_type)| (name, method_type.as_str(), ACC_PUBLIC | ACC_NATIVE | if index > 0 { ACC_BRIDGE | ACC_SYNTHETIC } else { 0 }))
+        .collect();
+    let mut fields: Vec<(String, String, u16)> = captured.iter().enumerate()
+        .map(|(index, descriptor)| (format!("arg${}", index + 1), descriptor.clone(), ACC_PRIVATE | ACC_FINAL))
+        .collect();
+    if fields.is_empty() {
+        fields.push((LAMBDA_INSTANCE.to_string(), format!("L{};", class_name), ACC_PRIVATE | ACC_STATIC | ACC_FINAL));
+    }
+    let bytes = class_bytes(&class_name, &interface_names, &fields, &methods);
+    let class = vm.class_loaders.define_class(&mut vm.method_area, caller.defining_loader, &class_name, bytes)?;
+    vm.link_class(&class)?;
+    vm.lambdas.insert(class.id, Rc::new(Lambda { reference_kind, implementation }));
+    Ok(class)
+}
+
+/// a new instance of the class spun for a lambda, the arguments to capture are on the operand stack of `thread`.
+/// A lambda capturing nothing is created once, as LambdaMetafactory returns the same instance each time.
+pub fn new_lambda(vm: &mut Vm, thread: &mut JavaThread, class: &Rc<Class>) -> Result<Value, Throwable> {
+    vm.initialize_class(thread, class)?;
+    let instance = class.fields.iter().find(|field| field.is_static());
+    if let Some(field) = instance {
+        let lambda = class.static_vars.borrow()[field.offset()].clone();
+        if !lambda.as_reference().is_null() {
+            return Ok(lambda);
+        }
+    }
+    // the captured arguments are roots until the lambda holds them
+    vm.reserve(thread, instance_size(class))?;
+    let frame = thread.frame();
+    let mut captured: Vec<Value> = captured_fields(class).map(|_| frame.pop()).collect();
+    captured.reverse();
+    let lambda = vm.heap.allocate(Object::new(class.clone()))?;
+    let object = lambda.object().expect("new lambda");
+    for (field, value) in captured_fields(class).zip(captured) {
+        vm.heap.write_barrier(object, &value);
+        object.set_field(field, value);
+    }
+    // the static variables are roots
+    if let Some(field) = instance {
+        class.static_vars.borrow_mut()[field.offset()] = Value::Reference(lambda.clone());
+    }
+    Ok(Value::Reference(lambda))
+}
+
+/// the fields of a lambda holding the arguments it captured
+fn captured_fields(class: &Class) -> impl Iterator<Item = &Rc<Field>> {
+    class.fields.iter().filter(|field| !field.is_static())
+}
+
+impl Lambda {
+    /// invoke the implementation method for the interface method `method` of the lambda `args[0]`: the captured and the
+    /// passed arguments are adapted to its parameter types as LambdaMetafactory does, boxing, unboxing and widening them
+    pub fn invoke(&self, vm: &mut Vm, thread: &mut JavaThread, method: &Method, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+        let lambda = args[0].as_reference().object().expect("receiver of lambda");
+        let mut sources: Vec<&str> = captured_fields(&lambda.class).map(|field| field.descriptor()).collect();
+        sources.extend(method.parameter_descriptors().iter().map(String::as_str));
+        let receiver = self.implementation.class().name.clone();
+        let receiver = [JVM_SIGNATURE_CLASS, &receiver, JVM_SIGNATURE_ENDCLASS].concat();
+        let mut targets: Vec<&str> = match self.reference_kind {
+            JVM_REF_INVOKE_VIRTUAL | JVM_REF_INVOKE_SPECIAL | JVM_REF_INVOKE_INTERFACE => vec![&receiver],
+            _ => vec![]
+        };
+        targets.extend(self.implementation.parameter_descriptors().iter().map(String::as_str));
+        if sources.len() != targets.len() {
+            return Err(Throwable::new(JAVA_LANG_INTERNAL_ERROR, format!("{} can not be adapted to {}",
+                method.descriptor(), interpreter::method_name(&self.implementation))));
+        }
+
+        // the arguments stay on the operand stack of the caller, one value per entry, while boxing may collect garbage
+        let frame = thread.frame();
+        let base = frame.stack.len();
+        frame.stack.extend(captured_fields(&lambda.class).map(|field| lambda.get_field(field)));
+        frame.stack.extend(values(&args[1..], method.parameter_descriptors()));
+        for (index, (source, target)) in sources.iter().zip(&targets).enumerate() {
+            let value = thread.frame().stack[base + index].clone();
+            let value = adapt(vm, thread, value, source, target, self.implementation.class().defining_loader)?;
+            thread.frame().stack[base + index] = value;
+        }
+
+        let result = if self.reference_kind == JVM_REF_NEW_INVOKE_SPECIAL {
+            let class = self.implementation.class();
+            vm.initialize_class(thread, &class)?;
+            vm.reserve(thread, instance_size(&class))?;
+            let object = Value::Reference(vm.heap.allocate(Object::new(class))?);
+            // kept below the arguments as `new` and `dup` would, the constructor returns nothing
+            thread.frame().stack.insert(base, object.clone());
+            let args = slots(thread.frame().stack.split_off(base + 1));
+            interpreter::invoke(vm, thread, &self.implementation, [vec![object], args].concat())?;
+            thread.frame().stack.pop()
+        } else {
+            if self.reference_kind == JVM_REF_INVOKE_STATIC {
+                vm.initialize_class(thread, &self.implementation.class())?;
+            }
+            let args = slots(thread.frame().stack.split_off(base));
+            let selected = match self.reference_kind {
+                JVM_REF_INVOKE_STATIC => self.implementation.clone(),
+                _ => {
+                    let receiver = args[0].as_reference().object()
+                        .ok_or_else(|| Throwable::new(JAVA_LANG_NULL_POINTER_EXCEPTION, ""))?;
+                    vm.link_class(&receiver.class)?;
+                    match self.reference_kind {
+                        JVM_REF_INVOKE_VIRTUAL => select_virtual(&receiver.class, self.implementation.clone())?,
+                        JVM_REF_INVOKE_INTERFACE => select_interface(&receiver.class, self.implementation.clone())?,
+                        // a private method of the class of the lambda
+                        _ => self.implementation.clone()
+                    }
+                }
+            };
+            interpreter::invoke(vm, thread, &selected, args)?
+        };
+        let source = match self.reference_kind {
+            JVM_REF_NEW_INVOKE_SPECIAL => &receiver,
+            _ => self.implementation.return_descriptor()
+        };
+        match (result, method.return_descriptor()) {
+            (_, JVM_SIGNATURE_VOID) => Ok(None),
+            (Some(value), target) => Ok(Some(adapt(vm, thread, value, source, target, self.implementation.class().defining_loader)?)),
+            (None, _) => Err(Throwable::new(JAVA_LANG_INTERNAL_ERROR, format!("{} returns no value for {}",
+                interpreter::method_name(&self.implementation), method.descriptor())))
+        }
+    }
+}
+
+fn conversion_error(message: String) -> Throwable {
+    Throwable::with_cause(JAVA_LANG_BOOTSTRAP_METHOD_ERROR, "bootstrap method initialization exception",
+        Throwable::new(JAVA_LANG_INVOKE_LAMBDA_CONVERSION_EXCEPTION, message))
+}
+
+/// the values of the slots of arguments of `descriptors`, a long or double takes two slots
+fn values(slots: &[Value], descriptors: &[String]) -> Vec<Value> {
+    let mut slots = slots.iter();
+    descriptors.iter().map(|_| {
+        let value = slots.next().expect("argument slot").clone();
+        if value.is_category2() {
+            slots.next();
+        }
+        value
+    }).collect()
+}
+
+fn slots(values: Vec<Value>) -> Vec<Value> {
+    values.into_iter().flat_map(|value| if value.is_category2() { vec![value, Value::Top] } else { vec![value] }).collect()
+}
+
+/// convert a value of the type `source` to `target` (JLS 5.3), a reference is checked to be an instance of the target class
+fn adapt(vm: &mut Vm, thread: &mut JavaThread, value: Value, source: &str, target: &str, loader: ClassLoaderId) -> Result<Value, Throwable> {
+    let is_primitive = |descriptor: &str| descriptor.len() == 1;
+    match (is_primitive(source), is_primitive(target)) {
+        (true, true) => Ok(widen(value, target)),
+        (true, false) => {
+            let (_, wrapper) = WRAPPERS.iter().find(|(primitive, _)| *primitive == source).expect("primitive type");
+            let wrapper = vm.load_class(BOOTSTRAP_LOADER, wrapper)?;
+            let value_of = wrapper.find_method("valueOf", &format!("({})L{};", source, wrapper.name)).expect("valueOf of wrapper class");
+            vm.initialize_class(thread, &wrapper)?;
+            let value = interpreter::invoke(vm, thread, &value_of, slots(vec![value]))?;
+            Ok(value.expect("boxed value"))
+        }
+        (false, true) => {
+            let object = value.as_reference().object().ok_or_else(|| Throwable::new(JAVA_LANG_NULL_POINTER_EXCEPTION, ""))?;
+            let (primitive, wrapper) = WRAPPERS.iter().find(|(_, wrapper)| *wrapper == object.class.name)
+                .or_else(|| WRAPPERS.iter().find(|(primitive, _)| *primitive == target))
+                .expect("primitive type");
+            match object.class.find_field("value", primitive) {
+                Some(field) if object.class.name == *wrapper => Ok(widen(object.get_field(&field), target)),
+                _ => Err(Throwable::new(JAVA_LANG_CLASS_CAST_EXCEPTION, format!("class {} cannot be cast to class {}",
+                    object.class.name.replace('/', "."), wrapper.replace('/', "."))))
+            }
+        }
+        (false, false) => {
+            let object = match value.as_reference().object() {
+                Some(object) if source != target => object,
+                _ => return Ok(value)
+            };
+            let name = target.strip_prefix(JVM_SIGNATURE_CLASS).and_then(|name| name.strip_suffix(JVM_SIGNATURE_ENDCLASS)).unwrap_or(target);
+            let class = vm.resolve_class(loader, name)?;
+            if !object.class.is_assignable_to(&class) {
+                return Err(Throwable::new(JAVA_LANG_CLASS_CAST_EXCEPTION, format!("class {} cannot be cast to class {}",
+                    object.class.name.replace('/', "."), class.name.replace('/', "."))));
+            }
+            Ok(value)
+        }
+    }
+}
+
+/// JLS 5.1.2 widening primitive conversion, an int stands for the smaller integral types
+fn widen(value: Value, target: &str) -> Value {
+    match (value, target) {
+        (Value::Int(value), JVM_SIGNATURE_LONG) => Value::Long(value as i64),
+        (Value::Int(value), JVM_SIGNATURE_FLOAT) => Value::Float(value as f32),
+        (Value::Int(value), JVM_SIGNATURE_DOUBLE) => Value::Double(value as f64),
+        (Value::Long(value), JVM_SIGNATURE_FLOAT) => Value::Float(value as f32),
+        (Value::Long(value), JVM_SIGNATURE_DOUBLE) => Value::Double(value as f64),
+        (Value::Float(value), JVM_SIGNATURE_DOUBLE) => Value::Double(value as f64),
+        (value, _) => value
+    }
+}
+
+/// the class file of a final class implementing `interfaces` with `fields` and `methods` without code
+fn class_bytes(name: &str, interfaces: &[String], fields: &[(String, String, u16)], methods: &[(&str, &str, u16)]) -> Vec<u8> {
+    let mut constants: Vec<Vec<u8>> = vec![];
+    let mut utf8 = |string: &str| -> u16 {
+        let mut entry = vec![JVM_TAG_UTF8];
+        entry.extend((string.len() as u16).to_be_bytes());
+        entry.extend(string.as_bytes());
+        let index = constants.iter().position(|constant| *constant == entry).unwrap_or_else(|| {
+            constants.push(entry);
+            constants.len() - 1
+        });
+        index as u16 + 1
+    };
+    let this_name = utf8(name);
+    let super_name = utf8(JAVA_LANG_OBJECT);
+    let interface_names: Vec<u16> = interfaces.iter().map(|interface| utf8(interface)).collect();
+    let fields: Vec<(u16, u16, u16)> = fields.iter().map(|(name, descriptor, access_flags)| (utf8(name), utf8(descriptor), *access_flags)).collect();
+    let methods: Vec<(u16, u16, u16)> = methods.iter().map(|(name, descriptor, access_flags)| (utf8(name), utf8(descriptor), *access_flags)).collect();
+    // the Class constants follow the names they refer to
+    let class_index = |position: u16| constants.len() as u16 + position;
+    let classes: Vec<u16> = [this_name, super_name].iter().chain(&interface_names).copied().collect();
+
+    let mut bytes = CLASS_FILE_MAGIC.to_be_bytes().to_vec();
+    bytes.extend([0u16, 52].iter().flat_map(|version| version.to_be_bytes()));
+    bytes.extend((constants.len() as u16 + classes.len() as u16 + 1).to_be_bytes());
+    for constant in &constants {
+        bytes.extend(constant);
+    }
+    for &name_index in &classes {
+        bytes.push(JVM_TAG_CLASS);
+        bytes.extend(name_index.to_be_bytes());
+    }
+    let u2s = [ACC_FINAL | ACC_SUPER | ACC_SYNTHETIC, class_index(1), class_index(2), interface_names.len() as u16];
+    bytes.extend(u2s.iter().flat_map(|u2| u2.to_be_bytes()));
+    for index in 0..interface_names.len() as u16 {
+        bytes.extend(class_index(3 + index).to_be_bytes());
+    }
+    bytes.extend((fields.len() as u16).to_be_bytes());
+    for (name, descriptor, access_flags) in fields {
+        bytes.extend([access_flags, name, descriptor, 0].iter().flat_map(|u2| u2.to_be_bytes()));
+    }
+    bytes.extend((methods.len() as u16).to_be_bytes());
+    for (name, descriptor, access_flags) in methods {
+        bytes.extend([access_flags, name, descriptor, 0].iter().flat_map(|u2| u2.to_be_bytes()));
+    }
+    // no attributes
+    bytes.extend(0u16.to_be_bytes());
+    bytes
+}
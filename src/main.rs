@@ -1,14 +1,248 @@
-use std::borrow::Borrow;
-use crate::types::Class;
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread::Builder;
+use crate::class_path::ClassPath;
+use crate::constants::{JAVA_LANG_CLASS, JAVA_LANG_LINKAGE_ERROR, JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR, JAVA_LANG_OBJECT, JAVA_LANG_STRING,
+    MAIN_METHOD_DESCRIPTOR, MAIN_METHOD_NAME};
+use crate::exception::Throwable;
+use crate::heap::Collector;
+use crate::object::Object;
+use crate::class_loader::{ClassLoaders, APP_LOADER, BOOTSTRAP_LOADER};
+use crate::thread::JavaThread;
+use crate::values::{ArrayReference, Value};
+use crate::vm::{Verification, Vm, VmOptions};
 
 mod types;
 mod constants;
 mod values;
 mod object;
+mod heap;
 mod class_file;
+mod class_path;
+mod zip;
+mod inflate;
+mod manifest;
+mod jimage;
+mod class_loader;
+mod method_area;
+mod exception;
+mod thread;
+mod vm;
+mod initialization;
+mod bytecode;
+mod frame;
+mod interpreter;
+mod dispatch;
+mod call_site;
+mod lambda;
+mod string_concat;
+mod arithmetic;
+mod natives;
+mod strings;
+mod verifier;
+mod type_inference;
+#[cfg(test)]
+mod class_builder;
+
+const USAGE: &str = "Usage: jara [options] <main class> [args...]
+           (to execute a class)
+   or  jara [options] -jar <jar file> [args...]
+           (to execute a jar file)
+ where options include:
+    -cp <class search path>
+    -Xverify:none|remote|all
+                  verify no classes, the classes not loaded by the bootstrap loader (default) or all classes
+    -Xmx<size>    set the maximum heap size, e.g. -Xmx512m (default 256m)
+    -XX:+UseGenerationalGC
+                  collect the heap with a generational copying collector rather than mark-sweep
+    -Xlog:gc      print every garbage collection pause and their statistics on exit";
+
+enum Launch {
+    MainClass(String),
+    Jar(String)
+}
+
+struct Options {
+    class_path: Option<String>,
+    vm_options: VmOptions,
+    launch: Launch,
+    args: Vec<String>
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut class_path = None;
+        let mut vm_options = VmOptions::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-cp" | "-classpath" | "--class-path" => {
+                    class_path = Some(args.next().ok_or(format!("{} requires class path specification", arg))?);
+                }
+                "-Xverify:none" => vm_options.verification = Verification::None,
+                "-Xverify:remote" => vm_options.verification = Verification::Remote,
+                "-Xverify:all" => vm_options.verification = Verification::All,
+                "-XX:+UseGenerationalGC" => vm_options.collector = Collector::Generational,
+                "-XX:-UseGenerationalGC" => vm_options.collector = Collector::MarkSweep,
+                "-Xlog:gc" => vm_options.log_gc = true,
+                _ if arg.starts_with("-Xmx") => {
+                    vm_options.max_heap_size = parse_size(&arg["-Xmx".len()..]).ok_or(format!("Invalid maximum heap size: {}", arg))?;
+                }
+                "-jar" => {
+                    let jar = args.next().ok_or("-jar requires jar file specification")?;
+                    return Ok(Options {
+                        class_path,
+                        vm_options,
+                        launch: Launch::Jar(jar),
+                        args: args.collect()
+                    })
+                }
+                _ if arg.starts_with('-') => return Err(format!("Unrecognized option: {}", arg)),
+                _ => return Ok(Options {
+                    class_path,
+                    vm_options,
+                    launch: Launch::MainClass(arg),
+                    args: args.collect()
+                })
+            }
+        }
+        Err("Missing main class".to_string())
+    }
+}
+
+/// a size in bytes, kilobytes, megabytes or gigabytes, e.g. `64m` or `1G`
+fn parse_size(size: &str) -> Option<usize> {
+    let (digits, unit) = match size.char_indices().last()? {
+        (index, unit) if unit.is_ascii_alphabetic() => (&size[..index], unit.to_ascii_lowercase()),
+        _ => (size, 'b')
+    };
+    let shift = match unit {
+        'b' => 0,
+        'k' => 10,
+        'm' => 20,
+        'g' => 30,
+        _ => return None
+    };
+    digits.parse::<usize>().ok()?.checked_mul(1 << shift)
+}
+
+/// `JAVA_HOME`, or the JDK of the `java` launcher on `PATH`
+fn find_java_home() -> Option<PathBuf> {
+    if let Some(java_home) = env::var_os("JAVA_HOME").filter(|java_home| !java_home.is_empty()) {
+        return Some(PathBuf::from(java_home));
+    }
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join("java"))
+        .find(|java| java.is_file())
+        .and_then(|java| java.canonicalize().ok())
+        .and_then(|java| java.parent().and_then(Path::parent).map(Path::to_path_buf))
+}
+
+// the interpreter recurses on every java invocation, the native stack holds MAX_STACK_DEPTH frames of it
+const NATIVE_STACK_SIZE: usize = 256 * 1024 * 1024;
 
 fn main() {
-    let classfile = class_file::read("./java/HelloWorld.class");
-    let class = Class::from(&classfile);
-    println!("{}", "ok");
+    // like the java launcher, the main method runs on a new thread rather than the primordial one
+    let main_thread = Builder::new()
+        .name("main".to_string())
+        .stack_size(NATIVE_STACK_SIZE)
+        .spawn(run)
+        .expect("main thread");
+    if main_thread.join().is_err() {
+        process::exit(1)
+    }
+}
+
+fn run() {
+    let options = Options::parse(env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        process::exit(1)
+    });
+    let (boot_class_path, platform_class_path, app_modules) = find_java_home()
+        .ok_or_else(|| "JAVA_HOME is not set".to_string())
+        .and_then(|java_home| ClassPath::runtime(&java_home).map_err(|error| error.to_string()))
+        .unwrap_or_else(|message| {
+            eprintln!("Error occurred during initialization of boot layer\n{}", message);
+            process::exit(1)
+        });
+    let (class_path, main_class) = match &options.launch {
+        // the jar is the only user class path, -cp is ignored
+        Launch::Jar(jar) => {
+            if !Path::new(jar).is_file() {
+                eprintln!("Error: Unable to access jarfile {}", jar);
+                process::exit(1)
+            }
+            let class_path = ClassPath::parse(jar);
+            let main_class = class_path.main_class().unwrap_or_else(|| {
+                eprintln!("no main manifest attribute, in {}", jar);
+                process::exit(1)
+            });
+            (class_path, main_class)
+        }
+        Launch::MainClass(main_class) => {
+            let class_path = match &options.class_path {
+                Some(class_path) => ClassPath::parse(class_path),
+                None => ClassPath::from_env()
+            };
+            (class_path, main_class.clone())
+        }
+    };
+    let mut vm = Vm::new(ClassLoaders::new(boot_class_path, platform_class_path, app_modules.append(class_path)), options.vm_options);
+    let mut thread = JavaThread::main();
+    // the well-known classes, every class loaded afterwards gets its mirror at once
+    for name in [JAVA_LANG_OBJECT, JAVA_LANG_STRING, JAVA_LANG_CLASS] {
+        if let Err(throwable) = vm.load_class(BOOTSTRAP_LOADER, name) {
+            eprintln!("Error occurred during initialization of VM\n{}", throwable);
+            process::exit(1)
+        }
+    }
+    if let Err(throwable) = vm.preallocate_out_of_memory_error(&mut thread) {
+        eprintln!("Error occurred during initialization of VM\n{}", throwable);
+        process::exit(1)
+    }
+    let main_class = main_class.replace('.', "/");
+    let class = vm.load_class(APP_LOADER, &main_class).unwrap_or_else(|throwable| {
+        // like LauncherHelper, a class which is found but can not be derived is told apart from a missing one
+        let is_linkage_error = match (vm.load_class(BOOTSTRAP_LOADER, &throwable.class_name), vm.load_class(BOOTSTRAP_LOADER, JAVA_LANG_LINKAGE_ERROR)) {
+            (Ok(class), Ok(linkage_error)) => class.is_subclass_of(&linkage_error) && throwable.class_name != JAVA_LANG_NO_CLASS_DEF_FOUND_ERROR,
+            _ => false
+        };
+        if is_linkage_error {
+            eprintln!("Error: LinkageError occurred while loading main class {}\n\t{}", main_class.replace('/', "."), throwable);
+        } else {
+            eprintln!("Error: Could not find or load main class {}", main_class.replace('/', "."));
+            eprintln!("Caused by: {}", throwable);
+        }
+        process::exit(1)
+    });
+    let args = options.args;
+    let main_method = class.find_method(MAIN_METHOD_NAME, MAIN_METHOD_DESCRIPTOR)
+        .filter(|method| method.is_static())
+        .unwrap_or_else(|| {
+            eprintln!("Error: Main method not found in class {}, please define the main method as:\n   public static void main(String[] args)",
+                main_class.replace('/', "."));
+            process::exit(1)
+        });
+    let result = vm.initialize_class(&mut thread, &class)
+        .and_then(|_| new_string_array(&mut vm, &args))
+        .and_then(|args| interpreter::invoke(&mut vm, &mut thread, &main_method, vec![Value::Reference(args)]));
+    vm.heap.report();
+    if let Err(throwable) = result {
+        eprint!("Exception in thread \"{}\" {}", thread.name, vm.stack_trace(&throwable));
+        process::exit(1)
+    }
+}
+
+/// the `String[] args` of the main method
+fn new_string_array(vm: &mut Vm, strings: &[String]) -> Result<ArrayReference, Throwable> {
+    let array_class = vm.load_class(BOOTSTRAP_LOADER, "[Ljava/lang/String;")?;
+    let array = vm.heap.allocate(Object::new_array(array_class, strings.len()))?;
+    for (index, string) in strings.iter().enumerate() {
+        let string = vm.new_string(string)?;
+        let (array, string) = (array.object().expect("new array"), Value::Reference(string));
+        vm.heap.write_barrier(array, &string);
+        array.array().store(index, string);
+    }
+    Ok(array)
 }
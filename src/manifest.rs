@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+pub const MANIFEST_NAME: &str = "META-INF/MANIFEST.MF";
+pub const ATTRIBUTE_MAIN_CLASS: &str = "Main-Class";
+pub const ATTRIBUTE_CLASS_PATH: &str = "Class-Path";
+
+/// the main section attributes of a jar manifest
+pub struct Manifest {
+    // by the lower case name, attribute names are case-insensitive
+    attributes: HashMap<String, String>
+}
+
+impl Manifest {
+    pub fn parse(bytes: &[u8]) -> Self {
+        let text = String::from_utf8_lossy(bytes);
+        let mut attributes = HashMap::new();
+        let mut current: Option<(String, String)> = None;
+        for line in text.split('\n').map(|line| line.trim_end_matches('\r')) {
+            if let Some(continuation) = line.strip_prefix(' ') {
+                // a line longer than 72 bytes is continued on the next line starting with a single space
+                if let Some((_, value)) = current.as_mut() {
+                    value.push_str(continuation);
+                }
+                continue;
+            }
+            if let Some((name, value)) = current.take() {
+                attributes.insert(name, value);
+            }
+            if line.is_empty() {
+                break; // end of main section
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                current = Some((name.trim().to_ascii_lowercase(), value.trim_start().to_string()));
+            }
+        }
+        if let Some((name, value)) = current {
+            attributes.insert(name, value);
+        }
+        Manifest { attributes }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes.get(&name.to_ascii_lowercase()).map(|value| value.as_str())
+    }
+
+    pub fn main_class(&self) -> Option<&str> {
+        self.get(ATTRIBUTE_MAIN_CLASS).map(|main_class| main_class.trim()).filter(|main_class| !main_class.is_empty())
+    }
+
+    /// space separated relative URLs of the jars or directories this jar depends on
+    pub fn class_path(&self) -> Vec<&str> {
+        self.get(ATTRIBUTE_CLASS_PATH).map_or(vec![], |class_path| class_path.split_whitespace().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribute_names_are_case_insensitive() {
+        let manifest = Manifest::parse(b"Manifest-Version: 1.0\r\nmain-class: T2\r\nCLASS-PATH: a.jar  lib/\r\n\r\n");
+        assert_eq!(manifest.main_class(), Some("T2"));
+        assert_eq!(manifest.get("Manifest-Version"), Some("1.0"));
+        assert_eq!(manifest.class_path(), vec!["a.jar", "lib/"]);
+    }
+
+    #[test]
+    fn long_values_are_continued_on_the_next_lines() {
+        let manifest = Manifest::parse(b"Main-Class: com.acme.app\n .Main\nClass-Path: a.jar b\n .jar\n  c.jar\n");
+        assert_eq!(manifest.main_class(), Some("com.acme.app.Main"));
+        // the space starting a continuation line is not part of the value
+        assert_eq!(manifest.class_path(), vec!["a.jar", "b.jar", "c.jar"]);
+    }
+
+    #[test]
+    fn only_the_main_section_is_read() {
+        let manifest = Manifest::parse(b"Manifest-Version: 1.0\n\nName: T2.class\nMain-Class: T2\n");
+        assert_eq!(manifest.main_class(), None);
+        assert_eq!(Manifest::parse(b"Main-Class:  \n").main_class(), None);
+    }
+}
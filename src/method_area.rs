@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::class_file::ClassFile;
+use crate::class_loader::{ClassLoaderId, BOOTSTRAP_LOADER};
+use crate::constants::*;
+use crate::object::Object;
+use crate::types::{Class, Field, Type};
+use crate::values::{ObjectReference, Reference, Value};
+
+// the java names and descriptors of the types which have a mirror but no runtime class, e.g. `int.class`
+pub const PRIMITIVE_TYPES: [(&str, &str); 9] = [
+    ("boolean", JVM_SIGNATURE_BOOLEAN),
+    ("byte", JVM_SIGNATURE_BYTE),
+    ("char", JVM_SIGNATURE_CHAR),
+    ("short", JVM_SIGNATURE_SHORT),
+    ("int", JVM_SIGNATURE_INT),
+    ("long", JVM_SIGNATURE_LONG),
+    ("float", JVM_SIGNATURE_FLOAT),
+    ("double", JVM_SIGNATURE_DOUBLE),
+    ("void", JVM_SIGNATURE_VOID)
+];
+
+/// index of a runtime class in the method area, stable for the lifetime of the VM
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ClassId(pub(crate) usize);
+
+/// the method area owns every runtime class along with its fields and methods.
+/// Fields and methods refer back to their class weakly, so the classes are dropped with the method area.
+pub struct MethodArea {
+    classes: Vec<Rc<Class>>,
+    // (initiating loader, binary name) -> class, the defining loader is an initiating loader too
+    dictionary: HashMap<(ClassLoaderId, String), ClassId>,
+    java_lang_class: Option<Rc<Class>>,
+    // in the order of PRIMITIVE_TYPES, like the mirrors of classes they are not in the heap
+    #[allow(clippy::vec_box)]
+    primitive_mirrors: Vec<Box<Object>>
+}
+
+impl MethodArea {
+    pub fn new() -> Self {
+        MethodArea {
+            classes: vec![],
+            dictionary: HashMap::new(),
+            java_lang_class: None,
+            primitive_mirrors: vec![]
+        }
+    }
+
+    pub fn class(&self, id: ClassId) -> &Rc<Class> {
+        &self.classes[id.0]
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = &Rc<Class>> {
+        self.classes.iter()
+    }
+
+    /// the class of `name` which `loader` has been recorded as an initiating loader of
+    pub fn find_loaded_class(&self, loader: ClassLoaderId, name: &str) -> Option<Rc<Class>> {
+        self.dictionary.get(&(loader, name.to_string())).map(|&id| self.classes[id.0].clone())
+    }
+
+    /// create the runtime class of `classfile` whose superclass and superinterfaces are already loaded
+    pub(crate) fn add_class(&mut self, classfile: &ClassFile, defining_loader: ClassLoaderId,
+                            super_class: Option<Rc<Class>>, interfaces: Vec<Rc<Class>>) -> Rc<Class> {
+        let id = ClassId(self.classes.len());
+        let class = Class::from(id, classfile, defining_loader, super_class, interfaces);
+        self.classes.push(class.clone());
+        if class.name == JAVA_LANG_CLASS && defining_loader == BOOTSTRAP_LOADER {
+            self.java_lang_class = Some(class.clone());
+            self.create_primitive_mirrors();
+            // the classes loaded before java.lang.Class, e.g. Object, get their mirrors now
+            for class in self.classes.clone() {
+                self.create_mirror(&class);
+            }
+        } else {
+            self.create_mirror(&class);
+        }
+        class
+    }
+
+    pub(crate) fn add_array_class(&mut self, name: &str, defining_loader: ClassLoaderId, component_type: Type,
+                                  object: Rc<Class>, interfaces: Vec<Rc<Class>>) -> Rc<Class> {
+        let id = ClassId(self.classes.len());
+        let class = Class::array(id, name, defining_loader, component_type, object, interfaces);
+        self.classes.push(class.clone());
+        self.create_mirror(&class);
+        class
+    }
+
+    /// the java.lang.Class instance of a class is created along with the class and links back to it
+    fn create_mirror(&self, class: &Rc<Class>) {
+        let java_lang_class = match &self.java_lang_class {
+            Some(java_lang_class) => java_lang_class.clone(),
+            None => return
+        };
+        let mirror = Object::new(java_lang_class.clone());
+        mirror.set_field(&self.klass_field(), Value::Long(class.id.0 as i64));
+        if let (Some(component_type), Some(field)) = (&class.component_type, java_lang_class.find_field("componentType", "Ljava/lang/Class;")) {
+            let component_mirror = match component_type.as_ref() {
+                Type::Class(component) => self.mirror_of(component),
+                primitive => self.primitive_mirror(&primitive.descriptor()).unwrap_or_else(Reference::null)
+            };
+            mirror.set_field(&field, Value::Reference(component_mirror));
+        }
+        let _ = class.mirror.set(Box::new(mirror));
+    }
+
+    fn create_primitive_mirrors(&mut self) {
+        let java_lang_class = self.java_lang_class.clone().expect("java.lang.Class is loaded");
+        self.primitive_mirrors = PRIMITIVE_TYPES.iter().map(|_| {
+            let mirror = Object::new(java_lang_class.clone());
+            // a primitive mirror has no runtime class
+            mirror.set_field(&self.klass_field(), Value::Long(-1));
+            Box::new(mirror)
+        }).collect();
+    }
+
+    fn klass_field(&self) -> Rc<Field> {
+        let java_lang_class = self.java_lang_class.as_ref().expect("java.lang.Class is loaded");
+        java_lang_class.find_field(INJECTED_KLASS_FIELD_NAME, JVM_SIGNATURE_LONG).expect("injected klass field")
+    }
+
+    pub fn mirror_of(&self, class: &Class) -> ObjectReference {
+        class.mirror.get().map_or_else(Reference::null, |mirror| Reference::to(mirror))
+    }
+
+    /// the mirror of a primitive type or void by its descriptor, e.g. `I` for `int.class`
+    pub fn primitive_mirror(&self, descriptor: &str) -> Option<ObjectReference> {
+        PRIMITIVE_TYPES.iter().position(|(_, primitive)| *primitive == descriptor)
+            .and_then(|index| self.primitive_mirrors.get(index).map(|mirror| Reference::to(mirror)))
+    }
+
+    /// the runtime class of a mirror, none for the mirror of a primitive type
+    pub fn class_of_mirror(&self, mirror: &Object) -> Option<Rc<Class>> {
+        match mirror.get_field(&self.klass_field()) {
+            Value::Long(id) if id >= 0 => Some(self.classes[id as usize].clone()),
+            _ => None
+        }
+    }
+
+    /// the java name and descriptor of a primitive mirror, e.g. `("int", "I")`
+    pub fn primitive_of_mirror(&self, mirror: &Object) -> Option<(&'static str, &'static str)> {
+        self.primitive_mirrors.iter().position(|primitive_mirror| std::ptr::eq(primitive_mirror.as_ref(), mirror))
+            .map(|index| PRIMITIVE_TYPES[index])
+    }
+
+    /// the mirrors of the classes and of the primitive types, which are not in the heap
+    pub fn mirrors(&self) -> Vec<&Object> {
+        self.classes.iter().filter_map(|class| class.mirror.get())
+            .chain(&self.primitive_mirrors)
+            .map(|mirror| mirror.as_ref())
+            .collect()
+    }
+
+    /// record `loader` as an initiating loader of `class`
+    pub(crate) fn record(&mut self, loader: ClassLoaderId, class: &Class) {
+        self.dictionary.insert((loader, class.name.clone()), class.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::class_builder::{test_vm, ClassBuilder, Code};
+    use crate::class_loader::{APP_LOADER, BOOTSTRAP_LOADER};
+    use crate::constants::*;
+    use crate::vm::VmOptions;
+    use std::rc::Rc;
+
+    #[test]
+    fn classes_are_owned_by_the_method_area() {
+        let mut vm = test_vm(VmOptions::default());
+        let class = ClassBuilder::new("C", Some(JAVA_LANG_OBJECT))
+            .field(ACC_STATIC, "f", "I")
+            .method(ACC_STATIC, "m", "()V", Some(Code::new(0, 0, vec![0xb1])))
+            .define(&mut vm, APP_LOADER);
+        assert!(Rc::ptr_eq(vm.method_area.class(class.id), &class));
+        // the fields and methods refer back to their class, which refers to its superclass
+        assert!(Rc::ptr_eq(&class.find_field("f", "I").expect("C.f").class(), &class));
+        assert!(Rc::ptr_eq(&class.find_method("m", "()V").expect("C.m").class(), &class));
+        let object = vm.method_area.find_loaded_class(BOOTSTRAP_LOADER, JAVA_LANG_OBJECT).expect("Object");
+        assert!(Rc::ptr_eq(class.super_class.as_ref().expect("superclass"), &object));
+
+        // a class is found by its defining loader, and by the loaders which initiated loading it, as the application
+        // loader did for the superclass of C
+        assert!(vm.method_area.find_loaded_class(APP_LOADER, "C").is_some_and(|found| Rc::ptr_eq(&found, &class)));
+        assert!(vm.method_area.find_loaded_class(BOOTSTRAP_LOADER, "C").is_none());
+        assert!(vm.method_area.find_loaded_class(APP_LOADER, JAVA_LANG_OBJECT).is_some_and(|found| Rc::ptr_eq(&found, &object)));
+
+        // no class keeps itself alive through its fields or methods
+        let (weak_class, weak_object) = (Rc::downgrade(&class), Rc::downgrade(&object));
+        drop((class, object, vm));
+        assert!(weak_class.upgrade().is_none() && weak_object.upgrade().is_none());
+    }
+}
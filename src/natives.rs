@@ -0,0 +1,269 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::class_loader::BOOTSTRAP_LOADER;
+use crate::constants::*;
+use crate::exception::{backtrace, Throwable};
+use crate::object::{Array, Object};
+use crate::thread::JavaThread;
+use crate::types::{Class, Type};
+use crate::values::{Reference, Value};
+use crate::vm::Vm;
+
+/// a native method implemented by the VM, `args` include the receiver of an instance method
+pub type NativeMethod = fn(&mut Vm, &mut JavaThread, Vec<Value>) -> Result<Option<Value>, Throwable>;
+
+/// the VM implementation of a native method, none if it is not registered
+pub fn find_native(class_name: &str, name: &str, descriptor: &str) -> Option<NativeMethod> {
+    let native: NativeMethod = match (class_name, name, descriptor) {
+        ("java/lang/Object", "getClass", "()Ljava/lang/Class;") => object_get_class,
+        ("java/lang/Object", "hashCode", "()I") => object_hash_code,
+        ("java/lang/String", "intern", "()Ljava/lang/String;") => string_intern,
+        ("java/lang/StringUTF16", "isBigEndian", "()Z") => string_utf16_is_big_endian,
+        ("java/lang/Class", "registerNatives", "()V") => register_natives,
+        ("java/lang/System", "registerNatives", "()V") => register_natives,
+        ("java/lang/Class", "desiredAssertionStatus0", "(Ljava/lang/Class;)Z") => class_desired_assertion_status,
+        ("java/lang/Class", "getPrimitiveClass", "(Ljava/lang/String;)Ljava/lang/Class;") => class_get_primitive_class,
+        ("java/lang/Class", "initClassName", "()Ljava/lang/String;") => class_init_class_name,
+        ("java/lang/Class", "isInstance", "(Ljava/lang/Object;)Z") => class_is_instance,
+        ("java/lang/Class", "isAssignableFrom", "(Ljava/lang/Class;)Z") => class_is_assignable_from,
+        ("java/lang/Class", "isInterface", "()Z") => class_is_interface,
+        ("java/lang/Class", "isArray", "()Z") => class_is_array,
+        ("java/lang/Class", "isPrimitive", "()Z") => class_is_primitive,
+        ("java/lang/Class", "isHidden", "()Z") => class_is_hidden,
+        ("java/lang/Class", "getSuperclass", "()Ljava/lang/Class;") => class_get_superclass,
+        ("java/lang/Class", "getInterfaces0", "()[Ljava/lang/Class;") => class_get_interfaces,
+        ("java/lang/Class", "getModifiers", "()I") => class_get_modifiers,
+        ("java/lang/Float", "floatToRawIntBits", "(F)I") => float_to_raw_int_bits,
+        ("java/lang/Float", "intBitsToFloat", "(I)F") => int_bits_to_float,
+        ("java/lang/Double", "doubleToRawLongBits", "(D)J") => double_to_raw_long_bits,
+        ("java/lang/Double", "longBitsToDouble", "(J)D") => long_bits_to_double,
+        ("java/lang/System", "arraycopy", "(Ljava/lang/Object;ILjava/lang/Object;II)V") => system_arraycopy,
+        ("java/lang/Throwable", "fillInStackTrace", "(I)Ljava/lang/Throwable;") => throwable_fill_in_stack_trace,
+        _ => return None
+    };
+    Some(native)
+}
+
+fn object_arg(args: &[Value], index: usize) -> Result<&Object, Throwable> {
+    args[index].as_reference().object()
+        .ok_or_else(|| Throwable::new(JAVA_LANG_NULL_POINTER_EXCEPTION, ""))
+}
+
+fn boolean(value: bool) -> Result<Option<Value>, Throwable> {
+    Ok(Some(Value::Int(value as i32)))
+}
+
+fn object_get_class(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    let this = object_arg(&args, 0)?;
+    Ok(Some(Value::Reference(vm.method_area.mirror_of(&this.class))))
+}
+
+fn object_hash_code(_vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    Ok(Some(Value::Int(object_arg(&args, 0)?.hash_code)))
+}
+
+fn register_natives(_vm: &mut Vm, _thread: &mut JavaThread, _args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    Ok(None)
+}
+
+fn class_desired_assertion_status(_vm: &mut Vm, _thread: &mut JavaThread, _args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    boolean(false)
+}
+
+fn class_get_primitive_class(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    let name = vm.string_value(object_arg(&args, 0)?);
+    let mirror = crate::method_area::PRIMITIVE_TYPES.iter().find(|(java_name, _)| *java_name == name)
+        .and_then(|(_, descriptor)| vm.method_area.primitive_mirror(descriptor))
+        .unwrap_or_else(Reference::null);
+    Ok(Some(Value::Reference(mirror)))
+}
+
+fn string_intern(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    let this = args[0].as_reference().clone();
+    Ok(Some(Value::Reference(vm.intern_string(this))))
+}
+
+/// the chars of a UTF-16 string are in native byte order
+fn string_utf16_is_big_endian(_vm: &mut Vm, _thread: &mut JavaThread, _args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    boolean(cfg!(target_endian = "big"))
+}
+
+/// the binary name with dots, e.g. `java.lang.String`, `[I` or `int`, cached in `Class.name`
+fn class_init_class_name(vm: &mut Vm, thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    let this = object_arg(&args, 0)?;
+    let name = match (vm.method_area.class_of_mirror(this), vm.method_area.primitive_of_mirror(this)) {
+        (Some(class), _) => class.name.replace('/', "."),
+        (None, Some((java_name, _))) => java_name.to_string(),
+        (None, None) => unreachable!("a mirror is of a class or a primitive type")
+    };
+    // interned as HotSpot does, the mirror is not in the heap
+    let chars: Vec<u16> = name.encode_utf16().collect();
+    let name = vm.intern(thread, &chars)?;
+    if let Some(field) = this.class.find_field("name", "Ljava/lang/String;") {
+        this.set_field(&field, Value::Reference(name.clone()));
+    }
+    Ok(Some(Value::Reference(name)))
+}
+
+fn class_is_instance(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    let this = object_arg(&args, 0)?;
+    let instance_of = match (vm.method_area.class_of_mirror(this), object_arg(&args, 1)) {
+        (Some(class), Ok(object)) => object.class.is_assignable_to(&class),
+        _ => false
+    };
+    boolean(instance_of)
+}
+
+fn class_is_assignable_from(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    let (this, other) = (object_arg(&args, 0)?, object_arg(&args, 1)?);
+    let assignable = match (vm.method_area.class_of_mirror(this), vm.method_area.class_of_mirror(other)) {
+        (Some(class), Some(other)) => other.is_assignable_to(&class),
+        // a primitive type is only assignable from itself
+        _ => std::ptr::eq(this, other)
+    };
+    boolean(assignable)
+}
+
+fn class_of(vm: &Vm, args: &[Value]) -> Result<Option<Rc<Class>>, Throwable> {
+    Ok(vm.method_area.class_of_mirror(object_arg(args, 0)?))
+}
+
+fn class_is_interface(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    boolean(class_of(vm, &args)?.is_some_and(|class| class.is_interface()))
+}
+
+fn class_is_array(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    boolean(class_of(vm, &args)?.is_some_and(|class| class.is_array()))
+}
+
+fn class_is_primitive(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    boolean(class_of(vm, &args)?.is_none())
+}
+
+fn class_is_hidden(_vm: &mut Vm, _thread: &mut JavaThread, _args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    boolean(false)
+}
+
+/// null for Object, interfaces and primitive types
+fn class_get_superclass(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    let super_class = class_of(vm, &args)?
+        .filter(|class| !class.is_interface())
+        .and_then(|class| class.super_class.clone());
+    let mirror = super_class.map_or_else(Reference::null, |super_class| vm.method_area.mirror_of(&super_class));
+    Ok(Some(Value::Reference(mirror)))
+}
+
+fn class_get_interfaces(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    let interfaces = class_of(vm, &args)?.map_or(vec![], |class| class.interfaces.clone());
+    let array_class = vm.load_class(BOOTSTRAP_LOADER, "[Ljava/lang/Class;")?;
+    let mirrors = interfaces.iter().map(|interface| vm.method_area.mirror_of(interface)).collect();
+    let array = Object::from_array(array_class, Array::Reference(RefCell::new(mirrors)));
+    Ok(Some(Value::Reference(vm.heap.allocate(array)?)))
+}
+
+/// the class access flags as in java.lang.reflect.Modifier, ACC_SUPER is not a modifier
+fn class_get_modifiers(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    let modifiers = class_of(vm, &args)?.map_or(ACC_PUBLIC | ACC_FINAL | ACC_ABSTRACT, |class| class.access_flags & !ACC_SUPER);
+    Ok(Some(Value::Int(modifiers as i32)))
+}
+
+fn float_to_raw_int_bits(_vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    Ok(Some(Value::Int(args[0].as_float().to_bits() as i32)))
+}
+
+fn int_bits_to_float(_vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    Ok(Some(Value::Float(f32::from_bits(args[0].as_int() as u32))))
+}
+
+fn double_to_raw_long_bits(_vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    Ok(Some(Value::Long(args[0].as_double().to_bits() as i64)))
+}
+
+fn long_bits_to_double(_vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    Ok(Some(Value::Double(f64::from_bits(args[0].as_long() as u64))))
+}
+
+/// the checks and messages of HotSpot's ObjArrayKlass::copy_array and TypeArrayKlass::copy_array
+fn system_arraycopy(vm: &mut Vm, _thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    let (src, dest) = (object_arg(&args, 0)?, object_arg(&args, 2)?);
+    let (src_pos, dest_pos, length) = (args[1].as_int(), args[3].as_int(), args[4].as_int());
+    for (object, role) in [(src, "source"), (dest, "destination")] {
+        if !object.class.is_array() {
+            return Err(Throwable::new(JAVA_LANG_ARRAY_STORE_EXCEPTION, format!("arraycopy: {} type {} is not an array",
+                role, object.class.name.replace('/', "."))));
+        }
+    }
+    let (src_array, dest_array) = (src.array(), dest.array());
+    if std::mem::discriminant(src_array) != std::mem::discriminant(dest_array) {
+        return Err(Throwable::new(JAVA_LANG_ARRAY_STORE_EXCEPTION, format!("arraycopy: type mismatch: can not copy {}[] into {}[]",
+            array_type_name(src_array), array_type_name(dest_array))));
+    }
+    let out_of_bounds = |message: String| Err(Throwable::new(JAVA_LANG_ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, message));
+    if src_pos < 0 {
+        return out_of_bounds(format!("arraycopy: source index {} out of bounds for {}[{}]", src_pos, array_type_name(src_array), src_array.length()));
+    }
+    if dest_pos < 0 {
+        return out_of_bounds(format!("arraycopy: destination index {} out of bounds for {}[{}]", dest_pos, array_type_name(dest_array), dest_array.length()));
+    }
+    if length < 0 {
+        return out_of_bounds(format!("arraycopy: length {} is negative", length));
+    }
+    let (src_pos, dest_pos, length) = (src_pos as usize, dest_pos as usize, length as usize);
+    if src_pos + length > src_array.length() {
+        return out_of_bounds(format!("arraycopy: last source index {} out of bounds for {}[{}]",
+            src_pos + length, array_type_name(src_array), src_array.length()));
+    }
+    if dest_pos + length > dest_array.length() {
+        return out_of_bounds(format!("arraycopy: last destination index {} out of bounds for {}[{}]",
+            dest_pos + length, array_type_name(dest_array), dest_array.length()));
+    }
+    // the elements are read first, the arrays may be the same
+    let elements: Vec<Value> = (src_pos..src_pos + length).map(|index| src_array.load(index)).collect();
+    let component = dest.class.component_type.as_deref().and_then(|component| component.as_class().cloned());
+    for (index, element) in elements.into_iter().enumerate() {
+        if let Some(component) = &component {
+            if element.as_reference().object().is_some_and(|object| !object.class.is_assignable_to(component)) {
+                return Err(Throwable::new(JAVA_LANG_ARRAY_STORE_EXCEPTION, format!(
+                    "arraycopy: element type mismatch: can not cast one of the elements of {}[] to the type of the destination array, {}",
+                    src.class.component_type.as_deref().and_then(Type::as_class).map_or("java.lang.Object".to_string(), |class| class.name.replace('/', ".")),
+                    component.name.replace('/', "."))));
+            }
+        }
+        vm.heap.write_barrier(dest, &element);
+        dest_array.store(dest_pos + index, element);
+    }
+    Ok(None)
+}
+
+/// `int` or `object array` as in the messages of System.arraycopy
+fn array_type_name(array: &Array) -> &'static str {
+    match array {
+        Array::Boolean(_) => "boolean",
+        Array::Byte(_) => "byte",
+        Array::Char(_) => "char",
+        Array::Short(_) => "short",
+        Array::Int(_) => "int",
+        Array::Long(_) => "long",
+        Array::Float(_) => "float",
+        Array::Double(_) => "double",
+        Array::Reference(_) => "object array"
+    }
+}
+
+/// record the frames of the thread in the `backtrace` of the throwable, without the ones creating it: fillInStackTrace
+/// and the constructors of its class and superclasses
+fn throwable_fill_in_stack_trace(vm: &mut Vm, thread: &mut JavaThread, args: Vec<Value>) -> Result<Option<Value>, Throwable> {
+    let throwable = object_arg(&args, 0)?;
+    let backtrace = backtrace(thread.frames.iter().rev()
+        .skip_while(|frame| frame.method.name() == "fillInStackTrace")
+        .skip_while(|frame| frame.method.name() == OBJECT_INITIALIZER_NAME && throwable.class.is_subclass_of(&frame.method.class())));
+    let depth = Value::Int((backtrace.len() / 3) as i32);
+    let array_class = vm.load_class(BOOTSTRAP_LOADER, "[I")?;
+    let backtrace = Value::Reference(vm.heap.allocate(Object::from_array(array_class, Array::Int(RefCell::new(backtrace.into()))))?);
+    let throwable = object_arg(&args, 0)?;
+    let field = |name, descriptor| throwable.class.find_field(name, descriptor).unwrap_or_else(|| panic!("java.lang.Throwable#{}", name));
+    vm.heap.write_barrier(throwable, &backtrace);
+    throwable.set_field(&field("backtrace", "Ljava/lang/Object;"), backtrace);
+    throwable.set_field(&field("depth", JVM_SIGNATURE_INT), depth);
+    Ok(Some(args[0].clone()))
+}
@@ -1,12 +1,360 @@
+use std::cell::{Cell, RefCell};
+use std::convert::TryInto;
 use std::rc::Rc;
-use crate::values::Value;
-use crate::types::{Type, Class};
+use crate::constants::*;
+use crate::values::{Reference, Value};
+use crate::types::{Class, Field};
 
+/// an instance or an array, a header followed by its variables
 pub struct Object {
-    pub(crate) hash_code: i32,
+    // ---- the header -------
     pub(crate) class: Rc<Class>,
-    // TODO monitor
-    pub(crate) slots: Vec<Box<Value>>
+    pub(crate) hash_code: i32,
+    // the monitor, the id of the owning thread and the recursion count, 0 when unlocked
+    #[allow(dead_code)]
+    pub(crate) lock_word: Cell<u64>,
+    // the last collection which found the object reachable
+    pub(crate) mark: Cell<u32>,
+    // the collections survived in the young generation
+    pub(crate) age: Cell<u8>,
+
+    pub(crate) body: Body
+}
+
+pub enum Body {
+    Instance(Fields),
+    Array(Array)
+}
+
+/// the instance variables at the offsets of their fields, the primitives packed at their natural sizes
+/// and the references grouped apart
+pub struct Fields {
+    primitives: RefCell<Box<[u8]>>,
+    references: RefCell<Box<[Reference]>>
+}
+
+/// the elements of an array unboxed by component type
+pub enum Array {
+    // baload and bastore access both boolean and byte arrays, a boolean is stored as 0 or 1
+    Boolean(RefCell<Box<[i8]>>),
+    Byte(RefCell<Box<[i8]>>),
+    Char(RefCell<Box<[u16]>>),
+    Short(RefCell<Box<[i16]>>),
+    Int(RefCell<Box<[i32]>>),
+    Long(RefCell<Box<[i64]>>),
+    Float(RefCell<Box<[f32]>>),
+    Double(RefCell<Box<[f64]>>),
+    Reference(RefCell<Box<[Reference]>>)
 }
 
+fn elements<T: Clone>(value: T, length: usize) -> RefCell<Box<[T]>> {
+    RefCell::new(vec![value; length].into_boxed_slice())
+}
+
+impl Array {
+    /// `length` elements of `component_descriptor` set to their default value
+    pub fn new(component_descriptor: &str, length: usize) -> Self {
+        match &component_descriptor[..1] {
+            JVM_SIGNATURE_BOOLEAN => Array::Boolean(elements(0, length)),
+            JVM_SIGNATURE_BYTE => Array::Byte(elements(0, length)),
+            JVM_SIGNATURE_CHAR => Array::Char(elements(0, length)),
+            JVM_SIGNATURE_SHORT => Array::Short(elements(0, length)),
+            JVM_SIGNATURE_INT => Array::Int(elements(0, length)),
+            JVM_SIGNATURE_LONG => Array::Long(elements(0, length)),
+            JVM_SIGNATURE_FLOAT => Array::Float(elements(0.0, length)),
+            JVM_SIGNATURE_DOUBLE => Array::Double(elements(0.0, length)),
+            _ => Array::Reference(elements(Reference::null(), length))
+        }
+    }
+
+    pub fn length(&self) -> usize {
+        match self {
+            Array::Boolean(elements) | Array::Byte(elements) => elements.borrow().len(),
+            Array::Char(elements) => elements.borrow().len(),
+            Array::Short(elements) => elements.borrow().len(),
+            Array::Int(elements) => elements.borrow().len(),
+            Array::Long(elements) => elements.borrow().len(),
+            Array::Float(elements) => elements.borrow().len(),
+            Array::Double(elements) => elements.borrow().len(),
+            Array::Reference(elements) => elements.borrow().len()
+        }
+    }
 
+    /// the element at `index`, which is within bounds
+    pub fn load(&self, index: usize) -> Value {
+        match self {
+            Array::Boolean(elements) | Array::Byte(elements) => Value::Int(elements.borrow()[index] as i32),
+            Array::Char(elements) => Value::Int(elements.borrow()[index] as i32),
+            Array::Short(elements) => Value::Int(elements.borrow()[index] as i32),
+            Array::Int(elements) => Value::Int(elements.borrow()[index]),
+            Array::Long(elements) => Value::Long(elements.borrow()[index]),
+            Array::Float(elements) => Value::Float(elements.borrow()[index]),
+            Array::Double(elements) => Value::Double(elements.borrow()[index]),
+            Array::Reference(elements) => Value::Reference(elements.borrow()[index].clone())
+        }
+    }
+
+    /// store to the element at `index`, which is within bounds, an int is truncated to the component type
+    pub fn store(&self, index: usize, value: Value) {
+        match self {
+            Array::Boolean(elements) => elements.borrow_mut()[index] = (value.as_int() & 1) as i8,
+            Array::Byte(elements) => elements.borrow_mut()[index] = value.as_int() as i8,
+            Array::Char(elements) => elements.borrow_mut()[index] = value.as_int() as u16,
+            Array::Short(elements) => elements.borrow_mut()[index] = value.as_int() as i16,
+            Array::Int(elements) => elements.borrow_mut()[index] = value.as_int(),
+            Array::Long(elements) => elements.borrow_mut()[index] = value.as_long(),
+            Array::Float(elements) => elements.borrow_mut()[index] = value.as_float(),
+            Array::Double(elements) => elements.borrow_mut()[index] = value.as_double(),
+            Array::Reference(elements) => elements.borrow_mut()[index] = value.as_reference().clone()
+        }
+    }
+}
+
+/// the size in bytes of a variable of a primitive type, none for a reference
+pub fn primitive_size(descriptor: &str) -> Option<usize> {
+    match &descriptor[..1] {
+        JVM_SIGNATURE_BOOLEAN | JVM_SIGNATURE_BYTE => Some(1),
+        JVM_SIGNATURE_CHAR | JVM_SIGNATURE_SHORT => Some(2),
+        JVM_SIGNATURE_INT | JVM_SIGNATURE_FLOAT => Some(4),
+        JVM_SIGNATURE_LONG | JVM_SIGNATURE_DOUBLE => Some(8),
+        _ => None
+    }
+}
+
+/// the offsets of the instance fields of `descriptors` following `primitives_size` bytes of primitive fields and
+/// `references_count` reference fields of the superclasses, along with the grown sizes.
+/// The primitives are ordered from the widest so that each is aligned to its size with padding only before the first one.
+pub fn layout_fields(descriptors: &[&str], primitives_size: usize, references_count: usize) -> (Vec<usize>, usize, usize) {
+    let mut offsets = vec![0; descriptors.len()];
+    let mut primitives_size = primitives_size;
+    for size in [8, 4, 2, 1] {
+        for (offset, descriptor) in offsets.iter_mut().zip(descriptors) {
+            if primitive_size(descriptor) == Some(size) {
+                primitives_size = primitives_size.next_multiple_of(size);
+                *offset = primitives_size;
+                primitives_size += size;
+            }
+        }
+    }
+    let mut references_count = references_count;
+    for (offset, descriptor) in offsets.iter_mut().zip(descriptors) {
+        if primitive_size(descriptor).is_none() {
+            *offset = references_count;
+            references_count += 1;
+        }
+    }
+    (offsets, primitives_size, references_count)
+}
+
+// the bytes of the header as HotSpot's with compressed class pointers, and of a reference variable
+pub const HEADER_SIZE: usize = 16;
+pub const REFERENCE_SIZE: usize = 8;
+
+/// the bytes an object takes in the heap
+pub fn instance_size(class: &Class) -> usize {
+    HEADER_SIZE + class.primitive_fields_size + class.reference_fields_count * REFERENCE_SIZE
+}
+
+/// the bytes an array of `length` elements of `component_descriptor` takes in the heap, saturating for an impossible size
+pub fn array_size(component_descriptor: &str, length: usize) -> usize {
+    let element_size = primitive_size(component_descriptor).unwrap_or(REFERENCE_SIZE);
+    HEADER_SIZE.saturating_add(element_size.saturating_mul(length))
+}
+
+fn read<const N: usize>(bytes: &[u8], offset: usize) -> [u8; N] {
+    bytes[offset..offset + N].try_into().expect("field within the object")
+}
+
+thread_local! {
+    static HASH_STATE: Cell<(u32, u32, u32, u32)> = const { Cell::new((0x9e3779b9, 842502087, 0x8767, 273326509)) };
+}
+
+/// the identity hash code, Marsaglia's xor-shift as HotSpot's default `-XX:hashCode=5`
+fn next_hash_code() -> i32 {
+    HASH_STATE.with(|state| {
+        let (x, y, z, w) = state.get();
+        let t = x ^ (x << 11);
+        let v = (w ^ (w >> 19)) ^ (t ^ (t >> 8));
+        state.set((y, z, w, v));
+        // the hash code is 31 bits in the mark word and never 0
+        match (v & 0x7fff_ffff) as i32 {
+            0 => 0xbad,
+            hash_code => hash_code
+        }
+    })
+}
+
+impl Object {
+    /// a new instance of `class` with every instance variable, including the inherited ones, set to its default value
+    pub fn new(class: Rc<Class>) -> Self {
+        let body = Body::Instance(Fields {
+            // zero is the default of every primitive type
+            primitives: RefCell::new(vec![0; class.primitive_fields_size].into_boxed_slice()),
+            references: RefCell::new(vec![Reference::null(); class.reference_fields_count].into_boxed_slice())
+        });
+        Object {
+            class,
+            hash_code: next_hash_code(),
+            lock_word: Cell::new(0),
+            mark: Cell::new(0),
+            age: Cell::new(0),
+            body
+        }
+    }
+
+    /// a new array of the array class `class` with `length` elements set to the default value of its component type
+    pub fn new_array(class: Rc<Class>, length: usize) -> Self {
+        let array = Array::new(&class.name[1..], length);
+        Object::from_array(class, array)
+    }
+
+    /// an array of the array class `class` with the given elements
+    pub fn from_array(class: Rc<Class>, array: Array) -> Self {
+        Object {
+            class,
+            hash_code: next_hash_code(),
+            lock_word: Cell::new(0),
+            mark: Cell::new(0),
+            age: Cell::new(0),
+            body: Body::Array(array)
+        }
+    }
+
+    fn fields(&self) -> &Fields {
+        match &self.body {
+            Body::Instance(fields) => fields,
+            Body::Array(_) => panic!("an array has no fields")
+        }
+    }
+
+    /// the value of an instance variable, read at the offset of its field
+    pub fn get_field(&self, field: &Field) -> Value {
+        let Fields { primitives, references } = self.fields();
+        let offset = field.offset();
+        let primitives = primitives.borrow();
+        match &field.descriptor()[..1] {
+            JVM_SIGNATURE_BOOLEAN | JVM_SIGNATURE_BYTE => Value::Int(primitives[offset] as i8 as i32),
+            JVM_SIGNATURE_CHAR => Value::Int(u16::from_ne_bytes(read(&primitives, offset)) as i32),
+            JVM_SIGNATURE_SHORT => Value::Int(i16::from_ne_bytes(read(&primitives, offset)) as i32),
+            JVM_SIGNATURE_INT => Value::Int(i32::from_ne_bytes(read(&primitives, offset))),
+            JVM_SIGNATURE_FLOAT => Value::Float(f32::from_ne_bytes(read(&primitives, offset))),
+            JVM_SIGNATURE_LONG => Value::Long(i64::from_ne_bytes(read(&primitives, offset))),
+            JVM_SIGNATURE_DOUBLE => Value::Double(f64::from_ne_bytes(read(&primitives, offset))),
+            _ => Value::Reference(references.borrow()[offset].clone())
+        }
+    }
+
+    /// store to an instance variable, an int is truncated to the size of its field
+    pub fn set_field(&self, field: &Field, value: Value) {
+        let Fields { primitives, references } = self.fields();
+        let offset = field.offset();
+        let write = |bytes: &[u8]| primitives.borrow_mut()[offset..offset + bytes.len()].copy_from_slice(bytes);
+        match value {
+            Value::Int(value) => match primitive_size(field.descriptor()) {
+                Some(1) => write(&[value as u8]),
+                Some(2) => write(&(value as u16).to_ne_bytes()),
+                _ => write(&value.to_ne_bytes())
+            },
+            Value::Float(value) => write(&value.to_ne_bytes()),
+            Value::Long(value) => write(&value.to_ne_bytes()),
+            Value::Double(value) => write(&value.to_ne_bytes()),
+            Value::Reference(reference) => references.borrow_mut()[offset] = reference,
+            Value::ReturnAddress(_) | Value::Top => debug_assert!(false, "no variable holds a return address or top")
+        }
+    }
+
+    /// the elements of an array
+    pub fn array(&self) -> &Array {
+        match &self.body {
+            Body::Array(array) => array,
+            Body::Instance(_) => panic!("an instance has no elements")
+        }
+    }
+
+    pub fn array_length(&self) -> usize {
+        self.array().length()
+    }
+
+    /// the bytes the object takes in the heap
+    pub fn size(&self) -> usize {
+        match &self.body {
+            Body::Instance(_) => instance_size(&self.class),
+            Body::Array(array) => array_size(&self.class.name[1..], array.length())
+        }
+    }
+
+    /// visit the reference variables of this object, which may be updated to where a collector moved their objects
+    pub fn visit_references(&self, mut visit: impl FnMut(&mut Reference)) {
+        let references = match &self.body {
+            Body::Instance(fields) => &fields.references,
+            Body::Array(Array::Reference(elements)) => elements,
+            Body::Array(_) => return
+        };
+        references.borrow_mut().iter_mut().filter(|reference| !reference.is_null()).for_each(&mut visit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_builder::{test_vm, ClassBuilder};
+    use crate::class_loader::APP_LOADER;
+    use crate::vm::VmOptions;
+
+    #[test]
+    fn primitives_are_ordered_from_the_widest_and_aligned() {
+        let descriptors = ["B", "J", "Ljava/lang/String;", "I", "Z", "S", "[I", "D", "C", "F"];
+        assert_eq!(layout_fields(&descriptors, 0, 0), (vec![28, 0, 0, 16, 29, 24, 1, 8, 26, 20], 30, 2));
+        // after 5 bytes and a reference of the superclasses, the long is padded to 8
+        assert_eq!(layout_fields(&["I", "J", "B", "Ljava/lang/Object;"], 5, 1), (vec![16, 8, 20, 1], 21, 2));
+        assert_eq!(layout_fields(&[], 6, 1), (vec![], 6, 1));
+    }
+
+    #[test]
+    fn fields_follow_the_inherited_ones() {
+        let mut vm = test_vm(VmOptions::default());
+        let parent = ClassBuilder::new("Parent", Some(JAVA_LANG_OBJECT))
+            .field(0, "b", "B")
+            .field(0, "o", "Ljava/lang/Object;")
+            .field(0, "i", "I")
+            .field(ACC_STATIC, "s", "J")
+            .define(&mut vm, APP_LOADER);
+        let child = ClassBuilder::new("Child", Some("Parent"))
+            .field(0, "c", "C")
+            .field(0, "a", "[J")
+            .field(0, "l", "J")
+            .define(&mut vm, APP_LOADER);
+        let offset = |name: &str, descriptor: &str| child.find_field(name, descriptor).expect("field").offset();
+        assert_eq!((offset("i", "I"), offset("b", "B"), offset("o", "Ljava/lang/Object;")), (0, 4, 0));
+        assert_eq!((offset("l", "J"), offset("c", "C"), offset("a", "[J")), (8, 16, 1));
+        assert_eq!((parent.primitive_fields_size, parent.reference_fields_count), (5, 1));
+        assert_eq!((child.primitive_fields_size, child.reference_fields_count), (18, 2));
+        assert_eq!(instance_size(&child), HEADER_SIZE + 18 + 2 * REFERENCE_SIZE);
+    }
+
+    #[test]
+    fn each_primitive_width_is_read_as_written() {
+        let mut vm = test_vm(VmOptions::default());
+        let descriptors = ["Z", "B", "C", "S", "I", "F", "J", "D", "Ljava/lang/Object;"];
+        let class = descriptors.iter().enumerate()
+            .fold(ClassBuilder::new("C", Some(JAVA_LANG_OBJECT)), |builder, (index, descriptor)| builder.field(0, &format!("f{}", index), descriptor))
+            .define(&mut vm, APP_LOADER);
+        let object = Object::new(class.clone());
+        let field = |index: usize| class.find_field(&format!("f{}", index), descriptors[index]).expect("field");
+        // the ints are truncated to the size of their fields
+        let values = [Value::Int(0x101), Value::Int(0x180), Value::Int(-1), Value::Int(0x18000), Value::Int(i32::MIN),
+            Value::Float(-1.5), Value::Long(i64::MIN + 1), Value::Double(f64::MAX), Value::Reference(Reference::null())];
+        for (index, value) in values.iter().enumerate() {
+            object.set_field(&field(index), value.clone());
+        }
+        assert_eq!(object.get_field(&field(0)).as_int(), 1);
+        assert_eq!(object.get_field(&field(1)).as_int(), -128);
+        assert_eq!(object.get_field(&field(2)).as_int(), 0xffff);
+        assert_eq!(object.get_field(&field(3)).as_int(), -0x8000);
+        assert_eq!(object.get_field(&field(4)).as_int(), i32::MIN);
+        assert_eq!(object.get_field(&field(5)).as_float(), -1.5);
+        assert_eq!(object.get_field(&field(6)).as_long(), i64::MIN + 1);
+        assert_eq!(object.get_field(&field(7)).as_double(), f64::MAX);
+        assert!(object.get_field(&field(8)).as_reference().is_null());
+    }
+}
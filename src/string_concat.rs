@@ -0,0 +1,235 @@
+use std::rc::Rc;
+use crate::class_loader::BOOTSTRAP_LOADER;
+use crate::constants::*;
+use crate::dispatch::select_virtual;
+use crate::exception::Throwable;
+use crate::interpreter;
+use crate::thread::JavaThread;
+use crate::types::{parse_method_descriptor, Class, Constant};
+use crate::values::Value;
+use crate::vm::Vm;
+
+pub const JAVA_LANG_INVOKE_STRING_CONCAT_FACTORY: &str = "java/lang/invoke/StringConcatFactory";
+const JAVA_LANG_INVOKE_STRING_CONCAT_EXCEPTION: &str = "java/lang/invoke/StringConcatException";
+
+// the placeholders of a recipe for an argument and a constant
+const TAG_ARG: u16 = 0x0001;
+const TAG_CONST: u16 = 0x0002;
+
+enum Part {
+    // the text of the recipe and the constants in it
+    Literal(Vec<u16>),
+    // the index of the argument among the parameters of the call site
+    Argument(usize)
+}
+
+/// a string concatenation linked by StringConcatFactory, the arguments are formatted as `String.valueOf` does
+pub struct Concat {
+    parts: Vec<Part>,
+    parameter_descriptors: Vec<String>
+}
+
+/// StringConcatFactory.makeConcatWithConstants and makeConcat, which concatenates all arguments without a recipe
+pub fn link(caller: &Class, descriptor: &str, arguments: &[u16], with_constants: bool) -> Result<Concat, Throwable> {
+    let (parameter_descriptors, return_descriptor) = parse_method_descriptor(descriptor)
+        .ok_or_else(|| concat_error(format!("Invalid call site type {}", descriptor)))?;
+    if return_descriptor != "Ljava/lang/String;" {
+        return Err(concat_error(format!("The return type should be compatible with String, but it is {}", return_descriptor)));
+    }
+    let parameter_descriptors: Vec<String> = parameter_descriptors.into_iter().map(String::from).collect();
+    let mut parts = vec![];
+    let mut literal = vec![];
+    let recipe: Vec<u16> = match arguments.first().map(|&argument| &caller.constant_pool[argument as usize]) {
+        _ if !with_constants => vec![TAG_ARG; parameter_descriptors.len()],
+        Some(Constant::String(recipe)) => recipe.clone(),
+        _ => return Err(concat_error("The recipe should be a String constant".to_string()))
+    };
+    let mut constants = arguments.iter().skip(1);
+    for c in recipe {
+        match c {
+            TAG_ARG => {
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(literal));
+                    literal = vec![];
+                }
+                parts.push(Part::Argument(parts.iter().filter(|part| matches!(part, Part::Argument(_))).count()));
+            }
+            TAG_CONST => {
+                let constant = constants.next()
+                    .ok_or_else(|| concat_error("Insufficient number of constants".to_string()))?;
+                match &caller.constant_pool[*constant as usize] {
+                    Constant::String(chars) => literal.extend(chars),
+                    Constant::Integer(value) => literal.extend(value.to_string().encode_utf16()),
+                    Constant::Long(value) => literal.extend(value.to_string().encode_utf16()),
+                    Constant::Float(value) => literal.extend(float_to_string(*value).encode_utf16()),
+                    Constant::Double(value) => literal.extend(double_to_string(*value).encode_utf16()),
+                    _ => return Err(concat_error(format!("Unsupported constant at constant pool index {}", constant)))
+                }
+            }
+            c => literal.push(c)
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(Part::Literal(literal));
+    }
+    let argument_count = parts.iter().filter(|part| matches!(part, Part::Argument(_))).count();
+    if argument_count != parameter_descriptors.len() {
+        return Err(concat_error(format!("Mismatched number of concat arguments: recipe wants {} arguments, but signature provides {}",
+            argument_count, parameter_descriptors.len())));
+    }
+    if constants.next().is_some() {
+        return Err(concat_error("Excess constants".to_string()));
+    }
+    Ok(Concat { parts, parameter_descriptors })
+}
+
+impl Concat {
+    /// the new string of the arguments on the operand stack of `thread`. They stay there while objects other than
+    /// strings are converted by their `toString`, which may collect garbage.
+    pub fn invoke(&self, vm: &mut Vm, thread: &mut JavaThread) -> Result<Value, Throwable> {
+        let slot_count = |descriptor: &String| if descriptor == JVM_SIGNATURE_LONG || descriptor == JVM_SIGNATURE_DOUBLE { 2 } else { 1 };
+        let slots: usize = self.parameter_descriptors.iter().map(slot_count).sum();
+        let mut slot = thread.frame().stack.len() - slots;
+        let mut arguments = vec![];
+        for descriptor in &self.parameter_descriptors {
+            let value = thread.frame().stack[slot].clone();
+            slot += slot_count(descriptor);
+            arguments.push(match (descriptor.as_str(), value) {
+                (JVM_SIGNATURE_BOOLEAN, Value::Int(value)) => (if value != 0 { "true" } else { "false" }).encode_utf16().collect(),
+                (JVM_SIGNATURE_CHAR, Value::Int(value)) => vec![value as u16],
+                (_, Value::Int(value)) => value.to_string().encode_utf16().collect(),
+                (_, Value::Long(value)) => value.to_string().encode_utf16().collect(),
+                (_, Value::Float(value)) => float_to_string(value).encode_utf16().collect(),
+                (_, Value::Double(value)) => double_to_string(value).encode_utf16().collect(),
+                (_, value) => object_to_string(vm, thread, value)?
+            });
+        }
+        let chars: Vec<u16> = self.parts.iter().flat_map(|part| match part {
+            Part::Literal(literal) => literal,
+            Part::Argument(index) => &arguments[*index]
+        }).copied().collect();
+
+        thread.frame().pop_slots(slots);
+        vm.reserve_string(thread, chars.len())?;
+        Ok(Value::Reference(vm.new_string_utf16(&chars)?))
+    }
+}
+
+fn concat_error(message: String) -> Throwable {
+    Throwable::with_cause(JAVA_LANG_BOOTSTRAP_METHOD_ERROR, "bootstrap method initialization exception",
+        Throwable::new(JAVA_LANG_INVOKE_STRING_CONCAT_EXCEPTION, message))
+}
+
+/// `String.valueOf(Object)`: "null", the content of a string, or what `toString` of the object returns
+fn object_to_string(vm: &mut Vm, thread: &mut JavaThread, value: Value) -> Result<Vec<u16>, Throwable> {
+    let object = match value.as_reference().object() {
+        Some(object) => object,
+        None => return Ok("null".encode_utf16().collect())
+    };
+    if object.class.name == JAVA_LANG_STRING {
+        return Ok(vm.string_chars(object));
+    }
+    let object_class = vm.load_class(BOOTSTRAP_LOADER, JAVA_LANG_OBJECT)?;
+    let to_string = object_class.find_method("toString", "()Ljava/lang/String;").expect("java.lang.Object#toString");
+    let class: Rc<Class> = object.class.clone();
+    vm.link_class(&class)?;
+    let selected = select_virtual(&class, to_string)?;
+    let string = interpreter::invoke(vm, thread, &selected, vec![value])?.expect("result of toString");
+    Ok(match string.as_reference().object() {
+        Some(string) => vm.string_chars(string),
+        None => "null".encode_utf16().collect()
+    })
+}
+
+/// `Float.toString`: the shortest decimal that rounds to `value`, at least two digits
+pub fn float_to_string(value: f32) -> String {
+    if !value.is_finite() || value == 0.0 {
+        return special_to_string(value as f64);
+    }
+    let shortest = format!("{:e}", value);
+    let two_digits = format!("{:.1e}", value);
+    let digits = if two_digits.parse::<f32>() == Ok(value) { two_digits } else { shortest };
+    java_notation(&digits, (1e-3..1e7).contains(&value.abs()))
+}
+
+/// `Double.toString`: the shortest decimal that rounds to `value`, at least two digits. This is the specification of
+/// JDK 19, the FloatingDecimal of earlier JDKs prints a few values like 1.0E23 with more digits.
+pub fn double_to_string(value: f64) -> String {
+    if !value.is_finite() || value == 0.0 {
+        return special_to_string(value);
+    }
+    let shortest = format!("{:e}", value);
+    let two_digits = format!("{:.1e}", value);
+    let digits = if two_digits.parse::<f64>() == Ok(value) { two_digits } else { shortest };
+    java_notation(&digits, (1e-3..1e7).contains(&value.abs()))
+}
+
+fn special_to_string(value: f64) -> String {
+    match value {
+        value if value.is_nan() => "NaN".to_string(),
+        f64::INFINITY => "Infinity".to_string(),
+        f64::NEG_INFINITY => "-Infinity".to_string(),
+        value if value.is_sign_negative() => "-0.0".to_string(),
+        _ => "0.0".to_string()
+    }
+}
+
+/// rewrite Rust's scientific notation `-1.25e-5` as Java does: plain between 10^-3 and 10^7, `-1.25E-5` otherwise
+fn java_notation(scientific: &str, plain: bool) -> String {
+    let (sign, scientific) = match scientific.strip_prefix('-') {
+        Some(scientific) => ("-", scientific),
+        None => ("", scientific)
+    };
+    let (mantissa, exponent) = scientific.split_once('e').expect("scientific notation");
+    let exponent: i32 = exponent.parse().expect("exponent");
+    let digits: String = mantissa.chars().filter(|&c| c != '.').collect();
+    let digits = match digits.trim_end_matches('0') {
+        "" => "0",
+        trimmed => trimmed
+    };
+    if !plain {
+        let fraction = if digits.len() > 1 { &digits[1..] } else { "0" };
+        return format!("{}{}.{}E{}", sign, &digits[..1], fraction, exponent);
+    }
+    if exponent < 0 {
+        return format!("{}0.{}{}", sign, "0".repeat((-exponent - 1) as usize), digits);
+    }
+    let integer_length = exponent as usize + 1;
+    if digits.len() <= integer_length {
+        format!("{}{}{}.0", sign, digits, "0".repeat(integer_length - digits.len()))
+    } else {
+        format!("{}{}.{}", sign, &digits[..integer_length], &digits[integer_length..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubles_as_java_prints_them() {
+        let cases = [
+            (1.0, "1.0"), (-0.5, "-0.5"), (100.0, "100.0"), (1234567.0, "1234567.0"), (1.0e7, "1.0E7"),
+            (0.001, "0.001"), (0.0001, "1.0E-4"), (1.25e-5, "1.25E-5"), (0.1 + 0.2, "0.30000000000000004"),
+            (f64::MAX, "1.7976931348623157E308"), (f64::MIN_POSITIVE, "2.2250738585072014E-308"),
+            (f64::from_bits(1), "4.9E-324"), (1.0e23, "1.0E23"), (123456.789, "123456.789")
+        ];
+        for (value, expected) in cases {
+            assert_eq!(double_to_string(value), expected);
+        }
+        assert_eq!(double_to_string(f64::NAN), "NaN");
+        assert_eq!(double_to_string(f64::NEG_INFINITY), "-Infinity");
+        assert_eq!(double_to_string(-0.0), "-0.0");
+    }
+
+    #[test]
+    fn floats_as_java_prints_them() {
+        let cases = [
+            (1.0f32, "1.0"), (0.1, "0.1"), (3.4028235e38, "3.4028235E38"), (f32::from_bits(1), "1.4E-45"),
+            (1.0e-3, "0.001"), (9999999.0, "9999999.0"), (1.0e7, "1.0E7"), (-2.5e-4, "-2.5E-4")
+        ];
+        for (value, expected) in cases {
+            assert_eq!(float_to_string(value), expected);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use crate::class_loader::BOOTSTRAP_LOADER;
+use crate::constants::*;
+use crate::exception::Throwable;
+use crate::object::{array_size, instance_size, Array, Object};
+use crate::thread::JavaThread;
+use crate::values::{ObjectReference, Value};
+use crate::vm::Vm;
+
+// java.lang.String#coder, the bytes of `value` are either Latin-1 or UTF-16 in native byte order
+pub const LATIN1: i8 = 0;
+pub const UTF16: i8 = 1;
+
+/// the strings interned by `String.intern()` and the string literals, by their UTF-16 code units. The table is a root
+/// of the garbage collector, an interned string lives as long as the VM.
+#[derive(Default)]
+pub struct StringTable {
+    pub(crate) strings: HashMap<Vec<u16>, ObjectReference>
+}
+
+impl Vm {
+    /// a new java.lang.String of `value`, compacted to Latin-1 when possible
+    pub fn new_string(&mut self, value: &str) -> Result<ObjectReference, Throwable> {
+        let chars: Vec<u16> = value.encode_utf16().collect();
+        self.new_string_utf16(&chars)
+    }
+
+    /// a new java.lang.String of the UTF-16 code units `chars`, which may include unpaired surrogates
+    pub fn new_string_utf16(&mut self, chars: &[u16]) -> Result<ObjectReference, Throwable> {
+        let string_class = self.load_class(BOOTSTRAP_LOADER, JAVA_LANG_STRING)?;
+        let byte_array_class = self.load_class(BOOTSTRAP_LOADER, "[B")?;
+        let (coder, bytes): (i8, Vec<u8>) = if chars.iter().all(|&c| c <= 0xff) {
+            (LATIN1, chars.iter().map(|&c| c as u8).collect())
+        } else {
+            (UTF16, chars.iter().flat_map(|c| c.to_ne_bytes()).collect())
+        };
+        // String.hashCode(), cached as the string would
+        let hash = chars.iter().fold(0i32, |hash, &c| hash.wrapping_mul(31).wrapping_add(c as i32));
+        let array = Object::from_array(byte_array_class, Array::Byte(RefCell::new(bytes.into_iter().map(|byte| byte as i8).collect())));
+
+        let string = Object::new(string_class.clone());
+        let value_field = string_class.find_field("value", "[B").expect("java.lang.String#value");
+        let coder_field = string_class.find_field("coder", JVM_SIGNATURE_BYTE).expect("java.lang.String#coder");
+        string.set_field(&value_field, Value::Reference(self.heap.allocate(array)?));
+        string.set_field(&coder_field, Value::Int(coder as i32));
+        if let Some(hash_field) = string_class.find_field("hash", JVM_SIGNATURE_INT) {
+            string.set_field(&hash_field, Value::Int(hash));
+        }
+        // since JDK 13 a hash of zero is cached by a flag
+        if let (0, Some(hash_is_zero_field)) = (hash, string_class.find_field("hashIsZero", JVM_SIGNATURE_BOOLEAN)) {
+            string.set_field(&hash_is_zero_field, Value::Int(1));
+        }
+        self.heap.allocate(string)
+    }
+
+    /// a safepoint before a new string of at most `length` chars is allocated
+    pub fn reserve_string(&mut self, thread: &mut JavaThread, length: usize) -> Result<(), Throwable> {
+        let string_class = self.load_class(BOOTSTRAP_LOADER, JAVA_LANG_STRING)?;
+        self.reserve(thread, instance_size(&string_class) + array_size(JVM_SIGNATURE_BYTE, length.saturating_mul(2)))
+    }
+
+    /// the interned string of the UTF-16 code units `chars`, created the first time, as a string literal is (JLS 3.10.5)
+    pub fn intern(&mut self, thread: &mut JavaThread, chars: &[u16]) -> Result<ObjectReference, Throwable> {
+        if let Some(string) = self.string_table.strings.get(chars) {
+            return Ok(string.clone());
+        }
+        self.reserve_string(thread, chars.len())?;
+        let string = self.new_string_utf16(chars)?;
+        self.string_table.strings.insert(chars.to_vec(), string.clone());
+        Ok(string)
+    }
+
+    /// `String.intern()`: the interned string equal to `string`, which becomes the interned one if there is none
+    pub fn intern_string(&mut self, string: ObjectReference) -> ObjectReference {
+        let chars = self.string_chars(string.object().expect("non-null string"));
+        self.string_table.strings.entry(chars).or_insert(string).clone()
+    }
+
+    /// the content of a java.lang.String
+    pub fn string_value(&self, string: &Object) -> String {
+        String::from_utf16_lossy(&self.string_chars(string))
+    }
+
+    /// the UTF-16 code units of a java.lang.String
+    pub fn string_chars(&self, string: &Object) -> Vec<u16> {
+        let value_field = string.class.find_field("value", "[B").expect("java.lang.String#value");
+        let coder_field = string.class.find_field("coder", JVM_SIGNATURE_BYTE).expect("java.lang.String#coder");
+        let value = string.get_field(&value_field);
+        let bytes: Vec<u8> = match value.as_reference().object().map(|array| array.array()) {
+            Some(Array::Byte(bytes)) => bytes.borrow().iter().map(|&byte| byte as u8).collect(),
+            _ => return vec![]
+        };
+        match string.get_field(&coder_field).as_int() as i8 {
+            LATIN1 => bytes.iter().map(|&byte| byte as u16).collect(),
+            _ => bytes.chunks(2).map(|pair| u16::from_ne_bytes([pair[0], pair[1]])).collect()
+        }
+    }
+}
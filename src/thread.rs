@@ -0,0 +1,50 @@
+use crate::constants::JAVA_LANG_STACK_OVERFLOW_ERROR;
+use crate::exception::Throwable;
+use crate::frame::Frame;
+
+/// the identity of a java thread, the main thread is 1 as in `Thread.currentThread().getId()`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ThreadId(pub(crate) u64);
+
+pub const MAIN_THREAD_ID: ThreadId = ThreadId(1);
+
+// the frames a thread may have before a StackOverflowError is thrown
+pub const MAX_STACK_DEPTH: usize = 1024;
+
+pub struct JavaThread {
+    pub(crate) id: ThreadId,
+    pub(crate) name: String,
+    // the java virtual machine stack, the current frame is last
+    pub(crate) frames: Vec<Frame>
+}
+
+impl JavaThread {
+    pub fn new(id: ThreadId, name: &str) -> Self {
+        JavaThread {
+            id,
+            name: name.to_string(),
+            frames: vec![]
+        }
+    }
+
+    pub fn main() -> Self {
+        JavaThread::new(MAIN_THREAD_ID, "main")
+    }
+
+    pub fn push_frame(&mut self, frame: Frame) -> Result<(), Throwable> {
+        if self.frames.len() >= MAX_STACK_DEPTH {
+            return Err(Throwable::new(JAVA_LANG_STACK_OVERFLOW_ERROR, ""));
+        }
+        self.frames.push(frame);
+        Ok(())
+    }
+
+    pub fn pop_frame(&mut self) -> Option<Frame> {
+        self.frames.pop()
+    }
+
+    /// the frame of the method being executed
+    pub fn frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("current frame")
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::bytecode::*;
+use crate::constants::*;
+use crate::exception::Throwable;
+use crate::verifier::{Frame, MethodVerifier, VerificationType};
+use crate::verifier::VerificationType::*;
+
+// the subroutines an instruction is executed in, outermost first, identified by the offsets of their first instruction
+type Subroutines = Vec<u16>;
+
+/// the type state of the data-flow analysis, a frame and the subroutines of each instruction reached so far
+struct DataFlow {
+    frames: Vec<Option<Frame>>,
+    subroutines: Vec<Option<Subroutines>>,
+    // the local variables each subroutine stores to, the others are kept from the jsr on ret
+    modified: HashMap<u16, Vec<bool>>,
+    // the ret instructions reached in each subroutine
+    rets: HashMap<u16, BTreeSet<usize>>,
+    changed: BTreeSet<usize>
+}
+
+impl MethodVerifier<'_> {
+    /// JVMS 4.10.2: verify a method without a StackMapTable by inferring the frame of every instruction,
+    /// merging the frames where control flow joins until a fixed point is reached
+    pub(crate) fn infer_types(&mut self) -> Result<(), Throwable> {
+        let initial = Frame::initial(self.class, self.method).map_err(|reason| self.error(0, &reason))?;
+        self.check_handlers()?;
+        let code = self.code;
+        // the jsr instructions calling each subroutine
+        let mut callers: HashMap<u16, Vec<usize>> = HashMap::new();
+        for pc in (0..code.len()).filter(|&pc| self.is_instruction(pc)) {
+            if let Some(target) = self.jsr_target(pc) {
+                let target = self.branch_target(pc, target).map_err(|reason| self.error(pc, &reason))?;
+                callers.entry(target as u16).or_default().push(pc);
+            }
+        }
+
+        let mut flow = DataFlow {
+            frames: vec![None; code.len()],
+            subroutines: vec![None; code.len()],
+            modified: HashMap::new(),
+            rets: HashMap::new(),
+            changed: BTreeSet::new()
+        };
+        flow.frames[0] = Some(initial);
+        flow.subroutines[0] = Some(vec![]);
+        flow.changed.insert(0);
+
+        while let Some(pc) = flow.changed.pop_first() {
+            let frame = flow.frames[pc].clone().expect("frame of a changed instruction");
+            let subroutines = flow.subroutines[pc].clone().expect("subroutines of a changed instruction");
+            let length = self.instructions[pc].expect("instruction start");
+            let mut successors: Vec<(usize, Frame, Subroutines)> = vec![];
+            let mut handler_locals = vec![frame.locals.clone()];
+
+            if let Some(offset) = self.jsr_target(pc) {
+                let target = self.branch_target(pc, offset).map_err(|reason| self.error(pc, &reason))? as u16;
+                if subroutines.contains(&target) {
+                    return Err(self.error(pc, "Recursive call to jsr entry"));
+                }
+                let mut next = frame.clone();
+                next.push(ReturnAddress(target), self.method.max_stack()).map_err(|reason| self.error(pc, &reason))?;
+                let mut nested = subroutines.clone();
+                nested.push(target);
+                successors.push((target as usize, next, nested));
+                // the subroutine may already return, its ret instructions have a new return point now
+                if let Some(rets) = flow.rets.get(&target) {
+                    flow.changed.extend(rets);
+                }
+            } else if let Some(index) = self.ret_index(pc) {
+                let subroutine = match frame.local(index).map_err(|reason| self.error(pc, &reason))? {
+                    ReturnAddress(subroutine) => *subroutine,
+                    local => return Err(self.error(pc, &format!("Bad local variable type: expected return address but found {:?} at {}", local, index)))
+                };
+                if subroutines.last() != Some(&subroutine) {
+                    return Err(self.error(pc, "Illegal return from subroutine"));
+                }
+                flow.rets.entry(subroutine).or_default().insert(pc);
+                let modified = flow.modified.get(&subroutine).cloned().unwrap_or_default();
+                for &caller in callers.get(&subroutine).map_or(&[][..], |callers| &callers[..]) {
+                    let (caller_frame, caller_subroutines) = match (&flow.frames[caller], &flow.subroutines[caller]) {
+                        (Some(frame), Some(subroutines)) => (frame, subroutines.clone()),
+                        _ => continue
+                    };
+                    let locals = frame.locals.iter().zip(&caller_frame.locals).enumerate()
+                        .map(|(i, (returned, called))| if modified.get(i) == Some(&true) { returned.clone() } else { called.clone() })
+                        .collect();
+                    let next = Frame { locals, stack: frame.stack.clone(), flag_this_uninit: frame.flag_this_uninit };
+                    successors.push((caller + self.instructions[caller].expect("instruction start"), next, caller_subroutines));
+                }
+            } else {
+                let step = self.execute(pc, &frame).map_err(|reason| self.error(pc, &reason))?;
+                if let Some(next) = &step.next {
+                    // the local variables a subroutine stores to, including the halves of a long or double it overwrites
+                    let stored: Vec<usize> = (0..next.locals.len())
+                        .filter(|&i| next.locals[i] != frame.locals[i] || Some(i) == self.stored_local(pc))
+                        .collect();
+                    for subroutine in &subroutines {
+                        let modified = flow.modified.entry(*subroutine).or_insert_with(|| vec![false; next.locals.len()]);
+                        let mut grown = false;
+                        for &i in &stored {
+                            grown |= !modified[i];
+                            modified[i] = true;
+                        }
+                        if grown {
+                            if let Some(rets) = flow.rets.get(subroutine) {
+                                flow.changed.extend(rets);
+                            }
+                        }
+                    }
+                    if next.locals != frame.locals {
+                        handler_locals.push(next.locals.clone());
+                    }
+                }
+                for (target, target_frame) in step.targets {
+                    successors.push((target, target_frame, subroutines.clone()));
+                }
+                if let Some(next) = step.next {
+                    successors.push((pc + length, next, subroutines.clone()));
+                }
+            }
+
+            for handler in self.method.exceptions() {
+                if (handler.start_pc as usize..handler.end_pc as usize).contains(&pc) {
+                    for locals in &handler_locals {
+                        let handler_frame = Frame {
+                            locals: locals.clone(),
+                            stack: vec![self.catch_type(&handler.catch_type)],
+                            flag_this_uninit: frame.flag_this_uninit
+                        };
+                        successors.push((handler.handler_pc as usize, handler_frame, subroutines.clone()));
+                    }
+                }
+            }
+
+            for (target, frame, subroutines) in successors {
+                if target >= code.len() {
+                    return Err(self.error(pc, "Falling off the end of the code"));
+                }
+                self.merge_into(&mut flow, pc, target, frame, subroutines)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// merge the frame flowing from `pc` into the frame of `target`, which is visited again if it changed
+    fn merge_into(&mut self, flow: &mut DataFlow, pc: usize, target: usize, frame: Frame, subroutines: Subroutines) -> Result<(), Throwable> {
+        let merged = match &flow.frames[target] {
+            None => {
+                flow.subroutines[target] = Some(subroutines);
+                frame
+            }
+            Some(current) => {
+                if flow.subroutines[target].as_ref() != Some(&subroutines) {
+                    return Err(self.error(pc, &format!("Illegal subroutine structure at {}", target)));
+                }
+                if current.stack.len() != frame.stack.len() {
+                    return Err(self.error(pc, &format!("Inconsistent stack height {} != {}", frame.stack.len(), current.stack.len())));
+                }
+                let mut locals = vec![];
+                for (current, other) in current.locals.iter().zip(&frame.locals) {
+                    locals.push(self.merge_types(current, other)?);
+                }
+                let mut stack = vec![];
+                for (current, other) in current.stack.iter().zip(&frame.stack) {
+                    let merged = self.merge_types(current, other)?;
+                    if merged == Top {
+                        return Err(self.error(pc, &format!("Mismatched stack types {:?} and {:?} at {}", current, other, target)));
+                    }
+                    stack.push(merged);
+                }
+                let merged = Frame { locals, stack, flag_this_uninit: current.flag_this_uninit || frame.flag_this_uninit };
+                if &merged == current {
+                    return Ok(());
+                }
+                merged
+            }
+        };
+        flow.frames[target] = Some(merged);
+        flow.changed.insert(target);
+        Ok(())
+    }
+
+    /// the most specific type both types are assignable to, top if there is none
+    fn merge_types(&mut self, type1: &VerificationType, type2: &VerificationType) -> Result<VerificationType, Throwable> {
+        Ok(match (type1, type2) {
+            _ if type1 == type2 => type1.clone(),
+            (Null, Reference(_)) => type2.clone(),
+            (Reference(_), Null) => type1.clone(),
+            (Reference(name1), Reference(name2)) => Reference(self.common_super_class(name1, name2)?),
+            _ => Top
+        })
+    }
+
+    fn common_super_class(&mut self, name1: &str, name2: &str) -> Result<String, Throwable> {
+        let (array1, array2) = (name1.starts_with(JVM_SIGNATURE_ARRAY), name2.starts_with(JVM_SIGNATURE_ARRAY));
+        if array1 && array2 {
+            // arrays of references merge by their component types, arrays of different primitives are Objects
+            return match (VerificationType::of_descriptor(&name1[1..]), VerificationType::of_descriptor(&name2[1..])) {
+                (Reference(component1), Reference(component2)) => {
+                    let component = self.common_super_class(&component1, &component2)?;
+                    Ok(if component.starts_with(JVM_SIGNATURE_ARRAY) { format!("[{}", component) } else { format!("[L{};", component) })
+                }
+                _ => Ok(JAVA_LANG_OBJECT.to_string())
+            };
+        }
+        if array1 || array2 {
+            return Ok(JAVA_LANG_OBJECT.to_string());
+        }
+        let (class1, class2) = (self.load(name1)?, self.load(name2)?);
+        // interfaces are treated as Object
+        if class1.is_interface() || class2.is_interface() {
+            return Ok(JAVA_LANG_OBJECT.to_string());
+        }
+        let supers1: Vec<String> = std::iter::successors(Some(class1), |class| class.super_class.clone())
+            .map(|class| class.name.clone())
+            .collect();
+        Ok(std::iter::successors(Some(class2), |class| class.super_class.clone())
+            .find(|class| supers1.contains(&class.name))
+            .map_or_else(|| JAVA_LANG_OBJECT.to_string(), |class| class.name.clone()))
+    }
+
+    fn jsr_target(&self, pc: usize) -> Option<i32> {
+        match self.code[pc] {
+            JSR => Some(i2(self.code, pc + 1) as i32),
+            JSR_W => Some(i4(self.code, pc + 1)),
+            _ => None
+        }
+    }
+
+    fn ret_index(&self, pc: usize) -> Option<usize> {
+        match self.code[pc] {
+            RET => Some(u1(self.code, pc + 1) as usize),
+            WIDE if self.code[pc + 1] == RET => Some(u2(self.code, pc + 2) as usize),
+            _ => None
+        }
+    }
+
+    /// the local variable a store instruction writes
+    fn stored_local(&self, pc: usize) -> Option<usize> {
+        match self.code[pc] {
+            ISTORE..=ASTORE => Some(u1(self.code, pc + 1) as usize),
+            opcode @ ISTORE_0..=ASTORE_3 => Some(((opcode - ISTORE_0) % 4) as usize),
+            WIDE if (ISTORE..=ASTORE).contains(&self.code[pc + 1]) => Some(u2(self.code, pc + 2) as usize),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::class_builder::{test_vm, ClassBuilder, Code};
+    use crate::class_loader::APP_LOADER;
+    use crate::constants::*;
+    use crate::verifier::verify;
+    use crate::vm::VmOptions;
+
+    /// verify the static method `m` of a version 49 class C, with the classes A, B extends A and D extends A defined
+    fn verify_method(descriptor: &str, max_stack: u16, max_locals: u16, code: Vec<u8>) -> Result<(), String> {
+        let mut vm = test_vm(VmOptions::default());
+        ClassBuilder::new("A", Some(JAVA_LANG_OBJECT)).define(&mut vm, APP_LOADER);
+        ClassBuilder::new("B", Some("A")).define(&mut vm, APP_LOADER);
+        ClassBuilder::new("D", Some("A")).define(&mut vm, APP_LOADER);
+        let class = ClassBuilder::new("C", Some(JAVA_LANG_OBJECT))
+            .major_version(49)
+            .method(ACC_STATIC, "m", descriptor, Some(Code::new(max_stack, max_locals, code)))
+            .define(&mut vm, APP_LOADER);
+        verify(&mut vm, &class).map_err(|throwable| {
+            assert_eq!(throwable.class_name, JAVA_LANG_VERIFY_ERROR);
+            throwable.message
+        })
+    }
+
+    #[test]
+    fn subroutine_returns_after_its_jsr() {
+        // jsr 5, iload_0, ireturn; 5: astore_1, ret 1
+        assert_eq!(verify_method("(I)I", 1, 2, vec![0xa8, 0x00, 0x05, 0x1a, 0xac, 0x4c, 0xa9, 0x01]), Ok(()));
+        // the int in local 0 is overwritten by the return address before the subroutine returns
+        assert_eq!(verify_method("(I)I", 1, 1, vec![0xa8, 0x00, 0x05, 0x1a, 0xac, 0x4b, 0xa9, 0x00]),
+            Err("C.m(I)I @3: Bad local variable type: expected Integer but found ReturnAddress(5) at 0".to_string()));
+    }
+
+    #[test]
+    fn bad_subroutines_are_rejected() {
+        // ret 0 of an int
+        assert_eq!(verify_method("(I)V", 0, 1, vec![0xa9, 0x00]),
+            Err("C.m(I)V @0: Bad local variable type: expected return address but found Integer at 0".to_string()));
+        // jsr 4, return; 4: jsr 4, the subroutine calls itself
+        assert_eq!(verify_method("()V", 2, 0, vec![0xa8, 0x00, 0x04, 0xb1, 0xa8, 0x00, 0x00]),
+            Err("C.m()V @4: Recursive call to jsr entry".to_string()));
+    }
+
+    #[test]
+    fn references_merge_to_their_least_common_superclass() {
+        // iload_0, ifeq 8, aload_1, goto 9; 8: aload_2; 9: areturn
+        let code = vec![0x1a, 0x99, 0x00, 0x07, 0x2b, 0xa7, 0x00, 0x04, 0x2c, 0xb0];
+        assert_eq!(verify_method("(ILB;LD;)LA;", 1, 3, code.clone()), Ok(()));
+        assert_eq!(verify_method("(ILB;LD;)LB;", 1, 3, code),
+            Err("C.m(ILB;LD;)LB; @9: Bad type on operand stack: expected Reference(\"B\") but found Reference(\"A\")".to_string()));
+    }
+
+    #[test]
+    fn values_of_different_types_do_not_merge() {
+        // iload_0, ifeq 8, aload_1, goto 9; 8: iconst_0; 9: return
+        assert_eq!(verify_method("(ILB;)V", 1, 2, vec![0x1a, 0x99, 0x00, 0x07, 0x2b, 0xa7, 0x00, 0x04, 0x03, 0xb1]),
+            Err("C.m(ILB;)V @8: Mismatched stack types Reference(\"B\") and Integer at 9".to_string()));
+    }
+}
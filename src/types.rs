@@ -1,12 +1,19 @@
-use std::rc::Rc;
-use std::ptr::null;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
 use crate::constants::{FieldAccessFlag, JVM_SIGNATURE_CLASS, JVM_SIGNATURE_ENDCLASS, MethodAccessFlag};
 use crate::values::Value;
 use crate::class_file::*;
 use crate::constants::*;
+use crate::call_site::CallSite;
+use crate::exception::Throwable;
+use crate::class_loader::ClassLoaderId;
+use crate::method_area::ClassId;
+use crate::object::{layout_fields, Object};
+use crate::thread::ThreadId;
 use enum_as_inner::EnumAsInner;
 
-#[derive(EnumAsInner)]
+#[derive(EnumAsInner, Clone)]
 pub(crate) enum Type {
     Byte,
     Short,
@@ -16,7 +23,7 @@ pub(crate) enum Type {
     Float,
     Double,
     Boolean,
-    Class(Class)
+    Class(Rc<Class>)
 }
 
 impl Type {
@@ -34,7 +41,7 @@ impl Type {
         }
     }
 
-    fn descriptor(&self) -> String {
+    pub(crate) fn descriptor(&self) -> String {
         match self {
             Type::Byte => self.name(),
             Type::Short => self.name(),
@@ -44,147 +51,388 @@ impl Type {
             Type::Float => self.name(),
             Type::Double => self.name(),
             Type::Boolean => self.name(),
+            Type::Class(class) if class.is_array() => class.name.to_string(),
             Type::Class(class) =>  [JVM_SIGNATURE_CLASS, class.name.as_str(), JVM_SIGNATURE_ENDCLASS].concat()
         }
     }
+
+    /// the primitive type of a descriptor like `I`
+    pub fn primitive(descriptor: &str) -> Option<Type> {
+        match descriptor {
+            JVM_SIGNATURE_BYTE => Some(Type::Byte),
+            JVM_SIGNATURE_SHORT => Some(Type::Short),
+            JVM_SIGNATURE_CHAR => Some(Type::Char),
+            JVM_SIGNATURE_INT => Some(Type::Int),
+            JVM_SIGNATURE_LONG => Some(Type::Long),
+            JVM_SIGNATURE_FLOAT => Some(Type::Float),
+            JVM_SIGNATURE_DOUBLE => Some(Type::Double),
+            JVM_SIGNATURE_BOOLEAN => Some(Type::Boolean),
+            _ => None
+        }
+    }
+}
+
+/// a resolved method reference: the class it refers to and the method resolution finds
+pub type ResolvedMethod = (Rc<Class>, Rc<Method>);
+
+/// the methods selected for the methods an interface declares, in the same order
+pub struct ItableEntry {
+    pub(crate) interface: ClassId,
+    pub(crate) methods: Vec<Selection>
+}
+
+/// the method an invocation selects (JVMS 5.4.6)
+#[derive(Clone)]
+pub enum Selection {
+    Method(Rc<Method>),
+    // neither the class nor its superinterfaces implement the method
+    Missing,
+    // two of the maximally-specific superinterface methods are default methods
+    Conflict(Rc<Method>, Rc<Method>)
+}
+
+/// an entry of the BootstrapMethods attribute, the constant pool indexes of a MethodHandle and its static arguments
+pub struct BootstrapMethod {
+    pub(crate) method_handle: u16,
+    pub(crate) arguments: Vec<u16>
+}
+
+/// JVMS 5.5: a class is initialized once, by the first thread that needs it
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InitState {
+    Uninitialized,
+    BeingInitialized(ThreadId),
+    Initialized,
+    // the initialization failed, the class can not be used
+    Erroneous
 }
 
 pub struct Class {
+    pub(crate) id: ClassId,
     pub(crate) constant_pool: Vec<Constant>,
     pub(crate) name: String,
     pub(crate) access_flags: u16,
+    pub(crate) major_version: u16,
+    #[allow(dead_code)]
     pub(crate) super_class_name: String,
+    #[allow(dead_code)]
     pub(crate) interface_names: Vec<String>,
 
-    pub(crate) fields:       Vec<Field>,
-    pub(crate) methods:      Vec<Method>,
+    pub(crate) fields:       Vec<Rc<Field>>,
+    pub(crate) methods:      Vec<Rc<Method>>,
 
-    pub(crate) instance_vars_count: i32,
+    // instance fields include the ones inherited from the superclass, laid out after them
     pub(crate) instance_var_fields: Vec<Rc<Field>>,
-    pub(crate) static_vars_count:   i32,
+    // the instance layout: the bytes of the packed primitive fields and the number of reference fields
+    pub(crate) primitive_fields_size: usize,
+    pub(crate) reference_fields_count: usize,
+    #[allow(dead_code)]
+    pub(crate) static_vars_count:   usize,
     pub(crate) static_var_fields:   Vec<Rc<Field>>,
 
-    pub(crate) static_vars:        Vec<Value>,
+    pub(crate) static_vars:        RefCell<Vec<Value>>,
 
     pub(crate) source_file: String,
 
+    // the host of the nest of this class, empty if it hosts its own, and the members of the nest it hosts
+    pub(crate) nest_host_name: String,
+    pub(crate) nest_member_names: Vec<String>,
+
+    // referred to by the invokedynamic instructions of the class
+    pub(crate) bootstrap_methods: Vec<BootstrapMethod>,
+    // the method references of the constant pool resolved so far, by their index, with the classes they refer to
+    pub(crate) resolved_methods: RefCell<HashMap<u16, ResolvedMethod>>,
+
+    // the methods selected by invokevirtual, indexed by the vtable index of the resolved method, set once linked
+    pub(crate) vtable: OnceCell<Vec<Rc<Method>>>,
+    // the methods selected by invokeinterface for each superinterface, in the order of the methods it declares, set once linked
+    pub(crate) itable: OnceCell<Vec<ItableEntry>>,
+
     // ---- these fields are only for array class -------
-    pub(crate) component_type: Rc<Type>, // any type
-    pub(crate) element_type:   Rc<Type>, // must be not array type
-    pub(crate) dimensions:    i32,
+    pub(crate) component_type: Option<Rc<Type>>, // any type
+    pub(crate) element_type:   Option<Rc<Type>>, // must be not array type
+    pub(crate) dimensions:    usize,
 
     // status flags
+    #[allow(dead_code)]
     pub(crate) defined: bool, // once read from classfile
-    pub(crate) linked:  bool, // once resolve Ref symbols
-    pub(crate) initialized: i32, // once call <clinit>
+    pub(crate) linked:  Cell<bool>, // once verified
+    pub(crate) init_state: Cell<InitState>,
 
-    // after linked
-    pub(crate) super_class: Rc<Class>, // to be resolved
-    pub(crate) interfaces: Vec<Rc<Class>>, // to be resolved
+    // the java.lang.Class instance of this class, set once java.lang.Class is loaded
+    pub(crate) mirror: OnceCell<Box<Object>>,
 
-    // TODO
+    // a runtime class is identified by its name and defining loader
+    pub(crate) defining_loader: ClassLoaderId,
 
+    // loaded along with the class
+    pub(crate) super_class: Option<Rc<Class>>, // none only for java/lang/Object
+    pub(crate) interfaces: Vec<Rc<Class>>
 }
 
-fn uninitialized_class() -> Rc<Class> {
-    unsafe { Rc::<Class>::from_raw(null()) }
-}
-fn uninitialized_method() -> Rc<Method> {
-    unsafe { Rc::<Method>::from_raw(null()) }
-}
 
 impl Class {
-    pub(crate) fn from(classfile: &ClassFile) -> Self {
+    /// the fields and methods are created along with the class so that they can point back to it
+    pub(crate) fn from(id: ClassId, classfile: &ClassFile, defining_loader: ClassLoaderId, super_class: Option<Rc<Class>>, interfaces: Vec<Rc<Class>>) -> Rc<Self> {
+        Rc::new_cyclic(|class| Class::new(id, classfile, defining_loader, super_class, interfaces, class))
+    }
+
+    fn new(id: ClassId, classfile: &ClassFile, defining_loader: ClassLoaderId, super_class: Option<Rc<Class>>, interfaces: Vec<Rc<Class>>, class: &Weak<Class>) -> Self {
         let cp =  &classfile.constant_pool;
         let constant_pool_len = classfile.constant_pool_count as usize;
         let constant_pool= (0..constant_pool_len).map(|i| Constant::from(i, cp)).collect();
-        let fields = classfile.fields.iter().enumerate().map(|(i, field_info)|
-            Field {
-                access_flags: field_info.access_flags,
-                name: cp.resolve_utf8(field_info.name_index),
-                descriptor: cp.resolve_utf8(field_info.descriptor_index),
-                class: uninitialized_class(),
-                slot: i as u32
+
+        let name = cp.resolve_class(classfile.this_class);
+        let mut declared: Vec<(u16, String, String, u16)> = classfile.fields.iter().map(|field_info| (
+            field_info.access_flags,
+            cp.resolve_utf8(field_info.name_index),
+            cp.resolve_utf8(field_info.descriptor_index),
+            // index of the initial value of a static field
+            field_info.find_constant_value_attribute().map_or(0, |constant_value_attribute| constant_value_attribute.constant_value_index)
+        )).collect();
+        if name == JAVA_LANG_CLASS {
+            // the hidden field linking a mirror to its runtime class
+            declared.push((ACC_PRIVATE | ACC_SYNTHETIC, INJECTED_KLASS_FIELD_NAME.to_string(), JVM_SIGNATURE_LONG.to_string(), 0));
+        }
+
+        // preparation (JVMS 5.4.2): the instance fields are laid out after the ones of the superclass
+        let (primitive_fields_size, reference_fields_count) = super_class.as_ref()
+            .map_or((0, 0), |super_class| (super_class.primitive_fields_size, super_class.reference_fields_count));
+        let instance_descriptors: Vec<&str> = declared.iter()
+            .filter(|(access_flags, ..)| access_flags & ACC_STATIC == 0)
+            .map(|(_, _, descriptor, _)| descriptor.as_str())
+            .collect();
+        let (instance_offsets, primitive_fields_size, reference_fields_count) =
+            layout_fields(&instance_descriptors, primitive_fields_size, reference_fields_count);
+        let mut instance_offsets = instance_offsets.into_iter();
+        let mut instance_var_fields = super_class.as_ref().map_or(vec![], |super_class| super_class.instance_var_fields.clone());
+        let mut static_var_fields = vec![];
+        let fields: Vec<Rc<Field>> = declared.into_iter().map(|(access_flags, name, descriptor, constant_value_index)| {
+            let is_static = access_flags & ACC_STATIC != 0;
+            let field = Rc::new(Field {
+                access_flags,
+                name,
+                descriptor,
+                class: class.clone(),
+                constant_value_index,
+                offset: if is_static { static_var_fields.len() } else { instance_offsets.next().expect("offset of instance field") } as u32
+            });
+            if is_static {
+                static_var_fields.push(field.clone());
+            } else {
+                instance_var_fields.push(field.clone());
             }
-        ).collect();
+            field
+        }).collect();
+        let static_vars = static_var_fields.iter().map(|field| Value::default_of(&field.descriptor)).collect();
 
-        let methods = classfile.methods.iter().enumerate().map(|(i, method_info)| {
+        let methods = classfile.methods.iter().enumerate().map(|(index, method_info)| Rc::new_cyclic(|method| {
             let code_attribute = method_info.find_code_attribute();
-            let exceptions = code_attribute.exception_table.iter().map(|exception_entry|
+            let exceptions = code_attribute.map_or(vec![], |code_attribute| code_attribute.exception_table.iter().map(|exception_entry|
                 ExceptionHandler {
                     start_pc: exception_entry.start_pc,
                     end_pc: exception_entry.end_pc,
                     handler_pc: exception_entry.handle_pc,
-                    catch_type: cp.resolve_class(exception_entry.catch_type)
+                    // 0 catches any exception, e.g. finally
+                    catch_type: if exception_entry.catch_type == 0 { "".to_string() } else { cp.resolve_class(exception_entry.catch_type) }
                 }
-            ).collect();
-            let local_variable_table_attribute = code_attribute.find_local_variable_table_attribute();
-            let local_vars = local_variable_table_attribute.local_variable_table.iter().map(|local_variable_entry|
+            ).collect());
+            let local_variable_table_attribute = code_attribute.and_then(|code_attribute| code_attribute.find_local_variable_table_attribute());
+            let local_vars = local_variable_table_attribute.map_or(vec![], |local_variable_table_attribute| local_variable_table_attribute.local_variable_table.iter().map(|local_variable_entry|
                 LocalVariable {
-                    method: uninitialized_method(),
+                    method: method.clone(),
                     start_pc: local_variable_entry.start_pc,
                     length: local_variable_entry.length,
                     index: local_variable_entry.index,
                     name: cp.resolve_utf8(local_variable_entry.name_index),
                     descriptor: cp.resolve_utf8(local_variable_entry.descriptor_index)
                 }
-            ).collect();
-            let line_number_table_attribute = code_attribute.find_line_number_table_attribute();
-            let line_numbers = line_number_table_attribute.line_number_table.iter().map(|line_number_entry|
+            ).collect());
+            let line_number_table_attribute = code_attribute.and_then(|code_attribute| code_attribute.find_line_number_table_attribute());
+            let line_numbers = line_number_table_attribute.map_or(vec![], |line_number_table_attribute| line_number_table_attribute.line_number_table.iter().map(|line_number_entry|
                 LineNumber {
                     start_pc: line_number_entry.start_pc,
                     line_number: line_number_entry.line_number
                 }
-            ).collect();
+            ).collect());
 
             let descriptor = cp.resolve_utf8(method_info.descriptor_index);
-            //(vv,)v
-            assert!(descriptor.starts_with("("));
-            let params_and_return: Vec<&str> = descriptor.split(')').collect();
-            assert_eq!(params_and_return.len(), 2);
-            let mut params = params_and_return[0].to_string();
-            params.remove(0);
-            let parameter_descriptors = vec![params]; // TODO
-            let return_descriptor = params_and_return[1].to_string();
+            // a malformed descriptor is left to the verifier
+            let (parameter_descriptors, return_descriptor) = parse_method_descriptor(&descriptor)
+                .map_or((vec![], String::new()), |(parameters, return_descriptor)|
+                    (parameters.into_iter().map(String::from).collect(), return_descriptor.to_string()));
 
             Method {
                 access_flags: method_info.access_flags,
                 name: cp.resolve_utf8(method_info.name_index),
                 descriptor,
-                class: uninitialized_class(),
-                max_stack: code_attribute.max_stack as u32,
-                max_locals: code_attribute.max_locals as u32,
-                code: code_attribute.code.to_vec(),
+                class: class.clone(),
+                index,
+                vtable_index: OnceCell::new(),
+                call_sites: RefCell::new(HashMap::new()),
+                max_stack: code_attribute.map_or(0, |code_attribute| code_attribute.max_stack as u32),
+                max_locals: code_attribute.map_or(0, |code_attribute| code_attribute.max_locals as u32),
+                code: code_attribute.map_or(vec![], |code_attribute| code_attribute.code.to_vec()),
                 exceptions,
+                stack_map_frames: code_attribute.and_then(|code_attribute| code_attribute.find_stack_map_table_attribute())
+                    .map_or(vec![], |stack_map_table| stack_map_table.entries.clone()),
                 local_vars,
                 line_numbers,
                 parameter_descriptors,
                 return_descriptor
             }
-        }).collect();
+        })).collect();
         Class {
+            id,
             constant_pool,
-            name: cp.resolve_class(classfile.this_class),
-            access_flags: 0,
-            super_class_name: cp.resolve_class(classfile.super_class),
-            interface_names: classfile.interfaces.iter().map(|interface| cp.resolve_class(*interface)).collect(),
-            super_class: uninitialized_class(),
-            interfaces: vec![],
+            name,
+            access_flags: classfile.access_flags,
+            major_version: classfile.major_version,
+            super_class_name: classfile.super_class_name(),
+            interface_names: classfile.interface_names(),
+            defining_loader,
+            super_class,
+            interfaces,
             fields,
             methods,
-            instance_vars_count: 0,
-            instance_var_fields: vec![],
+            instance_var_fields,
+            primitive_fields_size,
+            reference_fields_count,
+            static_vars_count: static_var_fields.len(),
+            static_var_fields,
+            static_vars: RefCell::new(static_vars),
+            source_file: classfile.source_file(),
+            nest_host_name: classfile.nest_host_name(),
+            nest_member_names: classfile.nest_member_names(),
+            bootstrap_methods: classfile.bootstrap_methods().into_iter()
+                .map(|(method_handle, arguments)| BootstrapMethod { method_handle, arguments })
+                .collect(),
+            resolved_methods: RefCell::new(HashMap::new()),
+            vtable: OnceCell::new(),
+            itable: OnceCell::new(),
+            component_type: None,
+            element_type: None,
+            dimensions: 0,
+            defined: false,
+            linked: Cell::new(false),
+            init_state: Cell::new(InitState::Uninitialized),
+            mirror: OnceCell::new()
+        }
+    }
+
+    /// an array class (JVMS 5.3.3) has the members of Object, an array of a reference type is as accessible as its element class
+    pub(crate) fn array(id: ClassId, name: &str, defining_loader: ClassLoaderId, component_type: Type,
+                        object: Rc<Class>, interfaces: Vec<Rc<Class>>) -> Rc<Self> {
+        let (element_type, dimensions) = match &component_type {
+            Type::Class(component) if component.is_array() =>
+                (component.element_type.clone().expect("element type of array class"), component.dimensions + 1),
+            _ => (Rc::new(component_type.clone()), 1)
+        };
+        let access_flags = match element_type.as_ref() {
+            Type::Class(element) => element.access_flags & (ACC_PUBLIC | ACC_PRIVATE | ACC_PROTECTED),
+            _ => ACC_PUBLIC
+        } | ACC_FINAL | ACC_ABSTRACT;
+        Rc::new(Class {
+            id,
+            constant_pool: vec![],
+            name: name.to_string(),
+            access_flags,
+            major_version: object.major_version,
+            super_class_name: object.name.clone(),
+            interface_names: interfaces.iter().map(|interface| interface.name.clone()).collect(),
+            defining_loader,
+            instance_var_fields: object.instance_var_fields.clone(),
+            primitive_fields_size: object.primitive_fields_size,
+            reference_fields_count: object.reference_fields_count,
+            super_class: Some(object),
+            interfaces,
+            fields: vec![],
+            methods: vec![],
             static_vars_count: 0,
             static_var_fields: vec![],
-            static_vars: vec![],
+            static_vars: RefCell::new(vec![]),
             source_file: "".to_string(),
-            component_type: Rc::new(Type::Byte),
-            element_type: Rc::new(Type::Byte),
-            dimensions: 0,
-            defined: false,
-            linked: false,
-            initialized: 0
+            nest_host_name: "".to_string(),
+            nest_member_names: vec![],
+            bootstrap_methods: vec![],
+            resolved_methods: RefCell::new(HashMap::new()),
+            // an array class dispatches to the methods of Object
+            vtable: OnceCell::new(),
+            itable: OnceCell::new(),
+            component_type: Some(Rc::new(component_type)),
+            element_type: Some(element_type),
+            dimensions,
+            defined: true,
+            linked: Cell::new(true),
+            // there is no <clinit> to run
+            init_state: Cell::new(InitState::Initialized),
+            mirror: OnceCell::new()
+        })
+    }
+
+    /// the vtable set when the class is linked, an array class has the one of Object
+    pub fn vtable(&self) -> &[Rc<Method>] {
+        match &self.super_class {
+            Some(object) if self.is_array() => object.vtable(),
+            _ => self.vtable.get().expect("vtable of a linked class")
+        }
+    }
+
+    /// the itable set when the class is linked, the superinterfaces of an array class declare no methods
+    pub fn itable(&self) -> &[ItableEntry] {
+        match self.itable.get() {
+            None if self.is_array() => &[],
+            itable => itable.expect("itable of a linked class")
+        }
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.name.starts_with(JVM_SIGNATURE_ARRAY)
+    }
+
+    pub fn is_interface(&self) -> bool {
+        self.access_flags & ACC_INTERFACE != 0
+    }
+
+    /// the method declared by this class, inherited methods are not looked up
+    pub fn find_method(&self, name: &str, descriptor: &str) -> Option<Rc<Method>> {
+        self.methods.iter().find(|method| method.name == name && method.descriptor == descriptor).cloned()
+    }
+
+    /// the field declared by this class or its superclasses and superinterfaces (JVMS 5.4.3.2)
+    pub fn find_field(&self, name: &str, descriptor: &str) -> Option<Rc<Field>> {
+        self.fields.iter().find(|field| field.name == name && field.descriptor == descriptor).cloned()
+            .or_else(|| self.interfaces.iter().find_map(|interface| interface.find_field(name, descriptor)))
+            .or_else(|| self.super_class.as_ref().and_then(|super_class| super_class.find_field(name, descriptor)))
+    }
+
+    /// whether this class is `class` or a subclass of it
+    pub fn is_subclass_of(&self, class: &Class) -> bool {
+        self.id == class.id || self.super_class.as_ref().is_some_and(|super_class| super_class.is_subclass_of(class))
+    }
+
+    /// whether this class, its superclasses or superinterfaces implement the interface `interface`
+    pub fn implements(&self, interface: &Class) -> bool {
+        self.interfaces.iter().any(|implemented| implemented.id == interface.id || implemented.implements(interface))
+            || self.super_class.as_ref().is_some_and(|super_class| super_class.implements(interface))
+    }
+
+    /// whether a value of this class can be assigned to `class`, as checked by `checkcast` and `instanceof`
+    pub fn is_assignable_to(&self, class: &Class) -> bool {
+        if self.id == class.id {
+            return true;
+        }
+        if class.is_interface() {
+            return self.implements(class);
         }
+        if self.is_array() && class.is_array() {
+            return match (self.component_type.as_deref(), class.component_type.as_deref()) {
+                (Some(Type::Class(component)), Some(Type::Class(other))) => component.is_assignable_to(other),
+                // primitive array classes are distinct
+                _ => false
+            };
+        }
+        self.is_subclass_of(class)
     }
 }
 
@@ -194,24 +442,33 @@ pub struct Field {
     access_flags: FieldAccessFlag,
     name: String,
     descriptor: String,
-    class: Rc<Class>,
+    class: Weak<Class>,
+    // index of the initial value of a static field in constant pool, 0 if none
+    constant_value_index: u16,
     /**
-    index of instanceFields or staticFields
-    for instance fields, it is the global index considering superclass hierarchy
+    for an instance field, the byte offset among the primitive fields or the index among the reference fields of the object,
+    for a static field, the index in the static variables of its class
     */
-    slot: u32
+    offset: u32
 }
 
 pub struct Method {
     access_flags: MethodAccessFlag,
     name:        String,
     descriptor:  String,
-    class:       Rc<Class>,
+    class:       Weak<Class>,
+    // the position of the method in its class, and of its entry in the vtable of the class once linked
+    index:       usize,
+    pub(crate) vtable_index: OnceCell<usize>,
+    // the call sites of the invokedynamic instructions linked so far, or the error their linking failed with, by their pc
+    pub(crate) call_sites: RefCell<HashMap<usize, Result<Rc<CallSite>, Throwable>>>,
 
     max_stack:    u32,
     max_locals:   u32,
     code:        Vec<u8>,             //u4 code_length
     exceptions:  Vec<ExceptionHandler>, //u2 exception_table_length
+    stack_map_frames: Vec<StackMapFrame>,
+    #[allow(dead_code)]
     local_vars:   Vec<LocalVariable>,
     line_numbers: Vec<LineNumber>,
 
@@ -219,6 +476,149 @@ pub struct Method {
     return_descriptor:     String
 }
 
+// the method area owns the classes, a field or method can not outlive its class
+impl Field {
+    pub fn class(&self) -> Rc<Class> {
+        self.class.upgrade().expect("class of field has been dropped")
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn descriptor(&self) -> &str {
+        &self.descriptor
+    }
+
+    pub fn access_flags(&self) -> FieldAccessFlag {
+        self.access_flags
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.access_flags & ACC_STATIC != 0
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset as usize
+    }
+
+    pub fn constant_value_index(&self) -> u16 {
+        self.constant_value_index
+    }
+}
+
+impl Method {
+    pub fn class(&self) -> Rc<Class> {
+        self.class.upgrade().expect("class of method has been dropped")
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn descriptor(&self) -> &str {
+        &self.descriptor
+    }
+
+    pub fn access_flags(&self) -> MethodAccessFlag {
+        self.access_flags
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.access_flags & ACC_STATIC != 0
+    }
+
+    pub fn is_abstract(&self) -> bool {
+        self.access_flags & ACC_ABSTRACT != 0
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.access_flags & ACC_NATIVE != 0
+    }
+
+    pub fn code(&self) -> &[u8] {
+        &self.code
+    }
+
+    pub fn parameter_descriptors(&self) -> &[String] {
+        &self.parameter_descriptors
+    }
+
+    pub fn return_descriptor(&self) -> &str {
+        &self.return_descriptor
+    }
+
+    pub fn max_stack(&self) -> usize {
+        self.max_stack as usize
+    }
+
+    pub fn max_locals(&self) -> usize {
+        self.max_locals as usize
+    }
+
+    pub fn exceptions(&self) -> &[ExceptionHandler] {
+        &self.exceptions
+    }
+
+    pub fn stack_map_frames(&self) -> &[StackMapFrame] {
+        &self.stack_map_frames
+    }
+
+    #[allow(dead_code)]
+    pub fn line_numbers(&self) -> &[LineNumber] {
+        &self.line_numbers
+    }
+
+    /// the source line of the instruction at `pc`, from the nearest entry of the LineNumberTable starting at or before it
+    pub fn line_number(&self, pc: usize) -> Option<u16> {
+        self.line_numbers.iter()
+            .filter(|line_number| line_number.start_pc as usize <= pc)
+            .max_by_key(|line_number| line_number.start_pc)
+            .map(|line_number| line_number.line_number)
+    }
+}
+
+/// the length of the field descriptor at the start of `descriptor`, e.g. 1 of `I`, 3 of `[[J`, 18 of `Ljava/lang/Object;`
+pub fn field_descriptor_length(descriptor: &str) -> Option<usize> {
+    let dimensions = descriptor.bytes().take_while(|&b| b == b'[').count();
+    if dimensions > MAX_ARRAY_DIMENSIONS {
+        return None;
+    }
+    let rest = &descriptor[dimensions..];
+    let length = match rest.bytes().next()? {
+        b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => 1,
+        b'L' => {
+            let end = rest.find(';')?;
+            if end == 1 {
+                return None;
+            }
+            end + 1
+        }
+        _ => return None
+    };
+    Some(dimensions + length)
+}
+
+/// the parameter and return descriptors of a method descriptor, e.g. `["I", "[Ljava/lang/String;"]` and `V` of `(I[Ljava/lang/String;)V`
+pub fn parse_method_descriptor(descriptor: &str) -> Option<(Vec<&str>, &str)> {
+    let mut rest = descriptor.strip_prefix('(')?;
+    let mut parameters = vec![];
+    while !rest.starts_with(')') {
+        let length = field_descriptor_length(rest)?;
+        parameters.push(&rest[..length]);
+        rest = &rest[length..];
+    }
+    let return_descriptor = &rest[1..];
+    if return_descriptor != JVM_SIGNATURE_VOID && field_descriptor_length(return_descriptor)? != return_descriptor.len() {
+        return None;
+    }
+    Some((parameters, return_descriptor))
+}
+
 #[derive(EnumAsInner)]
 pub enum Constant {
     Unknown,
@@ -226,23 +626,32 @@ pub enum Constant {
     Long(i64),
     Float(f32),
     Double(f64),
+    #[allow(dead_code)]
     Utf8(String),
-    String(String),
+    // the UTF-16 code units, decoded from modified UTF-8
+    String(Vec<u16>),
+    #[allow(dead_code)]
     NameAndType{name: String, descriptor: String},
     Class(String),//Rc<Class>),
-    FieldRef(String, String),//{class: Rc<Class>, filed: Rc<Field>},
-    MethodRef(String, String),//{class: Rc<Class>, method: Rc<Method>}
-    MethodType(String)
+    FieldRef{class: String, name: String, descriptor: String},
+    MethodRef{class: String, name: String, descriptor: String},
+    InterfaceMethodRef{class: String, name: String, descriptor: String},
+    MethodType(String),
+    // reference_index is of a FieldRef, MethodRef or InterfaceMethodRef
+    MethodHandle{reference_kind: u8, reference_index: u16},
+    InvokeDynamic{bootstrap_method_attr_index: u16, name: String, descriptor: String},
+    #[allow(dead_code)]
+    Dynamic{bootstrap_method_attr_index: u16, name: String, descriptor: String}
 }
 
 impl Constant {
     fn from(index: usize, constant_pool: &ConstantPool) -> Self {
         match &constant_pool.0[index] {
             ConstantPoolInfo::Unknown => Constant::Unknown,
-            ConstantPoolInfo::Utf8(utf8_info) =>
+            ConstantPoolInfo::Utf8(_) =>
                 Constant::Utf8(constant_pool.resolve_utf8(index as u16)),
             ConstantPoolInfo::String(string_info) =>
-                Constant::String(constant_pool.resolve_utf8(string_info.string_index)),
+                Constant::String(constant_pool.resolve_utf16(string_info.string_index)),
             ConstantPoolInfo::Integer(integer_info) =>
                 Constant::Integer(i32::from_be_bytes(integer_info.bytes.to_be_bytes())),
             ConstantPoolInfo::Float(float_info) =>
@@ -260,21 +669,32 @@ impl Constant {
                 Constant::Class(constant_pool.resolve_utf8(class_info.name_index)),
             ConstantPoolInfo::FieldRef(field_ref) => {
                 let (name, descriptor) = constant_pool.resolve_name_and_type(field_ref.name_and_type_index);
-                Constant::FieldRef(name, descriptor)
+                Constant::FieldRef { class: constant_pool.resolve_class(field_ref.class_index), name, descriptor }
             }
             ConstantPoolInfo::MethodRef(method_ref) => {
                 let (name, descriptor) = constant_pool.resolve_name_and_type(method_ref.name_and_type_index);
-                Constant::MethodRef(name, descriptor)
+                Constant::MethodRef { class: constant_pool.resolve_class(method_ref.class_index), name, descriptor }
             }
             ConstantPoolInfo::MethodType(method_type) =>
                 Constant::MethodType(constant_pool.resolve_utf8(method_type.descriptor_index)),
-            // ConstantPoolInfo::InterfaceMethodRef(_) => {}
-            // ConstantPoolInfo::MethodHandle(_) => {}
-            // ConstantPoolInfo::InvokeDynamic(_) => {}
-            _ => {
-                println!("ignore constant");
-                Constant::Unknown
+            ConstantPoolInfo::InterfaceMethodRef(interface_method_ref) => {
+                let (name, descriptor) = constant_pool.resolve_name_and_type(interface_method_ref.name_and_type_index);
+                Constant::InterfaceMethodRef { class: constant_pool.resolve_class(interface_method_ref.class_index), name, descriptor }
             }
+            ConstantPoolInfo::MethodHandle(method_handle) =>
+                Constant::MethodHandle {
+                    reference_kind: method_handle.reference_kind,
+                    reference_index: method_handle.reference_index
+                },
+            ConstantPoolInfo::InvokeDynamic(invoke_dynamic) => {
+                let (name, descriptor) = constant_pool.resolve_name_and_type(invoke_dynamic.name_and_type_index);
+                Constant::InvokeDynamic { bootstrap_method_attr_index: invoke_dynamic.bootstrap_method_attr_index, name, descriptor }
+            }
+            ConstantPoolInfo::Dynamic(dynamic) => {
+                let (name, descriptor) = constant_pool.resolve_name_and_type(dynamic.name_and_type_index);
+                Constant::Dynamic { bootstrap_method_attr_index: dynamic.bootstrap_method_attr_index, name, descriptor }
+            }
+            _ => Constant::Unknown
         }
     }
 
@@ -282,14 +702,15 @@ impl Constant {
 }
 
 pub struct ExceptionHandler {
-    start_pc:   u16,
-    end_pc:     u16,
-    handler_pc: u16,
-    catch_type: String // index of constant pool: ClassRef
+    pub(crate) start_pc:   u16,
+    pub(crate) end_pc:     u16,
+    pub(crate) handler_pc: u16,
+    pub(crate) catch_type: String // index of constant pool: ClassRef
 }
 
+#[allow(dead_code)]
 pub struct LocalVariable {
-    method:     Rc<Method>,
+    method:     Weak<Method>,
     start_pc:    u16,
     length:     u16,
     index:      u16,
@@ -297,7 +718,14 @@ pub struct LocalVariable {
     descriptor: String
 }
 
+impl LocalVariable {
+    #[allow(dead_code)]
+    pub fn method(&self) -> Rc<Method> {
+        self.method.upgrade().expect("method of local variable has been dropped")
+    }
+}
+
 pub struct LineNumber {
-    start_pc:    u16,
-    line_number: u16
+    pub(crate) start_pc:    u16,
+    pub(crate) line_number: u16
 }
\ No newline at end of file
@@ -1,58 +1,136 @@
-use crate::types::{Type};
-use std::rc::Rc;
+use std::ptr::NonNull;
 use crate::object::Object;
+use crate::constants::*;
 
+/// a value in a local variable, on the operand stack or in a variable of a class or an object (JVMS 2.2 - 2.6).
+/// boolean, byte, char and short are held as int, truncated when stored to a variable of the narrower type.
+/// In local variables and on the operand stack a long or double takes two slots, the second of which is Top.
+#[derive(Clone)]
 pub enum Value {
-    Byte(i8),
-    Short(i16),
-    Char(u16),
     Int(i32),
     Long(i64),
     Float(f32),
     Double(f64),
-    Boolean(bool),
-    Reference(Reference)
+    Reference(Reference),
+    // the offset of the instruction after a jsr
+    ReturnAddress(usize),
+    // the second slot of a long or double, or a local variable which has not been assigned
+    Top
 }
 
 impl Value {
-    fn get_type(&self) -> Rc<Type> {
+    /// the default value of a variable of `descriptor` (JVMS 2.3, 2.4)
+    pub fn default_of(descriptor: &str) -> Value {
+        match &descriptor[..1] {
+            JVM_SIGNATURE_BYTE | JVM_SIGNATURE_SHORT | JVM_SIGNATURE_CHAR | JVM_SIGNATURE_INT | JVM_SIGNATURE_BOOLEAN => Value::Int(0),
+            JVM_SIGNATURE_LONG => Value::Long(0),
+            JVM_SIGNATURE_FLOAT => Value::Float(0.0),
+            JVM_SIGNATURE_DOUBLE => Value::Double(0.0),
+            _ => Value::Reference(Reference::null())
+        }
+    }
+
+    /// long and double take two slots of the local variables and the operand stack
+    pub fn is_category2(&self) -> bool {
+        matches!(self, Value::Long(_) | Value::Double(_))
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Long(_) => "long",
+            Value::Float(_) => "float",
+            Value::Double(_) => "double",
+            Value::Reference(_) => "reference",
+            Value::ReturnAddress(_) => "returnAddress",
+            Value::Top => "top"
+        }
+    }
+
+    // the verifier guarantees the kind of every value the interpreter reads, it is only checked in debug builds
+    fn mismatch<T>(&self, expected: &str, default: T) -> T {
+        debug_assert!(false, "{} expected but found {}", expected, self.kind());
+        default
+    }
+
+    pub fn as_int(&self) -> i32 {
+        match self {
+            Value::Int(value) => *value,
+            value => value.mismatch("int", 0)
+        }
+    }
+
+    pub fn as_long(&self) -> i64 {
         match self {
-            Value::Byte(_) => Rc::from(Type::Byte),
-            Value::Short(_) => Rc::from(Type::Short),
-            Value::Char(_) => Rc::from(Type::Char),
-            Value::Int(_) => Rc::from(Type::Int),
-            Value::Long(_) => Rc::from(Type::Long),
-            Value::Float(_) => Rc::from(Type::Float),
-            Value::Double(_) => Rc::from(Type::Double),
-            Value::Boolean(_) => Rc::from(Type::Boolean),
-            Value::Reference(reference) => unimplemented!() //&(reference.oop.unwrap()).class,
+            Value::Long(value) => *value,
+            value => value.mismatch("long", 0)
+        }
+    }
+
+    pub fn as_float(&self) -> f32 {
+        match self {
+            Value::Float(value) => *value,
+            value => value.mismatch("float", 0.0)
+        }
+    }
+
+    pub fn as_double(&self) -> f64 {
+        match self {
+            Value::Double(value) => *value,
+            value => value.mismatch("double", 0.0)
+        }
+    }
+
+    pub fn as_reference(&self) -> &Reference {
+        match self {
+            Value::Reference(reference) => reference,
+            value => value.mismatch("reference", NULL)
+        }
+    }
+
+    pub fn as_return_address(&self) -> usize {
+        match self {
+            Value::ReturnAddress(address) => *address,
+            value => value.mismatch("returnAddress", 0)
         }
     }
 }
 
+/// a reference to an object in the heap, or to a mirror which lives as long as its class.
+/// It does not own the object, which is only freed by the collector once no root reaches it. The collector runs at
+/// safepoints only, where every live reference is held by a root rather than by the VM itself.
+#[derive(Clone)]
 pub struct Reference {
-    oop: Option<Rc<Object>>
+    oop: Option<NonNull<Object>>
 }
 
 impl Reference {
-    fn is_null(&self) -> bool {
+    /// a reference to an object owned by the heap or a class
+    pub(crate) fn to(object: &Object) -> Self {
+        Reference { oop: Some(NonNull::from(object)) }
+    }
+
+    pub const fn null() -> Self {
+        Reference { oop: None }
+    }
+
+    pub fn is_null(&self) -> bool {
         self.oop.is_none()
     }
 
-    fn is_array(&self) -> bool {
-        unimplemented!()
+    /// the referenced object, none if null
+    pub fn object(&self) -> Option<&Object> {
+        // a reachable object is not freed, and a reference held across a safepoint is reachable
+        self.oop.map(|oop| unsafe { &*oop.as_ptr() })
     }
 
-    fn is_equal(&self, reference: &Reference) -> bool {
-        if self.is_null() && reference.is_null() {
-            return true
-        }
-        if !self.is_null() && !reference.is_null() {
-            return Rc::ptr_eq(
-                self.oop.as_ref().unwrap(),
-                reference.oop.as_ref().unwrap());
-        }
-        false
+    #[allow(dead_code)]
+    pub fn is_array(&self) -> bool {
+        self.object().is_some_and(|object| object.class.is_array())
+    }
+
+    pub fn is_equal(&self, reference: &Reference) -> bool {
+        self.oop == reference.oop
     }
 }
 
@@ -60,5 +138,5 @@ pub type ObjectReference = Reference;
 pub type ArrayReference = Reference;
 
 
-pub const NULL: &Reference = &Reference { oop: Option::None };
+pub const NULL: &Reference = &Reference::null();
 
@@ -0,0 +1,1170 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use crate::bytecode::*;
+use crate::class_file::{StackMapFrame, VerificationTypeInfo};
+use crate::constants::*;
+use crate::exception::Throwable;
+use crate::types::{field_descriptor_length, parse_method_descriptor, Class, Constant, Method};
+use crate::vm::Vm;
+
+const JAVA_LANG_THROWABLE: &str = "java/lang/Throwable";
+const JAVA_LANG_INVOKE_METHOD_TYPE: &str = "java/lang/invoke/MethodType";
+const JAVA_LANG_INVOKE_METHOD_HANDLE: &str = "java/lang/invoke/MethodHandle";
+
+/// the types of JVMS 4.10.1.2, a reference type is named by its class name or array descriptor
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum VerificationType {
+    Top,
+    Integer,
+    Float,
+    Long,
+    Double,
+    Null,
+    UninitializedThis,
+    // created by the `new` instruction at the offset
+    Uninitialized(u16),
+    Reference(String),
+    // pushed by jsr, only known to the type inference verifier
+    ReturnAddress(u16)
+}
+
+use VerificationType::*;
+
+impl VerificationType {
+    /// the type of a value of a field descriptor, the types smaller than int are int
+    pub fn of_descriptor(descriptor: &str) -> VerificationType {
+        match &descriptor[..1] {
+            JVM_SIGNATURE_BYTE | JVM_SIGNATURE_CHAR | JVM_SIGNATURE_SHORT | JVM_SIGNATURE_BOOLEAN | JVM_SIGNATURE_INT => Integer,
+            JVM_SIGNATURE_FLOAT => Float,
+            JVM_SIGNATURE_LONG => Long,
+            JVM_SIGNATURE_DOUBLE => Double,
+            JVM_SIGNATURE_ARRAY => Reference(descriptor.to_string()),
+            _ => Reference(descriptor[1..descriptor.len() - 1].to_string())
+        }
+    }
+
+    /// long and double take two local variables and two words of the operand stack
+    pub fn is_category2(&self) -> bool {
+        matches!(self, Long | Double)
+    }
+
+    pub fn is_reference(&self) -> bool {
+        matches!(self, Null | UninitializedThis | Uninitialized(_) | Reference(_))
+    }
+
+    fn is_array(&self) -> bool {
+        matches!(self, Reference(name) if name.starts_with(JVM_SIGNATURE_ARRAY))
+    }
+}
+
+/// the component type of an array type, null for the null type
+fn component_of(array: &VerificationType) -> Option<VerificationType> {
+    match array {
+        Null => Some(Null),
+        Reference(name) if name.starts_with(JVM_SIGNATURE_ARRAY) => Some(VerificationType::of_descriptor(&name[1..])),
+        _ => None
+    }
+}
+
+/// the type state before an instruction: the locals are indexed by slot, a category 2 type is followed by top,
+/// while the operand stack has one entry per value
+#[derive(Clone, PartialEq, Debug)]
+pub struct Frame {
+    pub(crate) locals: Vec<VerificationType>,
+    pub(crate) stack: Vec<VerificationType>,
+    // `this` is not initialized yet in a constructor
+    pub(crate) flag_this_uninit: bool
+}
+
+impl Frame {
+    /// the frame on entry to a method, built from its descriptor (JVMS 4.10.1.6)
+    pub fn initial(class: &Class, method: &Method) -> Result<Frame, String> {
+        let mut locals = vec![];
+        let mut flag_this_uninit = false;
+        if !method.is_static() {
+            if method.name() == OBJECT_INITIALIZER_NAME && class.name != JAVA_LANG_OBJECT {
+                locals.push(UninitializedThis);
+                flag_this_uninit = true;
+            } else {
+                locals.push(Reference(class.name.clone()));
+            }
+        }
+        let (parameters, _) = parse_method_descriptor(method.descriptor()).ok_or("Illegal method descriptor")?;
+        for parameter in parameters {
+            let parameter = VerificationType::of_descriptor(parameter);
+            let category2 = parameter.is_category2();
+            locals.push(parameter);
+            if category2 {
+                locals.push(Top);
+            }
+        }
+        if locals.len() > method.max_locals() {
+            return Err("Arguments can't fit into locals".to_string());
+        }
+        locals.resize(method.max_locals(), Top);
+        Ok(Frame { locals, stack: vec![], flag_this_uninit })
+    }
+
+    /// the number of words on the operand stack
+    pub fn stack_size(&self) -> usize {
+        self.stack.iter().map(|value| if value.is_category2() { 2 } else { 1 }).sum()
+    }
+
+    fn set_local(&mut self, index: usize, value: VerificationType) -> Result<(), String> {
+        let category2 = value.is_category2();
+        if index + if category2 { 1 } else { 0 } >= self.locals.len() {
+            return Err(format!("Illegal local variable number {}", index));
+        }
+        // overwriting the second word of a long or double invalidates it
+        if index > 0 && self.locals[index - 1].is_category2() {
+            self.locals[index - 1] = Top;
+        }
+        self.locals[index] = value;
+        if category2 {
+            self.locals[index + 1] = Top;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn local(&self, index: usize) -> Result<&VerificationType, String> {
+        self.locals.get(index).ok_or_else(|| format!("Illegal local variable number {}", index))
+    }
+
+    fn pop(&mut self) -> Result<VerificationType, String> {
+        self.stack.pop().ok_or_else(|| "Operand stack underflow".to_string())
+    }
+
+    fn pop_category1(&mut self) -> Result<VerificationType, String> {
+        let value = self.pop()?;
+        if value.is_category2() {
+            return Err("Bad type on operand stack".to_string());
+        }
+        Ok(value)
+    }
+
+    pub(crate) fn push(&mut self, value: VerificationType, max_stack: usize) -> Result<(), String> {
+        self.stack.push(value);
+        if self.stack_size() > max_stack {
+            return Err("Operand stack overflow".to_string());
+        }
+        Ok(())
+    }
+
+    /// replace an uninitialized type once its constructor is invoked
+    fn initialize(&mut self, uninitialized: &VerificationType, initialized: VerificationType) {
+        for value in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+            if value == uninitialized {
+                *value = initialized.clone();
+            }
+        }
+        if *uninitialized == UninitializedThis {
+            self.flag_this_uninit = false;
+        }
+    }
+}
+
+/// the effect of an instruction on the type state
+pub(crate) struct Step {
+    // the frame after the instruction, none if it does not fall through
+    pub(crate) next: Option<Frame>,
+    // the branch targets and the frame on the branch
+    pub(crate) targets: Vec<(usize, Frame)>
+}
+
+/// the context of verifying a method, shared by the type checker and the type inference verifier
+pub(crate) struct MethodVerifier<'a> {
+    pub(crate) vm: &'a mut Vm,
+    pub(crate) class: &'a Rc<Class>,
+    pub(crate) method: &'a Method,
+    pub(crate) code: &'a [u8],
+    // the instruction starting at each offset, none inside an instruction
+    pub(crate) instructions: Vec<Option<usize>>
+}
+
+pub(crate) fn verify_error(class: &Class, method: &Method, pc: usize, reason: &str) -> Throwable {
+    Throwable::new(JAVA_LANG_VERIFY_ERROR, format!("{}.{}{} @{}: {}", class.name, method.name(), method.descriptor(), pc, reason))
+}
+
+/// verify the methods of `class` before it is linked. Class files of version 50 and above are type checked
+/// against their StackMapTable, older ones fall back to type inference.
+pub fn verify(vm: &mut Vm, class: &Rc<Class>) -> Result<(), Throwable> {
+    for method in &class.methods {
+        if method.is_abstract() || method.is_native() {
+            continue;
+        }
+        let mut verifier = MethodVerifier::new(vm, class, method)?;
+        match class.major_version {
+            major_version if major_version < 50 => verifier.infer_types()?,
+            // a version 50 class file failing the type check is verified again by type inference
+            50 => verifier.type_check().or_else(|_| verifier.infer_types())?,
+            _ => verifier.type_check()?
+        }
+    }
+    Ok(())
+}
+
+impl<'a> MethodVerifier<'a> {
+    pub(crate) fn new(vm: &'a mut Vm, class: &'a Rc<Class>, method: &'a Method) -> Result<Self, Throwable> {
+        let code = method.code();
+        if code.is_empty() || code.len() > 65535 {
+            return Err(verify_error(class, method, 0, "Invalid method Code length"));
+        }
+        let mut instructions = vec![None; code.len()];
+        let mut pc = 0;
+        while pc < code.len() {
+            let length = instruction_length(code, pc)
+                .ok_or_else(|| verify_error(class, method, pc, &format!("Bad instruction: {:02x}", code[pc])))?;
+            instructions[pc] = Some(length);
+            pc += length;
+        }
+        Ok(MethodVerifier { vm, class, method, code, instructions })
+    }
+
+    pub(crate) fn error(&self, pc: usize, reason: &str) -> Throwable {
+        verify_error(self.class, self.method, pc, reason)
+    }
+
+    pub(crate) fn is_instruction(&self, pc: usize) -> bool {
+        self.instructions.get(pc).is_some_and(|instruction| instruction.is_some())
+    }
+
+    /// JVMS 4.10.1: every instruction is checked once in code order against the frames of the StackMapTable
+    fn type_check(&mut self) -> Result<(), Throwable> {
+        let initial = Frame::initial(self.class, self.method).map_err(|reason| self.error(0, &reason))?;
+        let frames = self.stack_map_frames(&initial)?;
+        self.check_handlers()?;
+
+        let mut current = Some(initial);
+        let mut pc = 0;
+        while pc < self.code.len() {
+            if let Some(frame) = frames.get(&pc) {
+                if let Some(current) = &current {
+                    if !self.is_frame_assignable(current, frame)? {
+                        return Err(self.error(pc, "Instruction type does not match stack map"));
+                    }
+                }
+                current = Some(frame.clone());
+            }
+            let frame = current.take().ok_or_else(|| self.error(pc, "Expecting a stack map frame"))?;
+            self.check_handler_frames(pc, &frame, &frames)?;
+            let step = self.execute(pc, &frame).map_err(|reason| self.error(pc, &reason))?;
+            for (target, target_frame) in &step.targets {
+                match frames.get(target) {
+                    Some(frame) if self.is_frame_assignable(target_frame, frame)? => {}
+                    Some(_) => return Err(self.error(pc, &format!("Inconsistent stackmap frames at branch target {}", target))),
+                    None => return Err(self.error(pc, &format!("Expecting a stackmap frame at branch target {}", target)))
+                }
+            }
+            // a store changes the locals seen by the handlers
+            if let Some(next) = &step.next {
+                if next.locals != frame.locals {
+                    self.check_handler_frames(pc, next, &frames)?;
+                }
+            }
+            current = step.next;
+            pc += self.instructions[pc].expect("instruction start");
+        }
+        if current.is_some() {
+            return Err(self.error(self.code.len(), "Falling off the end of the code"));
+        }
+        Ok(())
+    }
+
+    /// expand the compressed frames of the StackMapTable into full frames by offset (JVMS 4.7.4)
+    fn stack_map_frames(&mut self, initial: &Frame) -> Result<BTreeMap<usize, Frame>, Throwable> {
+        let max_locals = self.method.max_locals();
+        // the locals as listed in the StackMapTable, where a category 2 type is a single entry
+        let mut locals: Vec<VerificationType> = vec![];
+        let mut index = 0;
+        while index < initial.locals.len() && initial.locals[index] != Top {
+            locals.push(initial.locals[index].clone());
+            index += if initial.locals[index].is_category2() { 2 } else { 1 };
+        }
+        let mut frames = BTreeMap::new();
+        let mut offset: Option<usize> = None;
+        for stack_map_frame in self.method.stack_map_frames() {
+            let pc = offset.map_or(0, |offset| offset + 1) + stack_map_frame.offset_delta() as usize;
+            offset = Some(pc);
+            let stack = match stack_map_frame {
+                StackMapFrame::Same { .. } => vec![],
+                StackMapFrame::SameLocals1StackItem { stack, .. } => vec![self.verification_type(pc, stack)?],
+                StackMapFrame::Chop { k, .. } => {
+                    if (*k as usize) > locals.len() {
+                        return Err(self.error(pc, "Chop frame removes too many locals"));
+                    }
+                    locals.truncate(locals.len() - *k as usize);
+                    vec![]
+                }
+                StackMapFrame::Append { locals: appended, .. } => {
+                    for local in appended {
+                        locals.push(self.verification_type(pc, local)?);
+                    }
+                    vec![]
+                }
+                StackMapFrame::Full { locals: full, stack, .. } => {
+                    locals = full.iter().map(|local| self.verification_type(pc, local)).collect::<Result<_, _>>()?;
+                    stack.iter().map(|value| self.verification_type(pc, value)).collect::<Result<_, _>>()?
+                }
+                StackMapFrame::Reserved(frame_type) => return Err(self.error(pc, &format!("reserved frame type {}", frame_type)))
+            };
+            if !self.is_instruction(pc) {
+                return Err(self.error(pc, "StackMapTable error: bad offset"));
+            }
+            let mut expanded = vec![];
+            for local in &locals {
+                expanded.push(local.clone());
+                if local.is_category2() {
+                    expanded.push(Top);
+                }
+            }
+            if expanded.len() > max_locals {
+                return Err(self.error(pc, "StackMapTable error: local variables exceed max locals"));
+            }
+            expanded.resize(max_locals, Top);
+            let flag_this_uninit = expanded.contains(&UninitializedThis) || stack.contains(&UninitializedThis);
+            let frame = Frame { locals: expanded, stack, flag_this_uninit };
+            if frame.stack_size() > self.method.max_stack() {
+                return Err(self.error(pc, "StackMapTable error: stack size exceeds max stack"));
+            }
+            frames.insert(pc, frame);
+        }
+        Ok(frames)
+    }
+
+    fn verification_type(&self, pc: usize, info: &VerificationTypeInfo) -> Result<VerificationType, Throwable> {
+        Ok(match info {
+            VerificationTypeInfo::Top => Top,
+            VerificationTypeInfo::Integer => Integer,
+            VerificationTypeInfo::Float => Float,
+            VerificationTypeInfo::Double => Double,
+            VerificationTypeInfo::Long => Long,
+            VerificationTypeInfo::Null => Null,
+            VerificationTypeInfo::UninitializedThis => UninitializedThis,
+            VerificationTypeInfo::Object(index) => Reference(self.class_name(*index).map_err(|reason| self.error(pc, &reason))?),
+            VerificationTypeInfo::Uninitialized(offset) => {
+                if !self.is_instruction(*offset as usize) || self.code[*offset as usize] != NEW {
+                    return Err(self.error(pc, "StackMapTable error: bad uninitialized offset"));
+                }
+                Uninitialized(*offset)
+            }
+            VerificationTypeInfo::Invalid(tag) => return Err(self.error(pc, &format!("StackMapTable error: bad verification type {}", tag)))
+        })
+    }
+
+    pub(crate) fn check_handlers(&mut self) -> Result<(), Throwable> {
+        for handler in self.method.exceptions() {
+            let (start, end, target) = (handler.start_pc as usize, handler.end_pc as usize, handler.handler_pc as usize);
+            if start >= end || !self.is_instruction(start) || !(end == self.code.len() || self.is_instruction(end)) {
+                return Err(self.error(start, "Illegal exception table range"));
+            }
+            if !self.is_instruction(target) {
+                return Err(self.error(target, "Illegal exception table handler"));
+            }
+            let catch_type = self.catch_type(&handler.catch_type);
+            if !self.is_assignable(&catch_type, &Reference(JAVA_LANG_THROWABLE.to_string()))? {
+                return Err(self.error(target, "Catch type is not a subclass of Throwable in exception handler"));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn catch_type(&self, catch_type: &str) -> VerificationType {
+        // an empty catch type catches any exception
+        Reference(if catch_type.is_empty() { JAVA_LANG_THROWABLE } else { catch_type }.to_string())
+    }
+
+    /// the frame at each handler covering `pc` must accept the locals with the caught exception on the stack
+    fn check_handler_frames(&mut self, pc: usize, frame: &Frame, frames: &BTreeMap<usize, Frame>) -> Result<(), Throwable> {
+        for handler in self.method.exceptions() {
+            if (handler.start_pc as usize..handler.end_pc as usize).contains(&pc) {
+                let handler_frame = Frame {
+                    locals: frame.locals.clone(),
+                    stack: vec![self.catch_type(&handler.catch_type)],
+                    flag_this_uninit: frame.flag_this_uninit
+                };
+                match frames.get(&(handler.handler_pc as usize)) {
+                    Some(target) if self.is_frame_assignable(&handler_frame, target)? => {}
+                    Some(_) => return Err(self.error(pc, &format!("Stack map does not match the one at exception handler {}", handler.handler_pc))),
+                    None => return Err(self.error(pc, &format!("Expecting a stackmap frame at branch target {}", handler.handler_pc)))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn is_frame_assignable(&mut self, from: &Frame, to: &Frame) -> Result<bool, Throwable> {
+        if from.locals.len() != to.locals.len() || from.stack.len() != to.stack.len() {
+            return Ok(false);
+        }
+        if from.flag_this_uninit && !to.flag_this_uninit {
+            return Ok(false);
+        }
+        for (from, to) in from.locals.iter().zip(&to.locals).chain(from.stack.iter().zip(&to.stack)) {
+            if !self.is_assignable(from, to)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// JVMS 4.10.1.2 isAssignable, loading the classes whose hierarchy is needed
+    pub(crate) fn is_assignable(&mut self, from: &VerificationType, to: &VerificationType) -> Result<bool, Throwable> {
+        if from == to || *to == Top {
+            return Ok(true);
+        }
+        match (from, to) {
+            (Null, Reference(_)) => Ok(true),
+            (Reference(from), Reference(to)) => self.is_java_assignable(from, to),
+            _ => Ok(false)
+        }
+    }
+
+    fn is_java_assignable(&mut self, from: &str, to: &str) -> Result<bool, Throwable> {
+        if from == to || to == JAVA_LANG_OBJECT {
+            return Ok(true);
+        }
+        let (from_array, to_array) = (from.starts_with(JVM_SIGNATURE_ARRAY), to.starts_with(JVM_SIGNATURE_ARRAY));
+        if to_array {
+            if !from_array {
+                return Ok(false);
+            }
+            return match (VerificationType::of_descriptor(&from[1..]), VerificationType::of_descriptor(&to[1..])) {
+                (Reference(from), Reference(to)) => self.is_java_assignable(&from, &to),
+                // primitive components must be the same, e.g. byte[] is not assignable to boolean[]
+                _ => Ok(from[1..] == to[1..])
+            };
+        }
+        if from_array {
+            return Ok(to == JAVA_LANG_CLONEABLE || to == JAVA_IO_SERIALIZABLE);
+        }
+        let to_class = self.load(to)?;
+        // interfaces are treated as Object
+        if to_class.is_interface() {
+            return Ok(true);
+        }
+        let from_class = self.load(from)?;
+        Ok(from_class.is_subclass_of(&to_class))
+    }
+
+    pub(crate) fn load(&mut self, name: &str) -> Result<Rc<Class>, Throwable> {
+        if name == self.class.name {
+            return Ok(self.class.clone());
+        }
+        self.vm.resolve_class(self.class.defining_loader, name)
+    }
+
+    pub(crate) fn class_name(&self, index: u16) -> Result<String, String> {
+        match self.class.constant_pool.get(index as usize) {
+            Some(Constant::Class(name)) => Ok(name.clone()),
+            _ => Err(format!("Illegal constant pool index {} for a class", index))
+        }
+    }
+
+    /// the type of a class constant, an array class is named by its descriptor
+    fn class_type(&self, index: u16) -> Result<VerificationType, String> {
+        Ok(Reference(self.class_name(index)?))
+    }
+
+    fn pop_expect(&mut self, frame: &mut Frame, expected: &VerificationType) -> Result<VerificationType, String> {
+        let value = frame.pop()?;
+        if !self.is_assignable(&value, expected).map_err(|throwable| throwable.to_string())? {
+            return Err(format!("Bad type on operand stack: expected {:?} but found {:?}", expected, value));
+        }
+        Ok(value)
+    }
+
+    fn pop_reference(&mut self, frame: &mut Frame) -> Result<VerificationType, String> {
+        let value = frame.pop()?;
+        if !value.is_reference() {
+            return Err(format!("Bad type on operand stack: expected reference but found {:?}", value));
+        }
+        Ok(value)
+    }
+
+    fn pop_array(&mut self, frame: &mut Frame) -> Result<VerificationType, String> {
+        let value = frame.pop()?;
+        if value != Null && !value.is_array() {
+            return Err(format!("Bad type on operand stack: expected array but found {:?}", value));
+        }
+        Ok(value)
+    }
+
+    fn load_local(&self, frame: &Frame, index: usize, expected: &VerificationType) -> Result<(), String> {
+        let local = frame.local(index)?;
+        let matches = if expected.is_category2() {
+            local == expected && frame.local(index + 1)? == &Top
+        } else {
+            local == expected
+        };
+        if !matches {
+            return Err(format!("Bad local variable type: expected {:?} but found {:?} at {}", expected, local, index));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn branch_target(&self, pc: usize, offset: i32) -> Result<usize, String> {
+        let target = pc as i64 + offset as i64;
+        if target < 0 || !self.is_instruction(target as usize) {
+            return Err(format!("Illegal target of jump or branch {}", target));
+        }
+        Ok(target as usize)
+    }
+
+    /// the type state after the instruction at `pc` and the states at its branch targets (JVMS 4.10.1.9)
+    pub(crate) fn execute(&mut self, pc: usize, frame: &Frame) -> Result<Step, String> {
+        let code = self.code;
+        let max_stack = self.method.max_stack();
+        let mut frame = frame.clone();
+        let mut targets: Vec<usize> = vec![];
+        let mut falls_through = true;
+        let opcode = code[pc];
+
+        // the arithmetic, conversion and comparison instructions: the types popped, top first, and the type pushed
+        let simple: Option<(&[VerificationType], Option<VerificationType>)> = match opcode {
+            NOP => Some((&[], None)),
+            ACONST_NULL => Some((&[], Some(Null))),
+            ICONST_M1..=ICONST_5 | BIPUSH | SIPUSH => Some((&[], Some(Integer))),
+            LCONST_0 | LCONST_1 => Some((&[], Some(Long))),
+            FCONST_0..=FCONST_2 => Some((&[], Some(Float))),
+            DCONST_0 | DCONST_1 => Some((&[], Some(Double))),
+            IADD | ISUB | IMUL | IDIV | IREM | ISHL | ISHR | IUSHR | IAND | IOR | IXOR => Some((&[Integer, Integer], Some(Integer))),
+            LADD | LSUB | LMUL | LDIV | LREM | LAND | LOR | LXOR => Some((&[Long, Long], Some(Long))),
+            LSHL | LSHR | LUSHR => Some((&[Integer, Long], Some(Long))),
+            FADD | FSUB | FMUL | FDIV | FREM => Some((&[Float, Float], Some(Float))),
+            DADD | DSUB | DMUL | DDIV | DREM => Some((&[Double, Double], Some(Double))),
+            INEG | I2B | I2C | I2S => Some((&[Integer], Some(Integer))),
+            LNEG => Some((&[Long], Some(Long))),
+            FNEG => Some((&[Float], Some(Float))),
+            DNEG => Some((&[Double], Some(Double))),
+            I2L => Some((&[Integer], Some(Long))),
+            I2F => Some((&[Integer], Some(Float))),
+            I2D => Some((&[Integer], Some(Double))),
+            L2I => Some((&[Long], Some(Integer))),
+            L2F => Some((&[Long], Some(Float))),
+            L2D => Some((&[Long], Some(Double))),
+            F2I => Some((&[Float], Some(Integer))),
+            F2L => Some((&[Float], Some(Long))),
+            F2D => Some((&[Float], Some(Double))),
+            D2I => Some((&[Double], Some(Integer))),
+            D2L => Some((&[Double], Some(Long))),
+            D2F => Some((&[Double], Some(Float))),
+            LCMP => Some((&[Long, Long], Some(Integer))),
+            FCMPL | FCMPG => Some((&[Float, Float], Some(Integer))),
+            DCMPL | DCMPG => Some((&[Double, Double], Some(Integer))),
+            _ => None
+        };
+        if let Some((pops, push)) = simple {
+            for expected in pops {
+                self.pop_expect(&mut frame, expected)?;
+            }
+            if let Some(push) = push {
+                frame.push(push, max_stack)?;
+            }
+            return Ok(Step { next: Some(frame), targets: vec![] });
+        }
+
+        match opcode {
+            LDC | LDC_W | LDC2_W => {
+                let index = if opcode == LDC { u1(code, pc + 1) as u16 } else { u2(code, pc + 1) };
+                let value = match self.class.constant_pool.get(index as usize) {
+                    Some(Constant::Integer(_)) => Integer,
+                    Some(Constant::Float(_)) => Float,
+                    Some(Constant::Long(_)) => Long,
+                    Some(Constant::Double(_)) => Double,
+                    Some(Constant::String(_)) => Reference(JAVA_LANG_STRING.to_string()),
+                    Some(Constant::Class(_)) => Reference(JAVA_LANG_CLASS.to_string()),
+                    Some(Constant::MethodType(_)) => Reference(JAVA_LANG_INVOKE_METHOD_TYPE.to_string()),
+                    Some(Constant::MethodHandle { .. }) => Reference(JAVA_LANG_INVOKE_METHOD_HANDLE.to_string()),
+                    Some(Constant::Dynamic { descriptor, .. }) if field_descriptor_length(descriptor) == Some(descriptor.len()) =>
+                        VerificationType::of_descriptor(descriptor),
+                    _ => return Err(format!("Illegal type at constant pool entry {}", index))
+                };
+                if value.is_category2() != (opcode == LDC2_W) {
+                    return Err(format!("Illegal type at constant pool entry {}", index));
+                }
+                frame.push(value, max_stack)?;
+            }
+            ILOAD..=ALOAD | ILOAD_0..=ALOAD_3 => {
+                let (kind, index) = if opcode <= ALOAD {
+                    (opcode - ILOAD, u1(code, pc + 1) as usize)
+                } else {
+                    ((opcode - ILOAD_0) / 4, ((opcode - ILOAD_0) % 4) as usize)
+                };
+                self.load_value(&mut frame, kind, index, max_stack)?;
+            }
+            ISTORE..=ASTORE | ISTORE_0..=ASTORE_3 => {
+                let (kind, index) = if opcode <= ASTORE {
+                    (opcode - ISTORE, u1(code, pc + 1) as usize)
+                } else {
+                    ((opcode - ISTORE_0) / 4, ((opcode - ISTORE_0) % 4) as usize)
+                };
+                self.store_value(&mut frame, kind, index)?;
+            }
+            WIDE => {
+                let index = u2(code, pc + 2) as usize;
+                match code[pc + 1] {
+                    IINC => self.load_local(&frame, index, &Integer)?,
+                    modified @ ILOAD..=ALOAD => self.load_value(&mut frame, modified - ILOAD, index, max_stack)?,
+                    modified @ ISTORE..=ASTORE => self.store_value(&mut frame, modified - ISTORE, index)?,
+                    _ => return Err("jsr/ret are not supported by the type checker".to_string())
+                }
+            }
+            IINC => self.load_local(&frame, u1(code, pc + 1) as usize, &Integer)?,
+            IALOAD | LALOAD | FALOAD | DALOAD | BALOAD | CALOAD | SALOAD => {
+                self.pop_expect(&mut frame, &Integer)?;
+                let array = self.pop_array(&mut frame)?;
+                let allowed: &[&str] = match opcode {
+                    IALOAD => &["[I"],
+                    LALOAD => &["[J"],
+                    FALOAD => &["[F"],
+                    DALOAD => &["[D"],
+                    BALOAD => &["[B", "[Z"],
+                    CALOAD => &["[C"],
+                    _ => &["[S"]
+                };
+                if !matches!(&array, Reference(name) if allowed.contains(&name.as_str())) && array != Null {
+                    return Err(format!("Bad type on operand stack: {:?} is not {}", array, allowed.join(" or ")));
+                }
+                let value = match opcode {
+                    LALOAD => Long,
+                    FALOAD => Float,
+                    DALOAD => Double,
+                    _ => Integer
+                };
+                frame.push(value, max_stack)?;
+            }
+            AALOAD => {
+                self.pop_expect(&mut frame, &Integer)?;
+                let array = self.pop_array(&mut frame)?;
+                let component = component_of(&array).expect("array type");
+                if !component.is_reference() {
+                    return Err(format!("Bad type on operand stack: aaload on {:?}", array));
+                }
+                frame.push(component, max_stack)?;
+            }
+            IASTORE | LASTORE | FASTORE | DASTORE | BASTORE | CASTORE | SASTORE => {
+                let (value, allowed): (VerificationType, &[&str]) = match opcode {
+                    IASTORE => (Integer, &["[I"]),
+                    LASTORE => (Long, &["[J"]),
+                    FASTORE => (Float, &["[F"]),
+                    DASTORE => (Double, &["[D"]),
+                    BASTORE => (Integer, &["[B", "[Z"]),
+                    CASTORE => (Integer, &["[C"]),
+                    _ => (Integer, &["[S"])
+                };
+                self.pop_expect(&mut frame, &value)?;
+                self.pop_expect(&mut frame, &Integer)?;
+                let array = self.pop_array(&mut frame)?;
+                if !matches!(&array, Reference(name) if allowed.contains(&name.as_str())) && array != Null {
+                    return Err(format!("Bad type on operand stack: {:?} is not {}", array, allowed.join(" or ")));
+                }
+            }
+            AASTORE => {
+                // the component type of the value is checked at run time
+                self.pop_reference(&mut frame)?;
+                self.pop_expect(&mut frame, &Integer)?;
+                let array = self.pop_array(&mut frame)?;
+                if !component_of(&array).expect("array type").is_reference() {
+                    return Err(format!("Bad type on operand stack: aastore on {:?}", array));
+                }
+            }
+            POP => {
+                frame.pop_category1()?;
+            }
+            POP2 => {
+                if !frame.pop()?.is_category2() {
+                    frame.pop_category1()?;
+                }
+            }
+            DUP => {
+                let v1 = frame.pop_category1()?;
+                frame.push(v1.clone(), max_stack)?;
+                frame.push(v1, max_stack)?;
+            }
+            DUP_X1 => {
+                let v1 = frame.pop_category1()?;
+                let v2 = frame.pop_category1()?;
+                for value in [v1.clone(), v2, v1] {
+                    frame.push(value, max_stack)?;
+                }
+            }
+            DUP_X2 => {
+                let v1 = frame.pop_category1()?;
+                let v2 = frame.pop()?;
+                let values = if v2.is_category2() {
+                    vec![v1.clone(), v2, v1]
+                } else {
+                    let v3 = frame.pop_category1()?;
+                    vec![v1.clone(), v3, v2, v1]
+                };
+                for value in values {
+                    frame.push(value, max_stack)?;
+                }
+            }
+            DUP2 => {
+                let v1 = frame.pop()?;
+                let values = if v1.is_category2() {
+                    vec![v1.clone(), v1]
+                } else {
+                    let v2 = frame.pop_category1()?;
+                    vec![v2.clone(), v1.clone(), v2, v1]
+                };
+                for value in values {
+                    frame.push(value, max_stack)?;
+                }
+            }
+            DUP2_X1 => {
+                let v1 = frame.pop()?;
+                let values = if v1.is_category2() {
+                    let v2 = frame.pop_category1()?;
+                    vec![v1.clone(), v2, v1]
+                } else {
+                    let v2 = frame.pop_category1()?;
+                    let v3 = frame.pop_category1()?;
+                    vec![v2.clone(), v1.clone(), v3, v2, v1]
+                };
+                for value in values {
+                    frame.push(value, max_stack)?;
+                }
+            }
+            DUP2_X2 => {
+                let v1 = frame.pop()?;
+                let values = if v1.is_category2() {
+                    let v2 = frame.pop()?;
+                    if v2.is_category2() {
+                        vec![v1.clone(), v2, v1]
+                    } else {
+                        let v3 = frame.pop_category1()?;
+                        vec![v1.clone(), v3, v2, v1]
+                    }
+                } else {
+                    let v2 = frame.pop_category1()?;
+                    let v3 = frame.pop()?;
+                    if v3.is_category2() {
+                        vec![v2.clone(), v1.clone(), v3, v2, v1]
+                    } else {
+                        let v4 = frame.pop_category1()?;
+                        vec![v2.clone(), v1.clone(), v4, v3, v2, v1]
+                    }
+                };
+                for value in values {
+                    frame.push(value, max_stack)?;
+                }
+            }
+            SWAP => {
+                let v1 = frame.pop_category1()?;
+                let v2 = frame.pop_category1()?;
+                frame.push(v1, max_stack)?;
+                frame.push(v2, max_stack)?;
+            }
+            IFEQ..=IFLE => {
+                self.pop_expect(&mut frame, &Integer)?;
+                targets.push(self.branch_target(pc, i2(code, pc + 1) as i32)?);
+            }
+            IF_ICMPEQ..=IF_ICMPLE => {
+                self.pop_expect(&mut frame, &Integer)?;
+                self.pop_expect(&mut frame, &Integer)?;
+                targets.push(self.branch_target(pc, i2(code, pc + 1) as i32)?);
+            }
+            IF_ACMPEQ | IF_ACMPNE => {
+                self.pop_reference(&mut frame)?;
+                self.pop_reference(&mut frame)?;
+                targets.push(self.branch_target(pc, i2(code, pc + 1) as i32)?);
+            }
+            IFNULL | IFNONNULL => {
+                self.pop_reference(&mut frame)?;
+                targets.push(self.branch_target(pc, i2(code, pc + 1) as i32)?);
+            }
+            GOTO => {
+                targets.push(self.branch_target(pc, i2(code, pc + 1) as i32)?);
+                falls_through = false;
+            }
+            GOTO_W => {
+                targets.push(self.branch_target(pc, i4(code, pc + 1))?);
+                falls_through = false;
+            }
+            JSR | JSR_W | RET => return Err("jsr/ret are not supported by the type checker".to_string()),
+            TABLESWITCH | LOOKUPSWITCH => {
+                self.pop_expect(&mut frame, &Integer)?;
+                let operands = switch_operands(pc);
+                targets.push(self.branch_target(pc, i4(code, operands))?);
+                if opcode == TABLESWITCH {
+                    let (low, high) = (i4(code, operands + 4), i4(code, operands + 8));
+                    for i in 0..=(high as i64 - low as i64) as usize {
+                        targets.push(self.branch_target(pc, i4(code, operands + 12 + 4 * i))?);
+                    }
+                } else {
+                    let pairs = i4(code, operands + 4) as usize;
+                    for i in 0..pairs {
+                        let pair = operands + 8 + 8 * i;
+                        if i > 0 && i4(code, pair) <= i4(code, pair - 8) {
+                            return Err("Bad lookupswitch instruction: keys are not sorted".to_string());
+                        }
+                        targets.push(self.branch_target(pc, i4(code, pair + 4))?);
+                    }
+                }
+                falls_through = false;
+            }
+            IRETURN | LRETURN | FRETURN | DRETURN | ARETURN | RETURN => {
+                let return_descriptor = parse_method_descriptor(self.method.descriptor()).map(|(_, return_descriptor)| return_descriptor)
+                    .ok_or("Illegal method descriptor")?;
+                if opcode == RETURN {
+                    if return_descriptor != JVM_SIGNATURE_VOID {
+                        return Err("Method expects a return value".to_string());
+                    }
+                    if self.method.name() == OBJECT_INITIALIZER_NAME && frame.flag_this_uninit {
+                        return Err("Constructor must call super() or this() before return".to_string());
+                    }
+                } else {
+                    if return_descriptor == JVM_SIGNATURE_VOID {
+                        return Err("Method does not expect a return value".to_string());
+                    }
+                    let return_type = VerificationType::of_descriptor(return_descriptor);
+                    let expected = match opcode {
+                        IRETURN => Integer,
+                        LRETURN => Long,
+                        FRETURN => Float,
+                        DRETURN => Double,
+                        _ => Reference(String::new())
+                    };
+                    if std::mem::discriminant(&expected) != std::mem::discriminant(&return_type) {
+                        return Err("Bad return type".to_string());
+                    }
+                    self.pop_expect(&mut frame, &return_type)?;
+                }
+                falls_through = false;
+            }
+            GETSTATIC | PUTSTATIC | GETFIELD | PUTFIELD => {
+                let index = u2(code, pc + 1);
+                let (class_name, name, descriptor) = match self.class.constant_pool.get(index as usize) {
+                    Some(Constant::FieldRef { class, name, descriptor }) if field_descriptor_length(descriptor) == Some(descriptor.len()) =>
+                        (class.clone(), name.clone(), descriptor.clone()),
+                    _ => return Err(format!("Illegal constant pool index {} for a field reference", index))
+                };
+                let field_type = VerificationType::of_descriptor(&descriptor);
+                match opcode {
+                    GETSTATIC => frame.push(field_type, max_stack)?,
+                    PUTSTATIC => {
+                        self.pop_expect(&mut frame, &field_type)?;
+                    }
+                    GETFIELD => {
+                        let object = self.pop_expect(&mut frame, &Reference(class_name.clone()))?;
+                        self.check_protected(&object, &class_name, &name, &descriptor, true)?;
+                        frame.push(field_type, max_stack)?;
+                    }
+                    _ => {
+                        self.pop_expect(&mut frame, &field_type)?;
+                        let object = frame.pop()?;
+                        // a constructor may set the fields declared by its class before calling super()
+                        let own_field = object == UninitializedThis && class_name == self.class.name
+                            && self.class.fields.iter().any(|field| field.name() == name && field.descriptor() == descriptor);
+                        if !own_field {
+                            if !self.is_assignable(&object, &Reference(class_name.clone())).map_err(|throwable| throwable.to_string())? {
+                                return Err(format!("Bad type on operand stack: putfield on {:?}", object));
+                            }
+                            self.check_protected(&object, &class_name, &name, &descriptor, true)?;
+                        }
+                    }
+                }
+            }
+            INVOKEVIRTUAL | INVOKESPECIAL | INVOKESTATIC | INVOKEINTERFACE | INVOKEDYNAMIC => {
+                self.invoke(pc, opcode, &mut frame)?;
+            }
+            NEW => {
+                let class_type = self.class_type(u2(code, pc + 1))?;
+                if class_type.is_array() {
+                    return Err("Illegal new instruction of an array class".to_string());
+                }
+                frame.push(Uninitialized(pc as u16), max_stack)?;
+            }
+            NEWARRAY => {
+                let atype = u1(code, pc + 1);
+                let descriptor = newarray_descriptor(atype).ok_or_else(|| format!("Illegal newarray type {}", atype))?;
+                self.pop_expect(&mut frame, &Integer)?;
+                frame.push(Reference(descriptor.to_string()), max_stack)?;
+            }
+            ANEWARRAY => {
+                let component = self.class_name(u2(code, pc + 1))?;
+                let descriptor = if component.starts_with(JVM_SIGNATURE_ARRAY) {
+                    format!("[{}", component)
+                } else {
+                    format!("[L{};", component)
+                };
+                if field_descriptor_length(&descriptor).is_none() {
+                    return Err("Array with too many dimensions".to_string());
+                }
+                self.pop_expect(&mut frame, &Integer)?;
+                frame.push(Reference(descriptor), max_stack)?;
+            }
+            MULTIANEWARRAY => {
+                let class_name = self.class_name(u2(code, pc + 1))?;
+                let dimensions = u1(code, pc + 3) as usize;
+                if dimensions == 0 || class_name.bytes().take_while(|&b| b == b'[').count() < dimensions {
+                    return Err("Illegal dimension in multianewarray instruction".to_string());
+                }
+                for _ in 0..dimensions {
+                    self.pop_expect(&mut frame, &Integer)?;
+                }
+                frame.push(Reference(class_name), max_stack)?;
+            }
+            ARRAYLENGTH => {
+                self.pop_array(&mut frame)?;
+                frame.push(Integer, max_stack)?;
+            }
+            ATHROW => {
+                self.pop_expect(&mut frame, &Reference(JAVA_LANG_THROWABLE.to_string()))?;
+                falls_through = false;
+            }
+            CHECKCAST => {
+                let class_type = self.class_type(u2(code, pc + 1))?;
+                self.pop_reference(&mut frame)?;
+                frame.push(class_type, max_stack)?;
+            }
+            INSTANCEOF => {
+                self.class_type(u2(code, pc + 1))?;
+                self.pop_reference(&mut frame)?;
+                frame.push(Integer, max_stack)?;
+            }
+            MONITORENTER | MONITOREXIT => {
+                self.pop_reference(&mut frame)?;
+            }
+            _ => return Err(format!("Bad instruction: {:02x}", opcode))
+        }
+        let targets = targets.into_iter().map(|target| (target, frame.clone())).collect();
+        Ok(Step { next: if falls_through { Some(frame) } else { None }, targets })
+    }
+
+    // the kinds of load and store instructions are in the order int, long, float, double, reference
+    fn load_value(&self, frame: &mut Frame, kind: u8, index: usize, max_stack: usize) -> Result<(), String> {
+        let value = match kind {
+            0 => Integer,
+            1 => Long,
+            2 => Float,
+            3 => Double,
+            _ => {
+                let local = frame.local(index)?.clone();
+                if !local.is_reference() {
+                    return Err(format!("Bad local variable type: expected reference but found {:?} at {}", local, index));
+                }
+                return frame.push(local, max_stack);
+            }
+        };
+        self.load_local(frame, index, &value)?;
+        frame.push(value, max_stack)
+    }
+
+    fn store_value(&mut self, frame: &mut Frame, kind: u8, index: usize) -> Result<(), String> {
+        let value = match kind {
+            0 => self.pop_expect(frame, &Integer)?,
+            1 => self.pop_expect(frame, &Long)?,
+            2 => self.pop_expect(frame, &Float)?,
+            3 => self.pop_expect(frame, &Double)?,
+            _ => {
+                let value = frame.pop()?;
+                if !value.is_reference() && !matches!(value, ReturnAddress(_)) {
+                    return Err(format!("Bad type on operand stack: expected reference but found {:?}", value));
+                }
+                value
+            }
+        };
+        frame.set_local(index, value)
+    }
+
+    fn invoke(&mut self, pc: usize, opcode: u8, frame: &mut Frame) -> Result<(), String> {
+        let code = self.code;
+        let index = u2(code, pc + 1);
+        let (class_name, name, descriptor) = match (opcode, self.class.constant_pool.get(index as usize)) {
+            (INVOKEDYNAMIC, Some(Constant::InvokeDynamic { name, descriptor, .. })) => (String::new(), name.clone(), descriptor.clone()),
+            (INVOKEINTERFACE, Some(Constant::InterfaceMethodRef { class, name, descriptor })) => (class.clone(), name.clone(), descriptor.clone()),
+            (INVOKEVIRTUAL, Some(Constant::MethodRef { class, name, descriptor })) => (class.clone(), name.clone(), descriptor.clone()),
+            (INVOKESPECIAL | INVOKESTATIC, Some(Constant::MethodRef { class, name, descriptor } | Constant::InterfaceMethodRef { class, name, descriptor })) =>
+                (class.clone(), name.clone(), descriptor.clone()),
+            _ => return Err(format!("Illegal constant pool index {} for a method reference", index))
+        };
+        let (parameters, return_descriptor) = parse_method_descriptor(&descriptor).ok_or("Illegal method descriptor")?;
+        if name == CLASS_INITIALIZER_NAME || (name == OBJECT_INITIALIZER_NAME && (opcode != INVOKESPECIAL || return_descriptor != JVM_SIGNATURE_VOID)) {
+            return Err(format!("Illegal call to internal method {}", name));
+        }
+        if opcode == INVOKEINTERFACE {
+            let count = parameters.iter().map(|parameter| if VerificationType::of_descriptor(parameter).is_category2() { 2 } else { 1 }).sum::<usize>() + 1;
+            if u1(code, pc + 3) as usize != count || u1(code, pc + 4) != 0 {
+                return Err("Inconsistent args count operand in invokeinterface".to_string());
+            }
+        }
+        if opcode == INVOKEDYNAMIC && (u1(code, pc + 3) != 0 || u1(code, pc + 4) != 0) {
+            return Err("Third and fourth operand bytes of invokedynamic must be zero".to_string());
+        }
+        for parameter in parameters.iter().rev() {
+            self.pop_expect(frame, &VerificationType::of_descriptor(parameter))?;
+        }
+        let max_stack = self.method.max_stack();
+        let throwable_to_string = |throwable: Throwable| throwable.to_string();
+        match opcode {
+            INVOKESPECIAL if name == OBJECT_INITIALIZER_NAME => {
+                let receiver = frame.pop()?;
+                let initialized = match &receiver {
+                    UninitializedThis => {
+                        // this() or super()
+                        let super_name = self.class.super_class.as_ref().map(|super_class| super_class.name.as_str());
+                        if class_name != self.class.name && Some(class_name.as_str()) != super_name {
+                            return Err("Bad <init> method call".to_string());
+                        }
+                        Reference(self.class.name.clone())
+                    }
+                    Uninitialized(offset) => {
+                        let new_class = self.class_name(u2(code, *offset as usize + 1))?;
+                        if new_class != class_name {
+                            return Err("Call to wrong <init> method".to_string());
+                        }
+                        Reference(new_class)
+                    }
+                    _ => return Err(format!("Bad type on operand stack: <init> on {:?}", receiver))
+                };
+                frame.initialize(&receiver, initialized);
+            }
+            INVOKESPECIAL => {
+                // a private, super or default method of the current class, its superclasses or interfaces
+                self.pop_expect(frame, &Reference(self.class.name.clone()))?;
+                if class_name != self.class.name && !self.is_java_assignable(&self.class.name.clone(), &class_name).map_err(throwable_to_string)? {
+                    return Err("Bad invokespecial instruction: current class isn't assignable to reference class".to_string());
+                }
+            }
+            INVOKEVIRTUAL | INVOKEINTERFACE => {
+                let receiver = if opcode == INVOKEINTERFACE {
+                    self.pop_reference(frame)?
+                } else {
+                    self.pop_expect(frame, &Reference(class_name.clone()))?
+                };
+                if matches!(receiver, Uninitialized(_) | UninitializedThis) {
+                    return Err(format!("Bad type on operand stack: {} on an uninitialized object", name));
+                }
+                if opcode == INVOKEVIRTUAL {
+                    self.check_protected(&receiver, &class_name, &name, &descriptor, false)?;
+                }
+            }
+            _ => {}
+        }
+        if return_descriptor != JVM_SIGNATURE_VOID {
+            frame.push(VerificationType::of_descriptor(return_descriptor), max_stack)?;
+        }
+        Ok(())
+    }
+
+    /// JVMS 4.10.1.8: a protected member of a superclass in another run-time package may only be accessed
+    /// through an object of the current class or its subclasses
+    fn check_protected(&mut self, object: &VerificationType, class_name: &str, name: &str, descriptor: &str, is_field: bool) -> Result<(), String> {
+        let throwable_to_string = |throwable: Throwable| throwable.to_string();
+        if class_name.starts_with(JVM_SIGNATURE_ARRAY) || class_name == self.class.name {
+            return Ok(());
+        }
+        let is_superclass = self.class.super_class.as_ref()
+            .is_some_and(|super_class| std::iter::successors(Some(super_class.clone()), |class| class.super_class.clone()).any(|class| class.name == class_name));
+        if !is_superclass {
+            return Ok(());
+        }
+        let referenced = self.load(class_name).map_err(throwable_to_string)?;
+        let declaring = if is_field {
+            referenced.find_field(name, descriptor).filter(|field| field.access_flags() & ACC_PROTECTED != 0).map(|field| field.class())
+        } else {
+            std::iter::successors(Some(referenced), |class| class.super_class.clone())
+                .find_map(|class| class.find_method(name, descriptor))
+                .filter(|method| method.access_flags() & ACC_PROTECTED != 0)
+                .map(|method| method.class())
+        };
+        let declaring = match declaring {
+            Some(declaring) => declaring,
+            None => return Ok(())
+        };
+        if is_same_package(&declaring, self.class) {
+            return Ok(());
+        }
+        // arrays may invoke the protected Object.clone
+        if !is_field && name == "clone" && object.is_array() {
+            return Ok(());
+        }
+        if !self.is_assignable(object, &Reference(self.class.name.clone())).map_err(throwable_to_string)? {
+            return Err("Bad access to protected data".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// the classes of a run-time package have the same package name and defining loader
+pub fn is_same_package(class1: &Class, class2: &Class) -> bool {
+    let package = |class: &Class| class.name.rfind('/').map_or(String::new(), |end| class.name[..end].to_string());
+    class1.defining_loader == class2.defining_loader && package(class1) == package(class2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_builder::{test_vm, ClassBuilder, Code};
+    use crate::class_loader::APP_LOADER;
+    use crate::vm::VmOptions;
+
+    // `static int max(int a, int b)`, which branches to 7 where the frame is the same as the initial one
+    const MAX: [u8; 9] = [0x1a, 0x1b, 0xa2, 0x00, 0x05, 0x1b, 0xac, 0x1a, 0xac];
+
+    /// verify the class C declaring `method` with `code`, and a constructor calling the one of Object
+    fn verify_method(access_flags: u16, name: &str, descriptor: &str, code: impl FnOnce(&mut ClassBuilder) -> Code) -> Result<(), String> {
+        let mut vm = test_vm(VmOptions::default());
+        let mut builder = ClassBuilder::new("C", Some(JAVA_LANG_OBJECT)).constructor(JAVA_LANG_OBJECT);
+        let code = code(&mut builder);
+        let class = builder.method(access_flags, name, descriptor, Some(code)).define(&mut vm, APP_LOADER);
+        verify(&mut vm, &class).map_err(|throwable| {
+            assert_eq!(throwable.class_name, JAVA_LANG_VERIFY_ERROR);
+            throwable.message
+        })
+    }
+
+    fn max(stack_map_table: (u16, Vec<u8>)) -> Result<(), String> {
+        verify_method(ACC_STATIC, "max", "(II)I", |_| Code { stack_map_table: Some(stack_map_table), ..Code::new(2, 2, MAX.to_vec()) })
+    }
+
+    #[test]
+    fn valid_code_is_accepted() {
+        // same_frame at 7
+        assert_eq!(max((1, vec![7])), Ok(()));
+    }
+
+    #[test]
+    fn branch_target_without_a_frame_is_rejected() {
+        assert_eq!(verify_method(ACC_STATIC, "max", "(II)I", |_| Code::new(2, 2, MAX.to_vec())),
+            Err("C.max(II)I @2: Expecting a stackmap frame at branch target 7".to_string()));
+    }
+
+    #[test]
+    fn bad_stack_map_frame_is_rejected() {
+        // same_frame at 3, within the branch instruction
+        assert_eq!(max((1, vec![3])), Err("C.max(II)I @3: StackMapTable error: bad offset".to_string()));
+        // full_frame at 7 whose first local is a float
+        assert_eq!(max((1, vec![255, 0, 7, 0, 2, 2, 1, 0, 0])),
+            Err("C.max(II)I @2: Inconsistent stackmap frames at branch target 7".to_string()));
+        // same_locals_1_stack_item_frame at 7 of an invalid verification type
+        assert_eq!(max((1, vec![64 + 7, 9])), Err("C.max(II)I @7: StackMapTable error: bad verification type 9".to_string()));
+    }
+
+    #[test]
+    fn stack_underflow_is_rejected() {
+        // pop, return
+        assert_eq!(verify_method(ACC_STATIC, "m", "()V", |_| Code::new(1, 0, vec![0x57, 0xb1])),
+            Err("C.m()V @0: Operand stack underflow".to_string()));
+    }
+
+    #[test]
+    fn uninitialized_this_is_rejected() {
+        // aload_0, invokevirtual C.n()V before the constructor of Object is invoked
+        let result = verify_method(ACC_PUBLIC, "<init>", "(I)V", |builder| {
+            let [high, low] = builder.method_ref("C", "n", "()V").to_be_bytes();
+            Code::new(1, 2, vec![0x2a, 0xb6, high, low, 0xb1])
+        });
+        assert_eq!(result, Err("C.<init>(I)V @1: Bad type on operand stack: expected Reference(\"C\") but found UninitializedThis".to_string()));
+    }
+
+    #[test]
+    fn ill_typed_load_is_rejected() {
+        // aload_0 of an int, areturn
+        assert_eq!(verify_method(ACC_STATIC, "m", "(I)Ljava/lang/Object;", |_| Code::new(1, 1, vec![0x2a, 0xb0])),
+            Err("C.m(I)Ljava/lang/Object; @0: Bad local variable type: expected reference but found Integer at 0".to_string()));
+    }
+}
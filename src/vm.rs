@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::dispatch;
+use crate::class_loader::{ClassLoaderId, ClassLoaders, BOOTSTRAP_LOADER};
+use crate::exception::{PreallocatedError, Throwable};
+use crate::lambda::Lambda;
+use crate::heap::{Collector, Heap, DEFAULT_MAX_HEAP_SIZE};
+use crate::method_area::{ClassId, MethodArea};
+use crate::strings::StringTable;
+use crate::types::Class;
+use crate::verifier;
+
+/// which classes the bytecode verifier checks, as `-Xverify:none|remote|all`
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Verification {
+    None,
+    // the classes not defined by the bootstrap loader
+    Remote,
+    All
+}
+
+pub struct VmOptions {
+    pub(crate) verification: Verification,
+    // -Xmx, the bytes the heap may take
+    pub(crate) max_heap_size: usize,
+    pub(crate) collector: Collector,
+    // -Xlog:gc
+    pub(crate) log_gc: bool
+}
+
+impl Default for VmOptions {
+    fn default() -> Self {
+        VmOptions {
+            verification: Verification::Remote,
+            max_heap_size: DEFAULT_MAX_HEAP_SIZE,
+            collector: Collector::MarkSweep,
+            log_gc: false
+        }
+    }
+}
+
+/// the state shared by all threads of the VM
+pub struct Vm {
+    pub(crate) method_area: MethodArea,
+    pub(crate) class_loaders: ClassLoaders,
+    pub(crate) heap: Heap,
+    pub(crate) options: VmOptions,
+    // the implementations of the classes spun for lambdas
+    pub(crate) lambdas: HashMap<ClassId, Rc<Lambda>>,
+    pub(crate) string_table: StringTable,
+    pub(crate) out_of_memory_error: Option<PreallocatedError>
+}
+
+impl Vm {
+    pub fn new(class_loaders: ClassLoaders, options: VmOptions) -> Self {
+        Vm {
+            method_area: MethodArea::new(),
+            class_loaders,
+            heap: Heap::new(options.max_heap_size, options.collector, options.log_gc),
+            options,
+            lambdas: HashMap::new(),
+            string_table: StringTable::default(),
+            out_of_memory_error: None
+        }
+    }
+
+    pub fn load_class(&mut self, loader: ClassLoaderId, name: &str) -> Result<Rc<Class>, Throwable> {
+        self.class_loaders.load_class(&mut self.method_area, loader, name)
+    }
+
+    pub fn resolve_class(&mut self, loader: ClassLoaderId, name: &str) -> Result<Rc<Class>, Throwable> {
+        self.class_loaders.resolve_class(&mut self.method_area, loader, name)
+    }
+
+    /// link `class` after its superclass and superinterfaces (JVMS 5.4), verifying it unless disabled
+    pub fn link_class(&mut self, class: &Rc<Class>) -> Result<(), Throwable> {
+        if class.linked.get() {
+            return Ok(());
+        }
+        let supers: Vec<Rc<Class>> = class.super_class.iter().chain(&class.interfaces).cloned().collect();
+        for super_class in &supers {
+            self.link_class(super_class)?;
+        }
+        let verify = match self.options.verification {
+            Verification::None => false,
+            Verification::Remote => class.defining_loader != BOOTSTRAP_LOADER,
+            Verification::All => true
+        };
+        if verify {
+            verifier::verify(self, class)?;
+        }
+        dispatch::link_methods(class);
+        self.add_override_constraints(class)?;
+        class.linked.set(true);
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::inflate;
+
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x02014b50;
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+const CENTRAL_DIRECTORY_HEADER_SIZE: usize = 46;
+const LOCAL_HEADER_SIZE: usize = 30;
+const MAX_COMMENT_SIZE: usize = 0xffff;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+/*
+central directory file header {
+    u4 signature;
+    u2 version_made_by;
+    u2 version_needed;
+    u2 flags;
+    u2 compression_method;
+    u2 last_modified_time;
+    u2 last_modified_date;
+    u4 crc32;
+    u4 compressed_size;
+    u4 uncompressed_size;
+    u2 file_name_length;
+    u2 extra_field_length;
+    u2 file_comment_length;
+    u2 disk_number_start;
+    u2 internal_file_attributes;
+    u4 external_file_attributes;
+    u4 local_header_offset;
+    u1 file_name[file_name_length];
+    u1 extra_field[extra_field_length];
+    u1 file_comment[file_comment_length];
+}
+*/
+pub struct ZipEntry {
+    pub(crate) name: String,
+    pub(crate) method: u16,
+    pub(crate) compressed_size: usize,
+    pub(crate) uncompressed_size: usize,
+    pub(crate) local_header_offset: usize
+}
+
+/// a zip archive read into memory, entries are indexed from the central directory.
+/// Data prepended to the archive (e.g. the jmod header) is tolerated.
+pub struct ZipFile {
+    pub(crate) path: PathBuf,
+    bytes: Vec<u8>,
+    entries: HashMap<String, ZipEntry>
+}
+
+fn u2(bytes: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([bytes[pos], bytes[pos + 1]])
+}
+
+fn u4(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+fn invalid(path: &Path, message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), message))
+}
+
+impl ZipFile {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        let end = ZipFile::find_end_of_central_directory(&bytes)
+            .ok_or_else(|| invalid(path, "end of central directory not found"))?;
+        let entry_count = u2(&bytes, end + 10) as usize;
+        let central_directory_size = u4(&bytes, end + 12) as usize;
+        let central_directory_offset = u4(&bytes, end + 16) as usize;
+        if central_directory_size + central_directory_offset > end {
+            return Err(invalid(path, "invalid central directory"));
+        }
+        // offsets are relative to the start of the archive, which is not the file start if data is prepended
+        let base = end - central_directory_size - central_directory_offset;
+
+        let mut entries = HashMap::with_capacity(entry_count);
+        let mut pos = base + central_directory_offset;
+        for _ in 0..entry_count {
+            if pos + CENTRAL_DIRECTORY_HEADER_SIZE > end || u4(&bytes, pos) != CENTRAL_DIRECTORY_SIGNATURE {
+                return Err(invalid(path, "invalid central directory header"));
+            }
+            let name_length = u2(&bytes, pos + 28) as usize;
+            let extra_length = u2(&bytes, pos + 30) as usize;
+            let comment_length = u2(&bytes, pos + 32) as usize;
+            let name_start = pos + CENTRAL_DIRECTORY_HEADER_SIZE;
+            if name_start + name_length > end {
+                return Err(invalid(path, "invalid central directory header"));
+            }
+            let name = String::from_utf8_lossy(&bytes[name_start..name_start + name_length]).into_owned();
+            let entry = ZipEntry {
+                name: name.clone(),
+                method: u2(&bytes, pos + 10),
+                compressed_size: u4(&bytes, pos + 20) as usize,
+                uncompressed_size: u4(&bytes, pos + 24) as usize,
+                local_header_offset: base + u4(&bytes, pos + 42) as usize
+            };
+            entries.insert(name, entry);
+            pos = name_start + name_length + extra_length + comment_length;
+        }
+        Ok(ZipFile {
+            path: path.to_path_buf(),
+            bytes,
+            entries
+        })
+    }
+
+    // the end of central directory record is at the end of file followed by a variable length comment
+    fn find_end_of_central_directory(bytes: &[u8]) -> Option<usize> {
+        if bytes.len() < END_OF_CENTRAL_DIRECTORY_SIZE {
+            return None;
+        }
+        let last = bytes.len() - END_OF_CENTRAL_DIRECTORY_SIZE;
+        let first = last.saturating_sub(MAX_COMMENT_SIZE);
+        (first..=last).rev().find(|&pos| u4(bytes, pos) == END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+    }
+
+    /// read the uncompressed content of an entry, `None` if there is no such entry
+    pub fn read(&self, name: &str) -> Option<io::Result<Vec<u8>>> {
+        self.entries.get(name).map(|entry| self.read_entry(entry))
+    }
+
+    fn read_entry(&self, entry: &ZipEntry) -> io::Result<Vec<u8>> {
+        let pos = entry.local_header_offset;
+        if pos + LOCAL_HEADER_SIZE > self.bytes.len() || u4(&self.bytes, pos) != LOCAL_HEADER_SIGNATURE {
+            return Err(invalid(&self.path, &format!("invalid local header of {}", entry.name)));
+        }
+        // the name and extra field lengths in local header may differ from the central directory
+        let start = pos + LOCAL_HEADER_SIZE + u2(&self.bytes, pos + 26) as usize + u2(&self.bytes, pos + 28) as usize;
+        let end = start + entry.compressed_size;
+        if end > self.bytes.len() {
+            return Err(invalid(&self.path, &format!("truncated entry {}", entry.name)));
+        }
+        let data = &self.bytes[start..end];
+        match entry.method {
+            METHOD_STORED => Ok(data.to_vec()),
+            METHOD_DEFLATED => inflate::inflate(data, entry.uncompressed_size)
+                .map_err(|message| invalid(&self.path, &format!("{}: {}", entry.name, message))),
+            method => Err(invalid(&self.path, &format!("unsupported compression method {} of {}", method, entry.name)))
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // "hello, hello, hello!" deflated
+    const DEFLATED: [u8; 12] = [203, 72, 205, 201, 201, 215, 81, 200, 64, 162, 20, 1];
+
+    /// an archive of the entries (name, method, data, uncompressed size) after `prefix`,
+    /// the offsets are relative to the start of the archive
+    pub(crate) fn archive(prefix: &[u8], entries: &[(&str, u16, &[u8], usize)]) -> Vec<u8> {
+        let (mut local, mut central) = (vec![], vec![]);
+        for &(name, method, data, size) in entries {
+            let sizes = [(data.len() as u32).to_le_bytes(), (size as u32).to_le_bytes()].concat();
+            central.extend([&CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes()[..], &[20, 0, 20, 0, 0, 0], &method.to_le_bytes(), &[0; 8], &sizes,
+                &(name.len() as u16).to_le_bytes(), &[0; 12], &(local.len() as u32).to_le_bytes(), name.as_bytes()].concat());
+            // an extra field in the local header only
+            local.extend([&LOCAL_HEADER_SIGNATURE.to_le_bytes()[..], &[20, 0, 0, 0], &method.to_le_bytes(), &[0; 8], &sizes,
+                &(name.len() as u16).to_le_bytes(), &[2, 0], name.as_bytes(), &[0, 0], data].concat());
+        }
+        let end = [&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes()[..], &[0; 4], &(entries.len() as u16).to_le_bytes(),
+            &(entries.len() as u16).to_le_bytes(), &(central.len() as u32).to_le_bytes(), &(local.len() as u32).to_le_bytes(), &[0, 0]].concat();
+        [prefix, &local, &central, &end].concat()
+    }
+
+    fn open(name: &str, bytes: &[u8]) -> io::Result<ZipFile> {
+        let path = std::env::temp_dir().join(format!("jara-{}-{}", std::process::id(), name));
+        fs::write(&path, bytes).unwrap();
+        let zip = ZipFile::open(&path);
+        fs::remove_file(&path).unwrap();
+        zip
+    }
+
+    #[test]
+    fn entries_are_found_in_the_central_directory() {
+        let bytes = archive(b"", &[("a.txt", METHOD_STORED, b"stored", 6), ("dir/b.txt", METHOD_DEFLATED, &DEFLATED, 20)]);
+        let zip = open("entries.zip", &bytes).unwrap();
+        assert_eq!(zip.read("a.txt").unwrap().unwrap(), b"stored");
+        assert_eq!(zip.read("dir/b.txt").unwrap().unwrap(), b"hello, hello, hello!");
+        assert!(zip.read("b.txt").is_none());
+    }
+
+    #[test]
+    fn data_before_the_archive_is_skipped() {
+        // a jmod starts with its own header
+        let bytes = archive(b"JM\x01\x00", &[("classes/A.class", METHOD_STORED, b"\xca\xfe\xba\xbe", 4)]);
+        let zip = open("prefixed.zip", &bytes).unwrap();
+        assert_eq!(zip.read("classes/A.class").unwrap().unwrap(), b"\xca\xfe\xba\xbe");
+    }
+
+    #[test]
+    fn invalid_archives_are_rejected() {
+        let error = open("empty.zip", b"PK").err().unwrap();
+        assert!(error.to_string().ends_with(": end of central directory not found"), "{}", error);
+        let mut bytes = archive(b"", &[("a.txt", METHOD_STORED, b"stored", 6)]);
+        let central_directory = bytes.len() - END_OF_CENTRAL_DIRECTORY_SIZE - CENTRAL_DIRECTORY_HEADER_SIZE - "a.txt".len();
+        bytes[central_directory] = 0;
+        let error = open("corrupt.zip", &bytes).err().unwrap();
+        assert!(error.to_string().ends_with(": invalid central directory header"), "{}", error);
+
+        let zip = open("bzip2.zip", &archive(b"", &[("a.txt", 12, b"BZh", 6)])).unwrap();
+        let error = zip.read("a.txt").unwrap().unwrap_err();
+        assert!(error.to_string().ends_with(": unsupported compression method 12 of a.txt"), "{}", error);
+    }
+}
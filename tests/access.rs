@@ -0,0 +1,17 @@
+mod common;
+
+use common::{run, uncaught};
+
+#[test]
+fn fields_are_accessible_to_their_nestmates_subclasses_and_package_only() {
+    let run = match run("field_access", "Main") {
+        Some(run) => run,
+        None => return
+    };
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: \
+        class Main tried to access private field other.Fields.secret; \
+        class Main tried to access field other.Fields.pkg; \
+        class Main tried to access protected field other.Fields.prot; \
+        class Main tried to access private field other.Fields.instance; \
+        4; 9");
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::{run, uncaught};
+
+#[test]
+fn arrays_hold_each_component_type_and_nest() {
+    let run = match run("arrays", "Main") {
+        Some(run) => run,
+        None => return
+    };
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: true,false -128,-128 65535,b \
+        -32768,1 -2147483648,2 -9223372036854775808,3 1.5,-0.0 1.7976931348623157E308,4.0 | 2,3,4,7,0 3,true x,null true,true,true");
+}
+
+#[test]
+fn array_accesses_are_checked() {
+    let run = match run("array_exceptions", "Main") {
+        Some(run) => run,
+        None => return
+    };
+    let lines: Vec<&str> = run.stderr.lines().take(9).collect();
+    assert_eq!(lines, [
+        "Exception in thread \"main\" java.lang.RuntimeException: java.lang.ArrayIndexOutOfBoundsException: Index 3 out of bounds for length 3",
+        "java.lang.ArrayIndexOutOfBoundsException: Index -1 out of bounds for length 3",
+        "java.lang.ArrayIndexOutOfBoundsException: Index 0 out of bounds for length 0",
+        "java.lang.ArrayStoreException: [I",
+        "java.lang.ArrayIndexOutOfBoundsException: Index 2 out of bounds for length 2",
+        "none",
+        "java.lang.NegativeArraySizeException: -1",
+        "java.lang.NegativeArraySizeException: -1",
+        "java.lang.NegativeArraySizeException: -1"
+    ]);
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use std::fs;
+use common::{compile, jar, jara, jara_in, jara_with_env, uncaught};
+
+#[test]
+fn jars_of_a_wildcard_directory_are_searched() {
+    let jar = match jar("class_path", "Manifest-Version: 1.0\n") {
+        Some(jar) => jar,
+        None => return
+    };
+    let lib = jar.with_file_name("class_path_lib");
+    let _ = fs::remove_dir_all(&lib);
+    fs::create_dir_all(&lib).unwrap();
+    fs::copy(&jar, lib.join("app.jar")).unwrap();
+    fs::write(lib.join("readme.txt"), "not a jar").unwrap();
+    let run = jara(&["-cp".as_ref(), lib.join("*").as_os_str(), "Main".as_ref()]);
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: found helper");
+    // without the wildcard the directory holds no classes
+    let run = jara(&["-cp".as_ref(), lib.as_os_str(), "Main".as_ref()]);
+    assert_eq!(run.stderr.lines().next(), Some("Error: Could not find or load main class Main"));
+}
+
+#[test]
+fn class_path_environment_variable_is_the_default() {
+    let classes = match compile("jar_launch") {
+        Some(classes) => classes,
+        None => return
+    };
+    let run = jara_with_env(&["T2", "x"], &[("CLASSPATH", Some(classes.as_os_str()))]);
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: launched with 1 x");
+    // -cp takes precedence over CLASSPATH
+    let run = jara_with_env(&["-cp".as_ref(), classes.as_os_str(), "T2".as_ref(), "y".as_ref()], &[("CLASSPATH", Some("/nonexistent".as_ref()))]);
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: launched with 1 y");
+    // without either the current directory is searched
+    let run = jara_in(&classes, &["T2", "z"], &[("CLASSPATH", None)]);
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: launched with 1 z");
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::{run, uncaught};
+
+#[test]
+fn fields_are_prepared_with_their_defaults_after_the_inherited_ones() {
+    let run = match run("preparation", "Main") {
+        Some(run) => run,
+        None => return
+    };
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: false,true,0.0,0,0,0,0.0,true 3,1,derived,base,42,1");
+}
+
+#[test]
+fn array_classes_are_created_and_as_accessible_as_their_elements() {
+    let run = match run("array_classes", "Main") {
+        Some(run) => run,
+        None => return
+    };
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: [[Ljava.lang.String;,[Ljava.lang.String;,[I,\
+        java.lang.Object,true,true,true,true,true,true,false,true \
+        failed to access class other.Hidden from class Main, failed to access class other.Hidden from class Main");
+}
+
+#[test]
+fn classes_and_primitive_types_have_mirrors() {
+    let run = match run("mirrors", "Main") {
+        Some(run) => run,
+        None => return
+    };
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: true,Main$Square,java.lang.Object,\
+        true,true,false,true,false,true,false,true,true,5 int,void,true,int,true,true,true,false,false java.lang.Object,[LMain$Square;,true,true");
+}
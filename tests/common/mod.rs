@@ -0,0 +1,116 @@
+//! compiles the Java programs under `tests/java` with the javac of the JDK and runs them on jara
+
+// every test crate compiles this module and uses a part of it
+#![allow(dead_code)]
+
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// what a program run by jara printed and how it exited
+pub struct Run {
+    pub stdout: String,
+    pub stderr: String,
+    pub status: Option<i32>
+}
+
+/// a tool of the JDK, e.g. javac
+fn jdk_tool(name: &str) -> Option<PathBuf> {
+    let java_home = env::var_os("JAVA_HOME").filter(|java_home| !java_home.is_empty());
+    let tool = match java_home {
+        Some(java_home) => Path::new(&java_home).join("bin").join(name),
+        None => env::split_paths(&env::var_os("PATH")?).map(|dir| dir.join(name)).find(|tool| tool.is_file())?
+    };
+    Some(tool).filter(|tool| tool.is_file())
+}
+
+/// the class files of `tests/java/<case>`, in a directory of their own. The sources of `tests/java/<case>/recompiled`
+/// are compiled afterwards over the first ones, which makes class files inconsistent as separate compilation does.
+/// `None` without a JDK to compile them.
+pub fn compile(case: &str) -> Option<PathBuf> {
+    let javac = match jdk_tool("javac") {
+        Some(javac) => javac,
+        None => {
+            eprintln!("skipped {}: no javac on JAVA_HOME or PATH", case);
+            return None;
+        }
+    };
+    let sources = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("java").join(case);
+    let classes = env::temp_dir().join(format!("jara-tests-{}", std::process::id())).join(case);
+    let _ = fs::remove_dir_all(&classes);
+    fs::create_dir_all(&classes).unwrap();
+    for dir in [sources.clone(), sources.join("recompiled")].iter().filter(|dir| dir.is_dir()) {
+        let files: Vec<PathBuf> = fs::read_dir(dir).unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "java"))
+            .collect();
+        let output = Command::new(&javac)
+            .args(["-encoding", "UTF-8", "-nowarn", "-d"]).arg(&classes)
+            .arg("-cp").arg(&classes)
+            .args(&files)
+            .output().unwrap();
+        assert!(output.status.success(), "javac failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    Some(classes)
+}
+
+/// the jar of the class files of `tests/java/<case>` with the `manifest` main section, `None` without a JDK
+pub fn jar(case: &str, manifest: &str) -> Option<PathBuf> {
+    let classes = compile(case)?;
+    let jar = classes.with_extension("jar");
+    let manifest_file = classes.with_extension("mf");
+    fs::write(&manifest_file, manifest).unwrap();
+    let output = Command::new(jdk_tool("jar")?)
+        .arg("cfm").arg(&jar).arg(&manifest_file)
+        .arg("-C").arg(&classes).arg(".")
+        .output().unwrap();
+    assert!(output.status.success(), "jar failed: {}", String::from_utf8_lossy(&output.stderr));
+    Some(jar)
+}
+
+/// runs jara with the command line `args`
+pub fn jara<S: AsRef<OsStr>>(args: &[S]) -> Run {
+    jara_with_env(args, &[])
+}
+
+/// runs jara with the command line `args` and the environment `vars` set, or removed if `None`
+pub fn jara_with_env<S: AsRef<OsStr>>(args: &[S], vars: &[(&str, Option<&OsStr>)]) -> Run {
+    jara_in(Path::new("."), args, vars)
+}
+
+/// runs jara as `jara_with_env` does, in the current directory `dir`
+pub fn jara_in<S: AsRef<OsStr>>(dir: &Path, args: &[S], vars: &[(&str, Option<&OsStr>)]) -> Run {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_jara"));
+    command.current_dir(dir);
+    for &(name, value) in vars {
+        match value {
+            Some(value) => command.env(name, value),
+            None => command.env_remove(name)
+        };
+    }
+    let output = command.args(args).output().unwrap();
+    Run {
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        status: output.status.code()
+    }
+}
+
+/// runs `main_class` of `tests/java/<case>` with the extra jara `options`
+pub fn run_with(case: &str, options: &[&str], main_class: &str) -> Option<Run> {
+    let classes = compile(case)?;
+    let mut args: Vec<&OsStr> = options.iter().map(OsStr::new).collect();
+    args.extend([OsStr::new("-cp"), classes.as_os_str(), OsStr::new(main_class)]);
+    Some(jara(&args))
+}
+
+pub fn run(case: &str, main_class: &str) -> Option<Run> {
+    run_with(case, &[], main_class)
+}
+
+/// the first line of the stack trace of the uncaught exception, the way the programs report their results
+pub fn uncaught(run: &Run) -> &str {
+    run.stderr.lines().next().unwrap_or("")
+}
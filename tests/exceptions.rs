@@ -0,0 +1,47 @@
+mod common;
+
+use common::{run, run_with, uncaught};
+
+#[test]
+fn uncaught_exception_prints_its_causes_as_hotspot() {
+    for collector in ["-XX:-UseGenerationalGC", "-XX:+UseGenerationalGC"].iter() {
+        let run = match run_with("cause_chain", &["-Xmx8m", collector], "Main") {
+            Some(run) => run,
+            None => return
+        };
+        assert_eq!(run.stderr, "Exception in thread \"main\" java.lang.IllegalStateException: wrapped\n\
+            \tat Main.wrap(Main.java:27)\n\
+            \tat Main.main(Main.java:32)\n\
+            Caused by: java.lang.ExceptionInInitializerError\n\
+            \tat Main.fail(Main.java:18)\n\
+            \tat Main.fail(Main.java:20)\n\
+            \tat Main.fail(Main.java:20)\n\
+            \tat Main.wrap(Main.java:25)\n\
+            \t... 1 more\n\
+            Caused by: java.lang.ArithmeticException: / by zero\n\
+            \tat Main$Init.divide(Main.java:12)\n\
+            \tat Main$Init.<clinit>(Main.java:5)\n\
+            \t... 5 more\n", "{}", collector);
+        assert_eq!(run.status, Some(1));
+    }
+}
+
+#[test]
+fn handlers_are_searched_while_unwinding_frames() {
+    let run = match run("unwinding", "Main") {
+        Some(run) => run,
+        None => return
+    };
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: \
+        finally1 finally2 inner caught deep rethrown redeep handled -1");
+    assert_eq!(run.status, Some(1));
+}
+
+#[test]
+fn synchronized_on_null_throws_null_pointer_exception() {
+    let run = match run("monitors", "Main") {
+        Some(run) => run,
+        None => return
+    };
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: count 101");
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use common::{run_with, uncaught};
+
+#[test]
+fn arguments_survive_a_collection_in_the_static_initializer() {
+    for collector in ["-XX:-UseGenerationalGC", "-XX:+UseGenerationalGC"].iter() {
+        let run = match run_with("clinit_gc", &["-Xmx8m", collector], "Main") {
+            Some(run) => run,
+            None => return
+        };
+        assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: len=2 a0=7 a1=8, len=2 a0=9 a1=10", "{}", collector);
+    }
+}
+
+#[test]
+fn out_of_memory_error_has_a_stack_trace() {
+    for collector in ["-XX:-UseGenerationalGC", "-XX:+UseGenerationalGC"].iter() {
+        let run = match run_with("out_of_memory", &["-Xmx8m", collector], "Main") {
+            Some(run) => run,
+            None => return
+        };
+        assert_eq!(run.stderr, "Exception in thread \"main\" java.lang.OutOfMemoryError: Java heap space\n\
+            \tat Main.fill(Main.java:10)\n\
+            \tat Main.main(Main.java:25)\n", "{}", collector);
+        assert_eq!(run.status, Some(1));
+    }
+}
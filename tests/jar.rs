@@ -0,0 +1,22 @@
+mod common;
+
+use common::{jar, jara, uncaught};
+
+#[test]
+fn main_class_of_the_manifest_is_launched() {
+    // the attribute names are case-insensitive
+    let jar = match jar("jar_launch", "Manifest-Version: 1.0\nmain-class: T2\n") {
+        Some(jar) => jar,
+        None => return
+    };
+    let run = jara(&["-jar".as_ref(), jar.as_os_str(), "x".as_ref()]);
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: launched with 1 x");
+    assert_eq!(run.status, Some(1));
+}
+
+#[test]
+fn missing_jar_is_reported() {
+    let run = jara(&["-jar", "/tmp/nope.jar"]);
+    assert_eq!(run.stderr, "Error: Unable to access jarfile /tmp/nope.jar\n");
+    assert_eq!(run.status, Some(1));
+}
@@ -0,0 +1,29 @@
+public class Main {
+    static Object hiddenArray() {
+        return new other.Hidden[1];
+    }
+
+    static Object hidden() {
+        return new other.Hidden();
+    }
+
+    static String access(java.util.function.Supplier<Object> code) {
+        try {
+            return code.get().toString();
+        } catch (IllegalAccessError e) {
+            return e.getMessage();
+        }
+    }
+
+    public static void main(String[] args) {
+        String[][] strings = new String[2][];
+        int[] ints = new int[0];
+        Object array = strings;
+        Class<?> type = strings.getClass();
+        String classes = type.getName() + "," + type.getComponentType().getName() + "," + ints.getClass().getName()
+            + "," + type.getSuperclass().getName() + "," + (type == String[][].class) + "," + (type.getComponentType() == String[].class)
+            + "," + (array instanceof Cloneable) + "," + (array instanceof java.io.Serializable) + "," + (array instanceof Object[])
+            + "," + (array instanceof CharSequence[][]) + "," + (array instanceof Integer[][]) + "," + type.isArray();
+        throw new RuntimeException(classes + " " + access(Main::hiddenArray) + ", " + access(Main::hidden));
+    }
+}
@@ -0,0 +1,4 @@
+package other;
+
+class Hidden {
+}
@@ -0,0 +1,24 @@
+public class Main {
+    static String caught(Runnable code) {
+        try {
+            code.run();
+            return "none";
+        } catch (RuntimeException e) {
+            return e.getClass().getName() + ": " + e.getMessage();
+        }
+    }
+
+    public static void main(String[] args) {
+        int[] ints = new int[3];
+        long[] longs = new long[0];
+        Object[] objects = new String[2];
+        int[] size = {-1, 2};
+        String results = caught(() -> ints[3] = 1) + "\n" + caught(() -> ints[-1]++) + "\n" + caught(() -> longs[0]++)
+            + "\n" + caught(() -> objects[0] = ints) + "\n" + caught(() -> objects[2] = "x")
+            + "\n" + caught(() -> { Object[] fine = objects; fine[1] = "y"; fine[0] = null; })
+            + "\n" + caught(() -> { int[] a = new int[size[0]]; })
+            + "\n" + caught(() -> { int[][] a = new int[size[1]][size[0]]; })
+            + "\n" + caught(() -> { Object[] a = new Object[size[0]]; });
+        throw new RuntimeException(results);
+    }
+}
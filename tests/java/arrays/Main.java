@@ -0,0 +1,27 @@
+public class Main {
+    public static void main(String[] args) {
+        boolean[] z = {true, false};
+        byte[] b = {(byte) 0x80, 0x7f};
+        char[] c = {'\uffff', 'a'};
+        short[] s = {(short) 0x8000, 1};
+        int[] i = {Integer.MIN_VALUE, 2};
+        long[] l = {Long.MIN_VALUE, 3};
+        float[] f = {1.5f, -0.0f};
+        double[] d = {Double.MAX_VALUE, 4};
+        b[1]++;
+        c[1] += 1;
+        String widths = z[0] + "," + z[1] + " " + b[0] + "," + b[1] + " " + (int) c[0] + "," + c[1] + " " + s[0] + "," + s[1]
+            + " " + i[0] + "," + i[1] + " " + l[0] + "," + l[1] + " " + f[0] + "," + f[1] + " " + d[0] + "," + d[1];
+
+        int[][][] cube = new int[2][3][4];
+        cube[1][2][3] = 7;
+        long[][] partial = new long[3][];
+        String[][] strings = new String[2][1];
+        strings[1][0] = "x";
+        Object[] objects = new Integer[0][];
+        String multi = cube.length + "," + cube[1].length + "," + cube[1][2].length + "," + cube[1][2][3] + "," + cube[0][2][3]
+            + " " + partial.length + "," + (partial[2] == null) + " " + strings[1][0] + "," + strings[0][0]
+            + " " + (objects instanceof Integer[][]) + "," + (cube[0] instanceof int[][]) + "," + (cube[0] != cube[1]);
+        throw new RuntimeException(widths + " | " + multi);
+    }
+}
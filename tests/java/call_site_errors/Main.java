@@ -0,0 +1,20 @@
+public class Main {
+    // the methods of a record are linked by ObjectMethods.bootstrap, which the VM does not support
+    record Point(int x, int y) {}
+
+    static String hash(Point point) {
+        try {
+            return "hash " + point.hashCode();
+        } catch (BootstrapMethodError e) {
+            return e.getMessage();
+        }
+    }
+
+    public static void main(String[] args) {
+        Point point = new Point(1, 2);
+        String first = hash(point);
+        // the same invokedynamic instruction fails again with the same error
+        String second = hash(point);
+        throw new RuntimeException(first.equals(second) + " " + second);
+    }
+}
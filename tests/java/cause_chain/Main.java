@@ -0,0 +1,34 @@
+public class Main {
+    static Object garbage;
+
+    static class Init {
+        static int value = divide(1, 0);
+
+        static int divide(int a, int b) {
+            // the ExceptionInInitializerError wrapping the ArithmeticException is created on a full heap
+            for (int i = 0; i < 100000; i++) {
+                garbage = new long[8];
+            }
+            return a / b;
+        }
+    }
+
+    static int fail(int depth) {
+        if (depth == 0) {
+            return Init.value;
+        }
+        return fail(depth - 1);
+    }
+
+    static void wrap() {
+        try {
+            fail(2);
+        } catch (ExceptionInInitializerError e) {
+            throw new IllegalStateException("wrapped", e);
+        }
+    }
+
+    public static void main(String[] args) {
+        wrap();
+    }
+}
@@ -0,0 +1,9 @@
+public class Main {
+    public static void main(String[] args) {
+        throw new RuntimeException("found " + Helper.NAME);
+    }
+}
+
+class Helper {
+    static String NAME = "helper";
+}
@@ -0,0 +1,37 @@
+import java.util.function.Function;
+
+public class Main {
+    static class Holder {
+        static Object last;
+
+        static {
+            for (int i = 0; i < 200000; i++) {
+                last = new long[8];
+            }
+        }
+
+        static String use(int[] array) {
+            return "len=" + array.length + " a0=" + array[0] + " a1=" + array[1];
+        }
+    }
+
+    // initialized by the first call through the method reference
+    static class Referenced {
+        static Object last;
+
+        static {
+            for (int i = 0; i < 200000; i++) {
+                last = new long[8];
+            }
+        }
+
+        static String use(int[] array) {
+            return "len=" + array.length + " a0=" + array[0] + " a1=" + array[1];
+        }
+    }
+
+    public static void main(String[] args) {
+        Function<int[], String> reference = Referenced::use;
+        throw new RuntimeException(Holder.use(new int[]{7, 8}) + ", " + reference.apply(new int[]{9, 10}));
+    }
+}
@@ -0,0 +1,9 @@
+package other;
+
+// the fields are public when Main and Sub are compiled against them
+public class Fields {
+    public static int secret = 7;
+    public static int pkg = 3;
+    public static int prot = 4;
+    public int instance = 5;
+}
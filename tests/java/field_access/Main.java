@@ -0,0 +1,27 @@
+public class Main {
+    static class Nestmate {
+        private static int hidden = 9;
+    }
+
+    interface Access {
+        int get();
+    }
+
+    static String access(Access access) {
+        try {
+            return String.valueOf(access.get());
+        } catch (IllegalAccessError e) {
+            return e.getMessage();
+        }
+    }
+
+    public static void main(String[] args) {
+        String result = access(() -> other.Fields.secret)
+            + "; " + access(() -> other.Fields.pkg)
+            + "; " + access(() -> other.Fields.prot)
+            + "; " + access(() -> new other.Fields().instance)
+            + "; " + access(Sub::prot)
+            + "; " + access(() -> Nestmate.hidden);
+        throw new RuntimeException(result);
+    }
+}
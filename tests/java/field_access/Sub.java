@@ -0,0 +1,6 @@
+// a subclass in another package may access the protected fields
+public class Sub extends other.Fields {
+    static int prot() {
+        return prot;
+    }
+}
@@ -0,0 +1,8 @@
+package other;
+
+public class Fields {
+    private static int secret = 7;
+    static int pkg = 3;
+    protected static int prot = 4;
+    private int instance = 5;
+}
@@ -0,0 +1,5 @@
+public class T2 {
+    public static void main(String[] args) {
+        throw new RuntimeException("launched with " + args.length + " " + args[0]);
+    }
+}
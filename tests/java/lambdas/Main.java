@@ -0,0 +1,64 @@
+import java.util.function.Function;
+import java.util.function.IntBinaryOperator;
+import java.util.function.IntFunction;
+import java.util.function.IntSupplier;
+import java.util.function.IntUnaryOperator;
+import java.util.function.Supplier;
+import java.util.function.ToIntFunction;
+
+public class Main {
+    interface Shape {
+        int area();
+    }
+
+    static class Square implements Shape {
+        final int side;
+
+        Square(int side) {
+            this.side = side;
+        }
+
+        public int area() {
+            return side * side;
+        }
+    }
+
+    static int twice(int x) {
+        return 2 * x;
+    }
+
+    static IntSupplier counter(int start) {
+        int[] count = {start};
+        return () -> count[0]++;
+    }
+
+    static Runnable nonCapturing() {
+        return () -> {};
+    }
+
+    public static void main(String[] args) {
+        IntBinaryOperator add = (a, b) -> a + b;
+        int offset = 10;
+        IntUnaryOperator plus = x -> x + offset;
+        String prefix = "<";
+        Function<String, String> wrap = s -> prefix + s + ">";
+        IntSupplier counter = counter(5);
+        counter.getAsInt();
+        IntUnaryOperator staticRef = Main::twice;
+        ToIntFunction<String> virtualRef = String::length;
+        ToIntFunction<CharSequence> interfaceRef = CharSequence::length;
+        ToIntFunction<Shape> shapeRef = Shape::area;
+        IntFunction<Square> constructorRef = Square::new;
+        Supplier<StringBuilder> builder = StringBuilder::new;
+        // the call sites of counter and nonCapturing are linked once, each call captures again
+        int sum = 0;
+        for (int i = 0; i < 3; i++) {
+            sum += counter(i).getAsInt();
+        }
+        throw new RuntimeException("add=" + add.applyAsInt(2, 3) + " plus=" + plus.applyAsInt(1)
+            + " wrap=" + wrap.apply("x") + " counter=" + counter.getAsInt() + " static=" + staticRef.applyAsInt(21)
+            + " virtual=" + virtualRef.applyAsInt("four") + " interface=" + interfaceRef.applyAsInt(builder.get().append("abc"))
+            + " shape=" + shapeRef.applyAsInt(constructorRef.apply(3))
+            + " sum=" + sum + " same=" + (nonCapturing() == nonCapturing()) + " distinct=" + (counter(0) != counter(0)));
+    }
+}
@@ -0,0 +1,26 @@
+import java.io.Serializable;
+
+public class Main {
+    interface Shape {
+    }
+
+    static class Square implements Shape, Serializable {
+        static int count = 5;
+    }
+
+    public static void main(String[] args) {
+        Square square = new Square();
+        Class<?> type = square.getClass();
+        String mirrors = (type == Square.class) + "," + type.getName() + "," + type.getSuperclass().getName()
+            + "," + Square.class.isInstance(square) + "," + Shape.class.isInstance(square) + "," + String.class.isInstance(square)
+            + "," + Shape.class.isAssignableFrom(type) + "," + type.isAssignableFrom(Shape.class)
+            + "," + Shape.class.isInterface() + "," + type.isInterface() + "," + (Shape.class.getSuperclass() == null)
+            + "," + Serializable.class.isAssignableFrom(type) + "," + Square.count;
+        String primitives = int.class.getName() + "," + void.class.getName() + "," + int.class.isPrimitive() + ","
+            + Integer.TYPE.getName() + "," + (int.class == Integer.TYPE) + "," + (int[].class.getComponentType() == int.class)
+            + "," + (int.class.getSuperclass() == null) + "," + long.class.isInstance(square) + "," + type.isPrimitive();
+        String names = Object.class.getName() + "," + Square[].class.getName() + "," + (Main.class.getClass() == Class.class)
+            + "," + (Class.class.getClass() == Class.class);
+        throw new RuntimeException(mirrors + " " + primitives + " " + names);
+    }
+}
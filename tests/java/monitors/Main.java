@@ -0,0 +1,18 @@
+public class Main {
+    static Object lock;
+
+    public static void main(String[] args) {
+        int count = 0;
+        synchronized (Main.class) {
+            count++;
+        }
+        try {
+            synchronized (lock) {
+                count += 10;
+            }
+        } catch (NullPointerException e) {
+            count += 100;
+        }
+        throw new RuntimeException("count " + count);
+    }
+}
@@ -0,0 +1,27 @@
+public class Main {
+    // every array links to the one allocated before
+    static Object[] retained;
+    static int filled;
+
+    static void fill() {
+        while (true) {
+            Object[] link = new Object[2];
+            link[0] = retained;
+            link[1] = new long[16 * 1024];
+            retained = link;
+            filled++;
+        }
+    }
+
+    public static void main(String[] args) {
+        try {
+            fill();
+        } catch (OutOfMemoryError e) {
+            retained = null;
+            if (!"Java heap space".equals(e.getMessage()) || filled < 10) {
+                throw new RuntimeException("unexpected " + e.getMessage() + " after " + filled + " arrays");
+            }
+        }
+        fill();
+    }
+}
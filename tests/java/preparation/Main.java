@@ -0,0 +1,33 @@
+public class Main {
+    static class Base {
+        static int count;
+        int x = 1;
+        long wide = 2;
+        String name = "base";
+    }
+
+    static class Derived extends Base {
+        static boolean flag;
+        static Object object;
+        static double real;
+        int x = 3;
+        byte b;
+        char c;
+        short s;
+        float f;
+        Object o;
+        String name = "derived";
+    }
+
+    public static void main(String[] args) {
+        Derived derived = new Derived();
+        Base base = derived;
+        Base.count++;
+        derived.wide += 40;
+        String defaults = Derived.flag + "," + (Derived.object == null) + "," + Derived.real + "," + derived.b + ","
+            + (int) derived.c + "," + derived.s + "," + derived.f + "," + (derived.o == null);
+        // the fields of the superclass are kept apart from the ones of the same names of the subclass
+        String inherited = derived.x + "," + base.x + "," + derived.name + "," + base.name + "," + derived.wide + "," + Derived.count;
+        throw new RuntimeException(defaults + " " + inherited);
+    }
+}
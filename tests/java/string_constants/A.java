@@ -0,0 +1,10 @@
+// Main is compiled against fields which are not constants, it reads them with getstatic rather than inlining them
+public class A {
+    public static String X;
+    public static String NUL;
+    public static String SUPPLEMENTARY;
+
+    static String literal() {
+        return "a\0b😀";
+    }
+}
@@ -0,0 +1,14 @@
+public class Main {
+    public static void main(String[] args) {
+        String result = "X=" + A.X
+            + " constant=" + (A.X == "hello")
+            + " literal=" + (A.literal() == "a\0b😀")
+            + " nul=" + A.NUL.length() + "," + (int) A.NUL.charAt(1) + "," + (A.NUL == "a\0b")
+            + " supplementary=" + A.SUPPLEMENTARY.length() + "," + A.SUPPLEMENTARY.codePointAt(0) + "," + (A.SUPPLEMENTARY == "😀")
+            + " interned=" + (new String("hello").intern() == "hello")
+            // interned before the literal is loaded, the literal is the interned string
+            + " internedFirst=" + (("fresh" + args.length).intern() == "fresh0")
+            + " new=" + (new String("hello") == "hello");
+        throw new RuntimeException(result);
+    }
+}
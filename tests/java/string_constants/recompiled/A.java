@@ -0,0 +1,10 @@
+// the fields become constants, initialized by their ConstantValue attributes
+public class A {
+    public static final String X = "hello";
+    public static final String NUL = "a\0b";
+    public static final String SUPPLEMENTARY = "😀";
+
+    static String literal() {
+        return "a\0b😀";
+    }
+}
@@ -0,0 +1,54 @@
+public class Main {
+    static String trace = "";
+
+    static void thrower(int depth) {
+        if (depth == 0) {
+            throw new IllegalStateException("deep");
+        }
+        try {
+            thrower(depth - 1);
+        } finally {
+            trace += "finally" + depth + " ";
+        }
+    }
+
+    static void catcher() {
+        try {
+            try {
+                thrower(2);
+            } catch (IllegalArgumentException e) {
+                trace += "wrong ";
+            } finally {
+                trace += "inner ";
+            }
+        } catch (RuntimeException e) {
+            // a handler of a superclass catches, the exception is rethrown to the caller
+            trace += "caught " + e.getMessage() + " ";
+            throw new UnsupportedOperationException("re" + e.getMessage());
+        }
+    }
+
+    static int divide(int a, int b) {
+        return a / b;
+    }
+
+    static int handled() {
+        int x = 1;
+        try {
+            // x is on the operand stack when divide throws
+            return x + divide(x, 0);
+        } catch (ArithmeticException e) {
+            return -x;
+        }
+    }
+
+    public static void main(String[] args) {
+        try {
+            catcher();
+        } catch (UnsupportedOperationException e) {
+            trace += "rethrown " + e.getMessage() + " ";
+        }
+        trace += "handled " + handled();
+        throw new RuntimeException(trace);
+    }
+}
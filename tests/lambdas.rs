@@ -0,0 +1,27 @@
+mod common;
+
+use common::{run, run_with, uncaught};
+
+#[test]
+fn lambdas_and_method_references_are_linked_once() {
+    for collector in [&[][..], &["-XX:+UseGenerationalGC"]] {
+        let run = match run_with("lambdas", collector, "Main") {
+            Some(run) => run,
+            None => return
+        };
+        assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: add=5 plus=11 wrap=<x> counter=6 \
+            static=42 virtual=4 interface=3 shape=9 sum=3 same=true distinct=true");
+    }
+}
+
+#[test]
+fn failed_call_site_throws_the_same_error_again() {
+    let run = match run("call_site_errors", "Main") {
+        Some(run) => run,
+        None => return
+    };
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: true bootstrap method \
+        java.lang.runtime.ObjectMethods.bootstrap(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;\
+        Ljava/lang/invoke/TypeDescriptor;Ljava/lang/Class;Ljava/lang/String;[Ljava/lang/invoke/MethodHandle;)Ljava/lang/Object; \
+        is not supported");
+}
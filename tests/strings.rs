@@ -0,0 +1,13 @@
+mod common;
+
+use common::{run, uncaught};
+
+#[test]
+fn string_constants_and_literals_are_interned() {
+    let run = match run("string_constants", "Main") {
+        Some(run) => run,
+        None => return
+    };
+    assert_eq!(uncaught(&run), "Exception in thread \"main\" java.lang.RuntimeException: X=hello constant=true literal=true \
+        nul=3,0,true supplementary=2,128512,true interned=true internedFirst=true new=false");
+}